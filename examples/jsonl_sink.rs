@@ -0,0 +1,51 @@
+//! Example sink plugin: appends every saved sample to a JSON Lines file,
+//! tagged with its task name.
+//!
+//! Build with `cargo build --example jsonl_sink` and register it in
+//! `config.json`:
+//!
+//! ```json
+//! { "plugins": [
+//!     { "name": "archive", "kind": "sink",
+//!       "command": ["/path/to/target/debug/examples/jsonl_sink", "/path/to/archive.jsonl"] }
+//! ] }
+//! ```
+
+use std::env;
+use std::fs::OpenOptions;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::process;
+
+use serde_json::Value;
+
+fn main() {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: jsonl_sink <output.jsonl>");
+        process::exit(2);
+    };
+    if let Err(e) = run(&path) {
+        eprintln!("jsonl_sink: {}", e);
+        process::exit(1);
+    }
+}
+
+fn run(path: &str) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut out = BufWriter::new(file);
+    let mut task = Value::Null;
+
+    for line in io::stdin().lock().lines() {
+        let mut event: Value = serde_json::from_str(&line?)?;
+        match event["event"].as_str() {
+            Some("start") => task = event["task"].take(),
+            Some("record") => {
+                let mut record = event["record"].take();
+                record["task"] = task.clone();
+                writeln!(out, "{}", record)?;
+            }
+            _ => {}
+        }
+    }
+
+    out.flush()
+}
@@ -0,0 +1,97 @@
+# English GUI text; also used for anything missing from another language.
+
+app-title = Activity Tracker
+macos-permission-note = Note: On macOS, you may need to grant permission for input monitoring in System Preferences → Security & Privacy → Privacy → Input Monitoring
+macos-inputs-note = ⚠️ Note: If inputs aren't recording, check macOS privacy settings.
+
+## Task form
+
+task-name = Task Name:
+recent = Recent
+suggestion = Suggestion: { $task }
+saved-as = Saved as { $name }
+project = Project:
+estimate = Estimate:
+estimate-hint = e.g. 45m or 1h30m
+notes = Notes:
+create-task = Create Task
+start-preset = Start preset { $name }
+start-preset-hint = Start from this preset
+cancel = Cancel
+end-task = End Task
+compact = Mini window
+compact-hint = Keep only a small always-on-top window while recording; closing the window does the same
+expand = Show the full window
+keyboard-help = Tab moves between controls · Enter starts or ends the task · Esc cancels the countdown
+task-suggestion = No task name was given. { $task }?
+accept = Use this name
+dismiss = Keep "Untitled"
+
+## Status
+
+countdown = Recording will start in { $seconds } seconds...
+recording = Recording in progress...
+paused = Recording paused ({ $reason }).
+pause-sleep = system sleep
+pause-screen-lock = screen locked
+pause-private-window = private window
+pause-excluded-app = excluded app
+elapsed = Elapsed { $elapsed } of { $estimate } estimated
+file-size = Estimated file size: { $size } (about { $per_hour } per hour)
+file-size-at-estimate = About { $size } by the estimated end
+sample-count = { $samples } records captured · { $rate } samples/sec
+capture-stalled = No samples for a few seconds; capture may have stalled. See Diagnostics.
+sparkline = Activity over the last 5 minutes
+
+## Spoken announcements
+
+speech-started = Recording started for task { $task }
+speech-stopped = Recording stopped for task { $task }
+
+## Diagnostics
+
+diagnostics = Diagnostics
+backend = Backend
+last-sample = Last sample
+never = never
+seconds-ago = { $seconds } s ago
+sample-rate = Samples/sec
+rate-of-target = { $rate } of { $target }
+samples = Samples
+dropped = Dropped
+tick-work = Work per tick
+write-latency = Write latency
+view-log = View Log
+text-size = Text size { $percent }%
+zoom-in = Larger text
+zoom-out = Smaller text
+
+## Saving
+
+session-not-saved = Session not saved: { $error }
+unsaved-kept = The recorded data is kept until you discard it or start another task.
+retry = Retry
+save = Save
+discard = Discard
+choose-folder-title = Where should sessions be saved?
+no-output-folder = No Downloads, data or home folder was found to save the session in.
+enter-folder = Enter a folder; it is created if it does not exist.
+always-save-here = Always save sessions here
+folder = Folder:
+folder-hint = default output folder
+export = Export
+resample-every = Resample every
+seconds = { $seconds } s
+export-resampled = Save resampled CSV
+range-from = From
+range-to = to
+export-trimmed = Save copy of this range
+redact-range = Redact this range
+redact-range-hint = Blanks keys, clicks, pointer position and app names in this range of the saved file
+redact-confirm = Blank this range in { $path }? The original is kept beside it as a .bak file.
+redacted = Redacted { $path }; the original is kept as { $backup }
+exported-to = Exported to { $path }
+phone-view = Phone view
+phone-view-hint = Scan to watch this recording live on another device.
+phone-view-qr = QR code of the dashboard link
+dashboard-local-only = The dashboard only accepts connections from this computer; set its bind address to 0.0.0.0 to reach it from a phone.
@@ -0,0 +1,97 @@
+# Textos de la interfaz en español.
+
+app-title = Activity Tracker
+macos-permission-note = Nota: en macOS puede que tengas que conceder permiso de monitorización de entrada en Preferencias del Sistema → Seguridad y privacidad → Privacidad → Monitorización de entrada
+macos-inputs-note = ⚠️ Nota: si no se registran las entradas, revisa los ajustes de privacidad de macOS.
+
+## Formulario de tarea
+
+task-name = Nombre de la tarea:
+recent = Recientes
+suggestion = Sugerencia: { $task }
+saved-as = Se guardará como { $name }
+project = Proyecto:
+estimate = Estimación:
+estimate-hint = p. ej. 45m o 1h30m
+notes = Notas:
+create-task = Crear tarea
+start-preset = Iniciar plantilla { $name }
+start-preset-hint = Empezar con esta plantilla
+cancel = Cancelar
+end-task = Terminar tarea
+compact = Ventana mini
+compact-hint = Dejar solo una ventana pequeña siempre visible mientras se graba; cerrar la ventana hace lo mismo
+expand = Mostrar la ventana completa
+keyboard-help = Tab cambia de control · Intro inicia o termina la tarea · Esc cancela la cuenta atrás
+task-suggestion = No se indicó un nombre de tarea. ¿{ $task }?
+accept = Usar este nombre
+dismiss = Mantener "Untitled"
+
+## Estado
+
+countdown = La grabación empezará en { $seconds } segundos...
+recording = Grabación en curso...
+paused = Grabación en pausa ({ $reason }).
+pause-sleep = suspensión del sistema
+pause-screen-lock = pantalla bloqueada
+pause-private-window = ventana privada
+pause-excluded-app = aplicación excluida
+elapsed = { $elapsed } transcurridos de { $estimate } estimados
+file-size = Tamaño estimado del archivo: { $size } (unos { $per_hour } por hora)
+file-size-at-estimate = Unos { $size } al final estimado
+sample-count = { $samples } registros capturados · { $rate } muestras/s
+capture-stalled = Sin muestras desde hace unos segundos; puede que la captura se haya detenido. Consulta Diagnóstico.
+sparkline = Actividad de los últimos 5 minutos
+
+## Avisos hablados
+
+speech-started = Grabación iniciada para la tarea { $task }
+speech-stopped = Grabación detenida para la tarea { $task }
+
+## Diagnóstico
+
+diagnostics = Diagnóstico
+backend = Backend
+last-sample = Última muestra
+never = nunca
+seconds-ago = hace { $seconds } s
+sample-rate = Muestras/s
+rate-of-target = { $rate } de { $target }
+samples = Muestras
+dropped = Descartadas
+tick-work = Trabajo por ciclo
+write-latency = Latencia de escritura
+view-log = Ver registro
+text-size = Tamaño del texto { $percent }%
+zoom-in = Texto más grande
+zoom-out = Texto más pequeño
+
+## Guardado
+
+session-not-saved = No se guardó la sesión: { $error }
+unsaved-kept = Los datos grabados se conservan hasta que los descartes o empieces otra tarea.
+retry = Reintentar
+save = Guardar
+discard = Descartar
+choose-folder-title = ¿Dónde se deben guardar las sesiones?
+no-output-folder = No se encontró ninguna carpeta de descargas, de datos ni personal donde guardar la sesión.
+enter-folder = Indica una carpeta; se creará si no existe.
+always-save-here = Guardar siempre las sesiones aquí
+folder = Carpeta:
+folder-hint = carpeta de salida predeterminada
+export = Exportar
+resample-every = Remuestrear cada
+seconds = { $seconds } s
+export-resampled = Guardar CSV remuestreado
+range-from = Desde
+range-to = hasta
+export-trimmed = Guardar copia de este tramo
+redact-range = Censurar este tramo
+redact-range-hint = Borra teclas, clics, posición del puntero y nombres de aplicaciones en este tramo del archivo guardado
+redact-confirm = ¿Borrar este tramo en { $path }? El original se conserva al lado como archivo .bak.
+redacted = Censurado { $path }; el original se conserva como { $backup }
+exported-to = Exportado a { $path }
+phone-view = Vista en el móvil
+phone-view-hint = Escanea para ver esta grabación en directo desde otro dispositivo.
+phone-view-qr = Código QR del enlace al panel
+dashboard-local-only = El panel solo acepta conexiones desde este ordenador; cambia su dirección a 0.0.0.0 para abrirlo desde un móvil.
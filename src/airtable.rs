@@ -0,0 +1,118 @@
+//! Airtable sink, configured under `airtable` in the settings file:
+//!
+//! ```json
+//! "airtable": {
+//!     "token": "pat...",
+//!     "base_id": "app...",
+//!     "table": "Time tracking",
+//!     "fields": { "project": "Client", "clicks": null }
+//! }
+//! ```
+//!
+//! Each saved session becomes a record in `table`. `fields` names the table
+//! field for each value; a field set to `null` is left out. Values are sent
+//! with typecasting on, so new projects and tags are added as select
+//! options. The token needs the `data.records:write` scope.
+
+use serde::Deserialize;
+use serde_json::{Map, json};
+
+use crate::analysis;
+use crate::http;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+const API_URL: &str = "https://api.airtable.com/v0";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AirtableConfig {
+    /// Personal access token.
+    pub token: String,
+    pub base_id: String,
+    /// Table name or ID.
+    pub table: String,
+    pub fields: AirtableFields,
+}
+
+impl Default for AirtableConfig {
+    fn default() -> Self {
+        AirtableConfig {
+            token: String::new(),
+            base_id: String::new(),
+            table: "Sessions".to_string(),
+            fields: AirtableFields::default(),
+        }
+    }
+}
+
+/// Table field for each session value.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AirtableFields {
+    pub task: String,
+    pub project: Option<String>,
+    pub tags: Option<String>,
+    /// Date fields with time.
+    pub start: Option<String>,
+    pub end: Option<String>,
+    /// Duration field; tracked time in seconds.
+    pub duration: Option<String>,
+    pub key_presses: Option<String>,
+    pub clicks: Option<String>,
+    /// Percent field; idle share as a fraction.
+    pub idle: Option<String>,
+}
+
+impl Default for AirtableFields {
+    fn default() -> Self {
+        AirtableFields {
+            task: "Task".to_string(),
+            project: Some("Project".to_string()),
+            tags: Some("Tags".to_string()),
+            start: Some("Start".to_string()),
+            end: Some("End".to_string()),
+            duration: Some("Duration".to_string()),
+            key_presses: Some("Key presses".to_string()),
+            clicks: Some("Clicks".to_string()),
+            idle: Some("Idle".to_string()),
+        }
+    }
+}
+
+impl AirtableConfig {
+    pub fn add_record(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let time = |timestamp: u64| {
+            chrono::DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default().to_rfc3339()
+        };
+        let names = &self.fields;
+        let mut fields = Map::new();
+        fields.insert(names.task.clone(), json!(meta.task));
+        let values = [
+            (&names.project, meta.project.as_ref().map(|project| json!(project))),
+            (&names.tags, (!meta.tags.is_empty()).then(|| json!(meta.tags))),
+            (&names.start, Some(json!(time(summary.start)))),
+            (&names.end, Some(json!(time(summary.end)))),
+            (&names.duration, Some(json!(summary.tracked_secs()))),
+            (&names.key_presses, Some(json!(summary.key_presses))),
+            (&names.clicks, Some(json!(summary.clicks))),
+            (&names.idle, Some(json!(summary.idle_ratio()))),
+        ];
+        for (name, value) in values {
+            if let (Some(name), Some(value)) = (name, value) {
+                fields.insert(name.clone(), value);
+            }
+        }
+
+        let body = json!({ "records": [{ "fields": fields }], "typecast": true });
+        let url = format!("{}/{}/{}", API_URL, http::encode(&self.base_id), http::encode(&self.table));
+        let authorization = format!("Bearer {}", self.token);
+        let headers = [("Authorization", authorization.as_str()), ("Content-Type", "application/json")];
+        outbox::deliver("Airtable", "POST", &url, &headers, body.to_string().as_bytes())
+    }
+}
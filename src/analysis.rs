@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use device_query::Keycode;
+
+use crate::recorder::{ActivityRecord, GapReason};
+use crate::storage::SessionMeta;
+
+/// A click within this many samples of the last pointer movement ends that
+/// movement; later ones are clicks on the spot the pointer rests on.
+const AIM_SAMPLES: usize = 30;
+/// Steps of at most this many pixels in the last second before a click are
+/// micro-corrections.
+const CORRECTION_PX: f64 = 5.0;
+const CORRECTION_SAMPLES: usize = 10;
+
+/// Headline statistics for one recorded session.
+pub struct Summary {
+    pub samples: usize,
+    pub start: u64,
+    pub end: u64,
+    pub key_presses: usize,
+    pub clicks: usize,
+    pub mouse_distance: f64,
+    pub idle_samples: usize,
+    pub markers: Vec<(u64, String)>,
+    /// Stretches without capture, as `(start, end, reason)`.
+    pub gaps: Vec<(u64, u64, GapReason)>,
+    /// Clicks that ended a pointer movement, and for those the summed time
+    /// the pointer rested before the click and the small adjustments made
+    /// just before it.
+    pub aimed_clicks: usize,
+    pub settle_samples: usize,
+    pub corrections: usize,
+}
+
+impl Summary {
+    pub fn duration_secs(&self) -> u64 {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn gap_secs(&self) -> u64 {
+        self.gaps.iter().map(|(start, end, _)| end.saturating_sub(*start)).sum()
+    }
+
+    /// Time actually recorded: the session's span without its gaps.
+    pub fn tracked_secs(&self) -> u64 {
+        self.duration_secs().saturating_sub(self.gap_secs())
+    }
+
+    /// Mean time between the pointer coming to rest and the click, for
+    /// samples `interval` apart.
+    pub fn mean_settle_secs(&self, interval: Duration) -> Option<f64> {
+        (self.aimed_clicks > 0).then(|| self.settle_samples as f64 * interval.as_secs_f64() / self.aimed_clicks as f64)
+    }
+
+    pub fn corrections_per_click(&self) -> Option<f64> {
+        (self.aimed_clicks > 0).then(|| self.corrections as f64 / self.aimed_clicks as f64)
+    }
+
+    pub fn idle_ratio(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.idle_samples as f64 / self.samples as f64
+        }
+    }
+}
+
+pub fn summarize(records: &[ActivityRecord]) -> Summary {
+    let mut summary = Summary {
+        samples: records.len(),
+        start: records.first().map_or(0, |r| r.timestamp),
+        end: records.last().map_or(0, |r| r.timestamp),
+        key_presses: 0,
+        clicks: 0,
+        mouse_distance: 0.0,
+        idle_samples: 0,
+        markers: Vec::new(),
+        gaps: Vec::new(),
+        aimed_clicks: 0,
+        settle_samples: 0,
+        corrections: 0,
+    };
+
+    // Sample indices of the last movement and of recent small ones
+    let mut last_move: Option<usize> = None;
+    let mut small_moves: Vec<usize> = Vec::new();
+
+    let mut previous: Option<&ActivityRecord> = None;
+    for (i, record) in records.iter().enumerate() {
+        if record.gap.is_some() {
+            last_move = None;
+            small_moves.clear();
+        }
+        summary.key_presses += new_key_presses(previous, record);
+
+        let moved = match previous {
+            Some(p) => {
+                let dx = (record.mouse_x - p.mouse_x) as f64;
+                let dy = (record.mouse_y - p.mouse_y) as f64;
+                let step = (dx * dx + dy * dy).sqrt();
+                summary.mouse_distance += step;
+                if step > 0.0 {
+                    last_move = Some(i);
+                    if step <= CORRECTION_PX {
+                        small_moves.push(i);
+                    }
+                }
+                step > 0.0
+            }
+            None => false,
+        };
+        small_moves.retain(|&j| i - j < CORRECTION_SAMPLES);
+
+        let clicks = new_clicks(previous, record);
+        summary.clicks += clicks;
+        if clicks > 0 {
+            if let Some(j) = last_move.filter(|&j| i - j <= AIM_SAMPLES) {
+                summary.aimed_clicks += 1;
+                summary.settle_samples += i - j;
+                summary.corrections += small_moves.len();
+                last_move = None;
+                small_moves.clear();
+            }
+        }
+        if !moved && record.keys_pressed.is_empty() && record.mouse_buttons.is_empty() {
+            summary.idle_samples += 1;
+        }
+
+        if let Some(gap) = record.gap {
+            summary.gaps.push((gap.start, record.timestamp, gap.reason));
+        }
+        if let Some(marker) = &record.marker {
+            summary.markers.push((record.timestamp, marker.clone()));
+        }
+        previous = Some(record);
+    }
+
+    summary
+}
+
+/// Keys that went down in `record`. A key counts once per press, not once
+/// per sample it is held for.
+pub fn new_key_presses(previous: Option<&ActivityRecord>, record: &ActivityRecord) -> usize {
+    let held: &[Keycode] = previous.map_or(&[], |p| &p.keys_pressed);
+    record.keys_pressed.iter().filter(|k| !held.contains(k)).count()
+}
+
+/// Mouse buttons 1-3 that went down in `record`; the scroll wheel's 4 and 5
+/// are not clicks.
+pub fn new_clicks(previous: Option<&ActivityRecord>, record: &ActivityRecord) -> usize {
+    let held: &[u8] = previous.map_or(&[], |p| &p.mouse_buttons);
+    record
+        .mouse_buttons
+        .iter()
+        .filter(|b| (1..=3).contains(*b) && !held.contains(b))
+        .count()
+}
+
+/// Key presses per key, most pressed first.
+pub fn key_frequency(records: &[ActivityRecord]) -> Vec<(Keycode, usize)> {
+    let mut counts: HashMap<Keycode, usize> = HashMap::new();
+    let mut held: &[Keycode] = &[];
+    for record in records {
+        for key in record.keys_pressed.iter().filter(|k| !held.contains(k)) {
+            *counts.entry(*key).or_default() += 1;
+        }
+        held = &record.keys_pressed;
+    }
+
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| format!("{:?}", a.0).cmp(&format!("{:?}", b.0))));
+    counts
+}
+
+/// Seconds spent in each recorded app, most used first. The time up to the
+/// next sample counts towards the app of the earlier one; gaps count for
+/// none. Empty without `record_app`.
+pub fn app_time(records: &[ActivityRecord]) -> Vec<(String, u64)> {
+    time_by(records, |record| record.app.as_deref())
+}
+
+/// Seconds spent on each file reported by an editor, most used first,
+/// counted like [`app_time`].
+pub fn file_time(records: &[ActivityRecord]) -> Vec<(String, u64)> {
+    time_by(records, |record| record.context.editor.as_ref().map(|editor| editor.file.as_str()))
+}
+
+/// Like [`file_time`], per workspace folder or repository.
+pub fn project_time(records: &[ActivityRecord]) -> Vec<(String, u64)> {
+    time_by(records, |record| record.context.editor.as_ref().and_then(|editor| editor.project.as_deref()))
+}
+
+fn time_by<'a>(records: &'a [ActivityRecord], key: impl Fn(&'a ActivityRecord) -> Option<&'a str>) -> Vec<(String, u64)> {
+    let mut secs: HashMap<&str, u64> = HashMap::new();
+    for pair in records.windows(2) {
+        let (previous, record) = (&pair[0], &pair[1]);
+        if record.gap.is_some() {
+            continue;
+        }
+        if let Some(key) = key(previous) {
+            *secs.entry(key).or_default() += record.timestamp.saturating_sub(previous.timestamp);
+        }
+    }
+
+    let mut secs: Vec<(String, u64)> = secs.into_iter().map(|(key, secs)| (key.to_string(), secs)).collect();
+    secs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    secs
+}
+
+/// Seconds spent in each instance of the apps that ran as more than one
+/// process, most used first, labelled with the process ID and executable.
+/// Empty without `record_process`.
+pub fn instance_time(records: &[ActivityRecord]) -> Vec<(String, u64)> {
+    let mut secs: HashMap<(&str, u64, &str), u64> = HashMap::new();
+    for pair in records.windows(2) {
+        let (previous, record) = (&pair[0], &pair[1]);
+        if record.gap.is_some() {
+            continue;
+        }
+        if let (Some(app), Some(process)) = (&previous.app, &previous.context.process) {
+            *secs.entry((app, process.pid, &process.exe)).or_default() += record.timestamp.saturating_sub(previous.timestamp);
+        }
+    }
+
+    let mut instances: HashMap<&str, usize> = HashMap::new();
+    for (app, _, _) in secs.keys() {
+        *instances.entry(app).or_default() += 1;
+    }
+    let mut secs: Vec<(String, u64)> = secs
+        .into_iter()
+        .filter(|((app, _, _), _)| instances[app] > 1)
+        .map(|((app, pid, exe), secs)| (format!("{} [{}] {}", app, pid, exe), secs))
+        .collect();
+    secs.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    secs
+}
+
+/// Activity over time: for each `bucket_secs` window from the session's
+/// start, the number of samples with input (a key held or the mouse moved).
+pub fn activity_series(records: &[ActivityRecord], bucket_secs: u64) -> Vec<usize> {
+    let Some(first) = records.first() else {
+        return Vec::new();
+    };
+    let bucket_secs = bucket_secs.max(1);
+    let mut series = Vec::new();
+
+    let mut previous: Option<&ActivityRecord> = None;
+    for record in records {
+        let bucket = (record.timestamp.saturating_sub(first.timestamp) / bucket_secs) as usize;
+        if series.len() <= bucket {
+            series.resize(bucket + 1, 0);
+        }
+        let moved = previous.is_some_and(|p| p.mouse_x != record.mouse_x || p.mouse_y != record.mouse_y);
+        if moved || !record.keys_pressed.is_empty() {
+            series[bucket] += 1;
+        }
+        previous = Some(record);
+    }
+
+    series
+}
+
+/// The `bucket_secs` window of [`activity_series`] with the most input, as
+/// its start timestamp and number of active samples; the earliest on a tie.
+pub fn busiest_interval(records: &[ActivityRecord], bucket_secs: u64) -> Option<(u64, usize)> {
+    let first = records.first()?;
+    let series = activity_series(records, bucket_secs);
+    let (i, &active) = series.iter().enumerate().rev().max_by_key(|(_, active)| **active)?;
+    Some((first.timestamp + i as u64 * bucket_secs.max(1), active))
+}
+
+/// Like [`activity_series`], but for `buckets` windows starting at the
+/// timestamp `since`, e.g. the last few minutes of a running session.
+pub fn activity_since(records: &[ActivityRecord], since: u64, bucket_secs: u64, buckets: usize) -> Vec<usize> {
+    let bucket_secs = bucket_secs.max(1);
+    let start = records.partition_point(|r| r.timestamp < since);
+    let mut series = vec![0; buckets];
+
+    let mut previous = start.checked_sub(1).map(|i| &records[i]);
+    for record in &records[start..] {
+        let bucket = ((record.timestamp - since) / bucket_secs) as usize;
+        if bucket >= buckets {
+            break;
+        }
+        let moved = previous.is_some_and(|p| p.mouse_x != record.mouse_x || p.mouse_y != record.mouse_y);
+        if moved || !record.keys_pressed.is_empty() {
+            series[bucket] += 1;
+        }
+        previous = Some(record);
+    }
+
+    series
+}
+
+/// Pointer kinematics at one sample, in px/s and px/s². Either is `None`
+/// where the samples it needs are not adjacent: at the start and after a gap
+/// or a skipped stretch.
+#[derive(Clone, Copy, Default)]
+pub struct Motion {
+    pub velocity: Option<f64>,
+    pub acceleration: Option<f64>,
+}
+
+/// Velocity and acceleration for every sample, derived from consecutive
+/// positions. Timestamps only have second resolution, so adjacent samples
+/// are taken to be `interval` apart.
+pub fn motion_series(records: &[ActivityRecord], interval: Duration) -> Vec<Motion> {
+    let dt = interval.as_secs_f64();
+    let mut series: Vec<Motion> = Vec::with_capacity(records.len());
+
+    for (i, record) in records.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|j| &records[j]);
+        let adjacent = previous.filter(|p| record.gap.is_none() && record.timestamp.saturating_sub(p.timestamp) <= 1);
+        let velocity = adjacent.map(|p| {
+            let dx = (record.mouse_x - p.mouse_x) as f64;
+            let dy = (record.mouse_y - p.mouse_y) as f64;
+            dx.hypot(dy) / dt
+        });
+        let acceleration = match (velocity, series.last().and_then(|m| m.velocity)) {
+            (Some(v), Some(prev)) => Some((v - prev) / dt),
+            _ => None,
+        };
+        series.push(Motion { velocity, acceleration });
+    }
+
+    series
+}
+
+/// Mean pointer speed in px/s for each `bucket_secs` window from the
+/// session's start, for samples `interval` apart.
+pub fn speed_series(records: &[ActivityRecord], bucket_secs: u64, interval: Duration) -> Vec<f64> {
+    let Some(first) = records.first() else {
+        return Vec::new();
+    };
+    let bucket_secs = bucket_secs.max(1);
+    let mut sums: Vec<(f64, usize)> = Vec::new();
+
+    for (record, motion) in records.iter().zip(motion_series(records, interval)) {
+        let bucket = (record.timestamp.saturating_sub(first.timestamp) / bucket_secs) as usize;
+        if sums.len() <= bucket {
+            sums.resize(bucket + 1, (0.0, 0));
+        }
+        if let Some(v) = motion.velocity {
+            sums[bucket].0 += v;
+            sums[bucket].1 += 1;
+        }
+    }
+
+    sums.into_iter().map(|(sum, n)| if n == 0 { 0.0 } else { sum / n as f64 }).collect()
+}
+
+/// How well a project's sessions matched their time estimates.
+pub struct EstimateAccuracy {
+    pub project: String,
+    pub sessions: usize,
+    pub estimated_secs: u64,
+    pub actual_secs: u64,
+    /// Mean of |actual - estimate| / estimate over the sessions.
+    pub mean_error: f64,
+}
+
+/// Groups estimated sessions by project; sessions without an estimate are
+/// skipped and those without a project are grouped under "(none)".
+pub fn estimate_accuracy(sessions: &[(SessionMeta, Summary)]) -> Vec<EstimateAccuracy> {
+    let mut by_project: BTreeMap<String, EstimateAccuracy> = BTreeMap::new();
+
+    for (meta, summary) in sessions {
+        let Some(estimate) = meta.estimate_secs.filter(|&e| e > 0) else {
+            continue;
+        };
+        let project = meta.project.clone().unwrap_or_else(|| "(none)".to_string());
+        let entry = by_project.entry(project.clone()).or_insert(EstimateAccuracy {
+            project,
+            sessions: 0,
+            estimated_secs: 0,
+            actual_secs: 0,
+            mean_error: 0.0,
+        });
+
+        let actual = summary.tracked_secs();
+        entry.sessions += 1;
+        entry.estimated_secs += estimate;
+        entry.actual_secs += actual;
+        // Accumulate the sum here and divide once all sessions are in
+        entry.mean_error += actual.abs_diff(estimate) as f64 / estimate as f64;
+    }
+
+    by_project
+        .into_values()
+        .map(|mut accuracy| {
+            accuracy.mean_error /= accuracy.sessions as f64;
+            accuracy
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::DeviceInput;
+    use crate::context::SystemContext;
+    use crate::recorder::Gap;
+
+    fn sample(timestamp: u64, position: (i32, i32)) -> ActivityRecord {
+        ActivityRecord {
+            timestamp,
+            mouse_x: position.0,
+            mouse_y: position.1,
+            keys_pressed: Vec::new(),
+            mouse_buttons: Vec::new(),
+            app: None,
+            marker: None,
+            gap: None,
+            devices: DeviceInput::default(),
+            context: SystemContext::default(),
+            path: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn summarize_counts_input_and_gaps() {
+        let records = [
+            sample(100, (0, 0)),
+            ActivityRecord { keys_pressed: vec![Keycode::A], ..sample(101, (3, 4)) },
+            // A held key is not pressed again; the click ends the movement
+            ActivityRecord { keys_pressed: vec![Keycode::A], mouse_buttons: vec![1], ..sample(102, (3, 4)) },
+            // Neither the held button nor the scroll wheel is a click
+            ActivityRecord { mouse_buttons: vec![1, 4], ..sample(103, (3, 4)) },
+            ActivityRecord {
+                gap: Some(Gap { reason: GapReason::Pause, start: 104 }),
+                marker: Some("back".to_string()),
+                ..sample(110, (3, 4))
+            },
+        ];
+        let summary = summarize(&records);
+        assert_eq!(summary.samples, 5);
+        assert_eq!(summary.duration_secs(), 10);
+        assert_eq!(summary.tracked_secs(), 4);
+        assert_eq!(summary.key_presses, 1);
+        assert_eq!(summary.clicks, 1);
+        assert_eq!(summary.mouse_distance, 5.0);
+        assert_eq!(summary.idle_samples, 2);
+        assert_eq!(summary.markers, vec![(110, "back".to_string())]);
+        assert_eq!(summary.gaps.len(), 1);
+        assert!(matches!(summary.gaps[0], (104, 110, GapReason::Pause)));
+        assert_eq!((summary.aimed_clicks, summary.settle_samples, summary.corrections), (1, 1, 1));
+        assert_eq!(summary.mean_settle_secs(Duration::from_millis(100)), Some(0.1));
+    }
+
+    #[test]
+    fn summarize_empty_session() {
+        let summary = summarize(&[]);
+        assert_eq!(summary.samples, 0);
+        assert_eq!(summary.duration_secs(), 0);
+        assert_eq!(summary.idle_ratio(), 0.0);
+        assert_eq!(summary.mean_settle_secs(Duration::from_millis(100)), None);
+    }
+
+    #[test]
+    fn key_held_while_pointer_moves_is_one_press() {
+        let records = [
+            ActivityRecord { keys_pressed: vec![Keycode::A], ..sample(10, (0, 0)) },
+            ActivityRecord {
+                keys_pressed: vec![Keycode::A],
+                path: vec![(10_300, 10, 0), (10_600, 20, 0)],
+                ..sample(11, (30, 0))
+            },
+            ActivityRecord {
+                keys_pressed: vec![Keycode::A],
+                path: vec![(11_300, 40, 0), (11_600, 50, 0)],
+                ..sample(12, (60, 0))
+            },
+        ];
+        let summary = summarize(&records);
+        assert_eq!(summary.samples, 3);
+        assert_eq!(summary.key_presses, 1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Flags stretches of a session that look wrong rather than merely quiet:
+//! long silences inside a task, a session left running after work ended,
+//! capture that stalled, and input faster than a person can produce.
+
+use crate::cli::format_duration;
+use crate::recorder::ActivityRecord;
+
+/// No input for this long between two active stretches is worth a look.
+const SILENCE_SECS: u64 = 15 * 60;
+/// Samples are ~100 ms apart; a jump this long without a recorded gap means
+/// the capture thread stopped producing.
+const STALL_SECS: u64 = 5;
+/// New key presses within one second beyond which input is not human
+/// (fast typists peak around 15).
+const MAX_KEYS_PER_SEC: usize = 30;
+
+pub enum AnomalyKind {
+    /// No input between two active stretches.
+    Silence,
+    /// No input from some point until the session ended.
+    TrailingSilence,
+    /// No samples at all, without a gap explaining why.
+    Stall,
+    /// More key presses than a person can type.
+    ImpossibleInput(usize),
+}
+
+pub struct Anomaly {
+    pub start: u64,
+    pub end: u64,
+    pub kind: AnomalyKind,
+}
+
+impl Anomaly {
+    pub fn describe(&self) -> String {
+        let length = format_duration(self.end.saturating_sub(self.start));
+        match self.kind {
+            AnomalyKind::Silence => format!("no input for {} mid-task", length),
+            AnomalyKind::TrailingSilence => {
+                format!("no input for the last {}; the session may have been left running", length)
+            }
+            AnomalyKind::Stall => format!("capture stalled for {} without a recorded gap", length),
+            AnomalyKind::ImpossibleInput(keys) => {
+                format!("{} key presses within one second; input may be synthetic or corrupt", keys)
+            }
+        }
+    }
+}
+
+pub fn detect(records: &[ActivityRecord]) -> Vec<Anomaly> {
+    let mut anomalies = Vec::new();
+    // Start of the current run without input, if any
+    let mut silent_since: Option<u64> = None;
+    let mut seen_activity = false;
+    // (second, new key presses in it)
+    let mut current_second: Option<(u64, usize)> = None;
+
+    let mut previous: Option<&ActivityRecord> = None;
+    for record in records {
+        let held: &[_] = previous.map_or(&[], |p| &p.keys_pressed);
+        let new_keys = record.keys_pressed.iter().filter(|k| !held.contains(k)).count();
+        let moved = previous.is_some_and(|p| p.mouse_x != record.mouse_x || p.mouse_y != record.mouse_y);
+
+        if let Some(p) = previous {
+            let jump = record.timestamp.saturating_sub(p.timestamp);
+            if jump >= STALL_SECS && record.gap.is_none() {
+                anomalies.push(Anomaly { start: p.timestamp, end: record.timestamp, kind: AnomalyKind::Stall });
+            }
+        }
+
+        // A gap is an explained break, not a silence
+        if record.gap.is_some() {
+            silent_since = None;
+        }
+        if moved || new_keys > 0 || !record.keys_pressed.is_empty() {
+            if let Some(start) = silent_since.take() {
+                if seen_activity && record.timestamp.saturating_sub(start) >= SILENCE_SECS {
+                    anomalies.push(Anomaly { start, end: record.timestamp, kind: AnomalyKind::Silence });
+                }
+            }
+            seen_activity = true;
+        } else if silent_since.is_none() {
+            silent_since = Some(record.timestamp);
+        }
+
+        current_second = match current_second {
+            Some((second, keys)) if second == record.timestamp => Some((second, keys + new_keys)),
+            Some((second, keys)) => {
+                flag_rate(&mut anomalies, second, keys);
+                Some((record.timestamp, new_keys))
+            }
+            None => Some((record.timestamp, new_keys)),
+        };
+        previous = Some(record);
+    }
+
+    if let Some((second, keys)) = current_second {
+        flag_rate(&mut anomalies, second, keys);
+    }
+    if let (Some(start), Some(last)) = (silent_since, records.last()) {
+        if seen_activity && last.timestamp.saturating_sub(start) >= SILENCE_SECS {
+            anomalies.push(Anomaly { start, end: last.timestamp, kind: AnomalyKind::TrailingSilence });
+        }
+    }
+
+    anomalies.sort_by_key(|a| a.start);
+    anomalies
+}
+
+fn flag_rate(anomalies: &mut Vec<Anomaly>, second: u64, keys: usize) {
+    if keys > MAX_KEYS_PER_SEC {
+        anomalies.push(Anomaly { start: second, end: second + 1, kind: AnomalyKind::ImpossibleInput(keys) });
+    }
+}
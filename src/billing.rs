@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+
+use chrono::{DateTime, Datelike, Local};
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use crate::analysis::Summary;
+use crate::storage::SessionMeta;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Rounding {
+    Nearest,
+    Up,
+    Down,
+}
+
+impl Rounding {
+    /// Rounds `secs` to a multiple of `increment` (no rounding when zero).
+    pub fn apply(self, secs: u64, increment: u64) -> u64 {
+        if increment == 0 {
+            return secs;
+        }
+        let whole = secs / increment * increment;
+        let rest = secs % increment;
+        match self {
+            Rounding::Down => whole,
+            Rounding::Up if rest > 0 => whole + increment,
+            Rounding::Up => whole,
+            Rounding::Nearest if rest * 2 >= increment => whole + increment,
+            Rounding::Nearest => whole,
+        }
+    }
+}
+
+/// `billing` section of the settings file:
+///
+/// ```json
+/// "billing": { "currency": "EUR", "default_rate": 80, "rates": { "acme": 120 } }
+/// ```
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BillingConfig {
+    pub currency: String,
+    /// Hourly rate for projects without their own.
+    pub default_rate: Option<f64>,
+    /// Hourly rate per project name.
+    pub rates: HashMap<String, f64>,
+}
+
+impl BillingConfig {
+    pub fn rate(&self, project: &str) -> Option<f64> {
+        self.rates.get(project).copied().or(self.default_rate)
+    }
+}
+
+pub struct BillingRules {
+    pub increment_secs: u64,
+    pub rounding: Rounding,
+    /// Round every session on its own rather than each task's total.
+    pub per_session: bool,
+}
+
+/// Billable time for one task of one project.
+pub struct BillingLine {
+    pub project: String,
+    pub task: String,
+    pub sessions: usize,
+    pub tracked_secs: u64,
+    pub billable_secs: u64,
+    pub rate: Option<f64>,
+}
+
+impl BillingLine {
+    pub fn amount(&self) -> Option<f64> {
+        self.rate.map(|rate| rate * self.billable_secs as f64 / 3600.0)
+    }
+}
+
+pub fn bill(
+    sessions: &[(SessionMeta, Summary)],
+    rules: &BillingRules,
+    config: &BillingConfig,
+) -> Vec<BillingLine> {
+    let mut lines: BTreeMap<(String, String), BillingLine> = BTreeMap::new();
+
+    for (meta, summary) in sessions {
+        let project = meta.project.clone().unwrap_or_default();
+        let key = (project.clone(), meta.task.clone());
+        let line = lines.entry(key).or_insert_with(|| BillingLine {
+            rate: config.rate(&project),
+            project,
+            task: meta.task.clone(),
+            sessions: 0,
+            tracked_secs: 0,
+            billable_secs: 0,
+        });
+
+        let tracked = summary.tracked_secs();
+        line.sessions += 1;
+        line.tracked_secs += tracked;
+        if rules.per_session {
+            line.billable_secs += rules.rounding.apply(tracked, rules.increment_secs);
+        }
+    }
+
+    lines
+        .into_values()
+        .map(|mut line| {
+            if !rules.per_session {
+                line.billable_secs = rules.rounding.apply(line.tracked_secs, rules.increment_secs);
+            }
+            line
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+    Project,
+}
+
+/// Earnings over one day, week or project.
+pub struct Earnings {
+    pub label: String,
+    pub billable_secs: u64,
+    /// Sum over the lines that have a rate.
+    pub amount: f64,
+    /// Lines without any rate configured, which `amount` leaves out.
+    pub unpriced_lines: usize,
+}
+
+/// Bills each period's sessions separately, so rounding applies within a
+/// day or week the same way it would on an invoice for it.
+pub fn earnings(
+    sessions: Vec<(SessionMeta, Summary)>,
+    by: Period,
+    rules: &BillingRules,
+    config: &BillingConfig,
+) -> Vec<Earnings> {
+    let mut groups: BTreeMap<String, Vec<(SessionMeta, Summary)>> = BTreeMap::new();
+    for (meta, summary) in sessions {
+        let start = DateTime::from_timestamp(summary.start as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local);
+        let label = match by {
+            Period::Day => start.format("%Y-%m-%d").to_string(),
+            Period::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Period::Project => meta.project.clone().unwrap_or_else(|| "(none)".to_string()),
+        };
+        groups.entry(label).or_default().push((meta, summary));
+    }
+
+    groups
+        .into_iter()
+        .map(|(label, sessions)| {
+            let lines = bill(&sessions, rules, config);
+            Earnings {
+                label,
+                billable_secs: lines.iter().map(|l| l.billable_secs).sum(),
+                amount: lines.iter().filter_map(BillingLine::amount).fold(0.0, |a, b| a + b),
+                unpriced_lines: lines.iter().filter(|l| l.rate.is_none()).count(),
+            }
+        })
+        .collect()
+}
+
+/// Decimal hours with two places, the usual unit on invoices.
+pub fn hours(secs: u64) -> String {
+    format!("{:.2}", secs as f64 / 3600.0)
+}
+
+fn csv_field(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+/// Writes one row per task, hours as decimals, ready for an invoice. Rate and
+/// amount stay empty for projects without a rate.
+pub fn write_csv(lines: &[BillingLine], out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "project,task,sessions,tracked_hours,billable_hours,rate,amount")?;
+    for line in lines {
+        let money = |value: Option<f64>| value.map(|v| format!("{:.2}", v)).unwrap_or_default();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            csv_field(&line.project),
+            csv_field(&line.task),
+            line.sessions,
+            hours(line.tracked_secs),
+            hours(line.billable_secs),
+            money(line.rate),
+            money(line.amount())
+        )?;
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+//! CalDAV sync for self-hosted calendars such as Nextcloud or Radicale,
+//! configured under `caldav` in the settings file:
+//!
+//! ```json
+//! "caldav": {
+//!     "url": "https://cloud.example.com/remote.php/dav/calendars/me/work/",
+//!     "username": "me",
+//!     "password": "app password"
+//! }
+//! ```
+//!
+//! Each saved session is stored as its own `.ics` resource in the calendar
+//! collection at `url`, named after the event UID, so a re-saved session
+//! replaces its event.
+
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::http;
+use crate::ics;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CalDavConfig {
+    /// Calendar collection URL.
+    pub url: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl CalDavConfig {
+    pub fn upload(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let url = format!(
+            "{}/{}.ics",
+            self.url.trim_end_matches('/'),
+            http::encode(&ics::uid(meta, &summary))
+        );
+        let mut headers = vec![("Content-Type", "text/calendar; charset=utf-8".to_string())];
+        if let Some(username) = &self.username {
+            let password = self.password.as_deref().unwrap_or("");
+            headers.push(("Authorization", http::basic_auth(username, password)));
+        }
+        let headers: Vec<(&str, &str)> = headers.iter().map(|(name, value)| (*name, value.as_str())).collect();
+        outbox::deliver("CalDAV", "PUT", &url, &headers, &ics::single_event(meta, &summary))
+    }
+}
@@ -0,0 +1,497 @@
+//! Input capture. By default the mouse and the keyboard are polled through
+//! device_query on threads of their own, each at its own interval
+//! (`mouse_poll_ms`, `keyboard_poll_ms`). Builds with the `rdev` feature
+//! can instead listen for every input event (`"capture_backend": "events"`),
+//! falling back to polling where listening isn't possible, e.g. on Wayland.
+//!
+//! Either way, what is seen is merged into the capture loop's samples: a
+//! sample gets the latest pointer position and every button and key seen
+//! held since the previous sample. A device read more often than samples
+//! are taken thus doesn't lose short clicks or keystrokes in between; one
+//! read less often repeats its last reading. The positions the pointer
+//! passed through in between are kept as the sample's path. Other devices,
+//! such as `hid_devices`, are read on threads of their own and their input
+//! is attached to the next sample as a [`DeviceInput`].
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::evdev_input::{self, Evdev, PenState, TouchState, Wanted};
+use crate::gamepad::{self, GamepadState, Gamepads};
+use crate::hid::{self, HidEvent, HidReport};
+use crate::recorder::SLEEP_GAP;
+
+/// Shortest interval a device is polled at.
+const MIN_POLL: Duration = Duration::from_millis(5);
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureBackend {
+    /// device_query, at `mouse_poll_ms` and `keyboard_poll_ms`.
+    #[default]
+    Poll,
+    /// rdev's global listeners; needs the `rdev` feature.
+    Events,
+}
+
+/// What has been seen since the last sample.
+#[derive(Default)]
+struct Seen {
+    mouse: (i32, i32),
+    /// Each new pointer position seen, with the millisecond it was seen.
+    path: Vec<(u64, i32, i32)>,
+    /// Held right now.
+    buttons: Vec<u8>,
+    keys: Vec<Keycode>,
+    /// Held at any time since the last sample.
+    buttons_since: Vec<u8>,
+    keys_since: Vec<Keycode>,
+    devices: DeviceInput,
+}
+
+impl Seen {
+    /// Adds a new pointer position to the path, at most one every
+    /// `MIN_POLL` so a listener flooded with motion events stays bounded.
+    fn moved(&mut self, position: (i32, i32)) {
+        if position == self.mouse {
+            return;
+        }
+        self.mouse = position;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        match self.path.last_mut() {
+            Some(last) if now < last.0 + MIN_POLL.as_millis() as u64 => *last = (last.0, position.0, position.1),
+            _ => self.path.push((now, position.0, position.1)),
+        }
+    }
+}
+
+/// One merged reading of both devices.
+pub struct Input {
+    pub mouse: (i32, i32),
+    /// Earlier positions seen since the previous sample, oldest first,
+    /// with the millisecond each was seen.
+    pub path: Vec<(u64, i32, i32)>,
+    pub buttons: Vec<u8>,
+    pub keys: Vec<Keycode>,
+    pub devices: DeviceInput,
+}
+
+/// Input from devices besides the pointer and the keyboard since the
+/// previous sample, stored as JSON in the `devices` column of sessions.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DeviceInput {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hid: Vec<HidReport>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gamepads: Vec<GamepadState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pen: Option<PenState>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub touch: Vec<TouchState>,
+    /// Keyboards typed on and pointing devices used since the previous
+    /// sample, see `evdev_input.rs`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keyboards: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pointers: Vec<String>,
+    /// Devices plugged in or removed since the previous sample.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connected: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub disconnected: Vec<String>,
+}
+
+impl DeviceInput {
+    pub fn is_empty(&self) -> bool {
+        self.hid.is_empty()
+            && self.gamepads.is_empty()
+            && self.pen.is_none()
+            && self.touch.is_empty()
+            && self.keyboards.is_empty()
+            && self.pointers.is_empty()
+            && self.connected.is_empty()
+            && self.disconnected.is_empty()
+    }
+}
+
+/// The capture threads; pollers stop when this is dropped.
+pub struct Devices {
+    seen: Arc<Mutex<Seen>>,
+    gamepads: Option<Arc<Mutex<Gamepads>>>,
+    evdev: Option<Arc<Mutex<Evdev>>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl Devices {
+    /// Starts capturing, failing if the devices can't be opened, e.g.
+    /// without a display.
+    pub fn spawn(config: &Config) -> Result<Self, String> {
+        let mut devices = Self::pointer_and_keyboard(config)?;
+        if !config.hid_devices.is_empty() {
+            let seen = devices.seen.clone();
+            hid::spawn(&config.hid_devices, devices.stop.clone(), move |event| {
+                let mut seen = seen.lock().unwrap_or_else(|e| e.into_inner());
+                match event {
+                    HidEvent::Report(report) => seen.devices.hid.push(report),
+                    HidEvent::Connected(name) => seen.devices.connected.push(name),
+                    HidEvent::Disconnected(name) => seen.devices.disconnected.push(name),
+                }
+            });
+        }
+        if config.gamepads {
+            let gamepads = Arc::default();
+            gamepad::spawn(devices.stop.clone(), Arc::clone(&gamepads));
+            devices.gamepads = Some(gamepads);
+        }
+        let wanted = Wanted {
+            pen: config.pen,
+            touch: config.touch,
+            identify: config.identify_devices,
+            hotplug: config.hotplug,
+        };
+        if wanted.any() {
+            let evdev = Arc::default();
+            evdev_input::spawn(wanted, devices.stop.clone(), Arc::clone(&evdev));
+            devices.evdev = Some(evdev);
+        }
+        Ok(devices)
+    }
+
+    fn pointer_and_keyboard(config: &Config) -> Result<Self, String> {
+        if config.capture_backend == CaptureBackend::Events {
+            match events() {
+                Ok(seen) => {
+                    let devices = Devices {
+                        seen,
+                        gamepads: None,
+                        evdev: None,
+                        stop: Arc::default(),
+                    };
+                    // Drop what was seen between sessions
+                    devices.take();
+                    return Ok(devices);
+                }
+                Err(e) => tracing::warn!("{}; polling instead", e),
+            }
+        }
+
+        let devices = Devices {
+            seen: Arc::default(),
+            gamepads: None,
+            evdev: None,
+            stop: Arc::default(),
+        };
+        let (ready, started) = mpsc::channel();
+        devices.poll(Duration::from_millis(config.mouse_poll_ms), ready.clone(), |device, seen| {
+            let mouse = device.get_mouse();
+            seen.moved(mouse.coords);
+            // Index 0 is unused
+            seen.buttons = (1..mouse.button_pressed.len())
+                .filter(|&button| mouse.button_pressed[button])
+                .map(|button| button as u8)
+                .collect();
+            for &button in &seen.buttons {
+                add(&mut seen.buttons_since, button);
+            }
+        });
+        devices.poll(Duration::from_millis(config.keyboard_poll_ms), ready, |device, seen| {
+            seen.keys = device.get_keys();
+            for &key in &seen.keys {
+                add(&mut seen.keys_since, key);
+            }
+        });
+        for _ in 0..2 {
+            // A poller that panicked drops its sender without reporting
+            started
+                .recv()
+                .unwrap_or_else(|_| Err("Input polling failed to start".to_string()))?;
+        }
+        Ok(devices)
+    }
+
+    /// Everything seen since the last call.
+    pub fn take(&self) -> Input {
+        let mut guard = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let seen = &mut *guard;
+        let buttons = std::mem::replace(&mut seen.buttons_since, seen.buttons.clone());
+        let keys = std::mem::replace(&mut seen.keys_since, seen.keys.clone());
+        let mut path = std::mem::take(&mut seen.path);
+        // The latest position is the sample's own
+        if path.last().is_some_and(|&(_, x, y)| (x, y) == seen.mouse) {
+            path.pop();
+        }
+        let mut devices = std::mem::take(&mut seen.devices);
+        if let Some(gamepads) = &self.gamepads {
+            gamepads.lock().unwrap_or_else(|e| e.into_inner()).take(&mut devices);
+        }
+        if let Some(evdev) = &self.evdev {
+            evdev.lock().unwrap_or_else(|e| e.into_inner()).take(&mut devices);
+        }
+        Input {
+            mouse: seen.mouse,
+            path,
+            buttons,
+            keys,
+            devices,
+        }
+    }
+
+    fn poll(
+        &self,
+        interval: Duration,
+        ready: mpsc::Sender<Result<(), String>>,
+        read: impl Fn(&DeviceState, &mut Seen) + Send + 'static,
+    ) {
+        let (seen, stop) = (self.seen.clone(), self.stop.clone());
+        let interval = interval.max(MIN_POLL);
+        thread::spawn(move || {
+            // DeviceState can't be moved across threads, so each poller opens its own
+            let Some(mut device) = DeviceState::checked_new() else {
+                let _ = ready.send(Err("Could not open the mouse and keyboard; is a display available?".to_string()));
+                return;
+            };
+            let _ = ready.send(Ok(()));
+            let mut last_poll = SystemTime::now();
+            while !stop.load(Ordering::SeqCst) {
+                // The display connection may not survive a suspend
+                let now = SystemTime::now();
+                if now.duration_since(last_poll).is_ok_and(|gap| gap >= SLEEP_GAP) {
+                    if let Some(reopened) = DeviceState::checked_new() {
+                        device = reopened;
+                    }
+                }
+                last_poll = now;
+                read(&device, &mut seen.lock().unwrap_or_else(|e| e.into_inner()));
+                thread::sleep(interval);
+            }
+        });
+    }
+}
+
+impl Drop for Devices {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
+fn add<T: PartialEq>(list: &mut Vec<T>, item: T) {
+    if !list.contains(&item) {
+        list.push(item);
+    }
+}
+
+#[cfg(not(feature = "rdev"))]
+fn events() -> Result<Arc<Mutex<Seen>>, String> {
+    Err("This build can't listen for input events; rebuild it with `--features rdev`".to_string())
+}
+
+/// rdev's listener can't be stopped, so one is started on first use and
+/// shared by every session after that.
+#[cfg(feature = "rdev")]
+fn events() -> Result<Arc<Mutex<Seen>>, String> {
+    use std::sync::OnceLock;
+
+    use rdev::EventType;
+
+    /// How long the listener gets to fail before it's taken to be running.
+    const STARTUP: Duration = Duration::from_millis(500);
+    static LISTENER: OnceLock<Result<Arc<Mutex<Seen>>, String>> = OnceLock::new();
+
+    LISTENER
+        .get_or_init(|| {
+            let mut seen = Seen::default();
+            // Until the pointer first moves
+            if let Some(device) = DeviceState::checked_new() {
+                seen.mouse = device.get_mouse().coords;
+            }
+            let seen = Arc::new(Mutex::new(seen));
+            let listened = seen.clone();
+            let (failed, failure) = mpsc::channel();
+            thread::spawn(move || {
+                let result = rdev::listen(move |event| {
+                    let seen = &mut *listened.lock().unwrap_or_else(|e| e.into_inner());
+                    match event.event_type {
+                        EventType::KeyPress(key) => {
+                            if let Some(key) = keycode(key) {
+                                add(&mut seen.keys, key);
+                                add(&mut seen.keys_since, key);
+                            }
+                        }
+                        EventType::KeyRelease(key) => {
+                            if let Some(key) = keycode(key) {
+                                seen.keys.retain(|&held| held != key);
+                            }
+                        }
+                        EventType::ButtonPress(button) => {
+                            let button = button_number(button);
+                            add(&mut seen.buttons, button);
+                            add(&mut seen.buttons_since, button);
+                        }
+                        EventType::ButtonRelease(button) => {
+                            let button = button_number(button);
+                            seen.buttons.retain(|&held| held != button);
+                        }
+                        EventType::MouseMove { x, y } => seen.moved((x as i32, y as i32)),
+                        // Recorded like X11's wheel buttons, which are never held
+                        EventType::Wheel { delta_x, delta_y } => {
+                            let button = match (delta_x.signum(), delta_y.signum()) {
+                                (_, 1) => 4,
+                                (_, -1) => 5,
+                                (-1, _) => 6,
+                                (1, _) => 7,
+                                _ => return,
+                            };
+                            add(&mut seen.buttons_since, button);
+                        }
+                    }
+                });
+                if let Err(e) = result {
+                    let _ = failed.send(format!("{:?}", e));
+                }
+            });
+            match failure.recv_timeout(STARTUP) {
+                Ok(e) => Err(format!("Could not listen for input events: {}", e)),
+                Err(mpsc::RecvTimeoutError::Disconnected) => Err("The input event listener stopped".to_string()),
+                Err(mpsc::RecvTimeoutError::Timeout) => Ok(seen),
+            }
+        })
+        .clone()
+}
+
+/// Mouse buttons numbered like device_query's, from 1 (left).
+#[cfg(feature = "rdev")]
+fn button_number(button: rdev::Button) -> u8 {
+    match button {
+        rdev::Button::Left => 1,
+        rdev::Button::Middle => 2,
+        rdev::Button::Right => 3,
+        rdev::Button::Unknown(number) => number,
+    }
+}
+
+/// The device_query key for an rdev one, so recordings read the same
+/// whichever backend made them. Keys device_query doesn't know are dropped.
+#[cfg(feature = "rdev")]
+fn keycode(key: rdev::Key) -> Option<Keycode> {
+    use rdev::Key;
+
+    Some(match key {
+        Key::Num0 => Keycode::Key0,
+        Key::Num1 => Keycode::Key1,
+        Key::Num2 => Keycode::Key2,
+        Key::Num3 => Keycode::Key3,
+        Key::Num4 => Keycode::Key4,
+        Key::Num5 => Keycode::Key5,
+        Key::Num6 => Keycode::Key6,
+        Key::Num7 => Keycode::Key7,
+        Key::Num8 => Keycode::Key8,
+        Key::Num9 => Keycode::Key9,
+        Key::KeyA => Keycode::A,
+        Key::KeyB => Keycode::B,
+        Key::KeyC => Keycode::C,
+        Key::KeyD => Keycode::D,
+        Key::KeyE => Keycode::E,
+        Key::KeyF => Keycode::F,
+        Key::KeyG => Keycode::G,
+        Key::KeyH => Keycode::H,
+        Key::KeyI => Keycode::I,
+        Key::KeyJ => Keycode::J,
+        Key::KeyK => Keycode::K,
+        Key::KeyL => Keycode::L,
+        Key::KeyM => Keycode::M,
+        Key::KeyN => Keycode::N,
+        Key::KeyO => Keycode::O,
+        Key::KeyP => Keycode::P,
+        Key::KeyQ => Keycode::Q,
+        Key::KeyR => Keycode::R,
+        Key::KeyS => Keycode::S,
+        Key::KeyT => Keycode::T,
+        Key::KeyU => Keycode::U,
+        Key::KeyV => Keycode::V,
+        Key::KeyW => Keycode::W,
+        Key::KeyX => Keycode::X,
+        Key::KeyY => Keycode::Y,
+        Key::KeyZ => Keycode::Z,
+        Key::F1 => Keycode::F1,
+        Key::F2 => Keycode::F2,
+        Key::F3 => Keycode::F3,
+        Key::F4 => Keycode::F4,
+        Key::F5 => Keycode::F5,
+        Key::F6 => Keycode::F6,
+        Key::F7 => Keycode::F7,
+        Key::F8 => Keycode::F8,
+        Key::F9 => Keycode::F9,
+        Key::F10 => Keycode::F10,
+        Key::F11 => Keycode::F11,
+        Key::F12 => Keycode::F12,
+        Key::Escape => Keycode::Escape,
+        Key::Space => Keycode::Space,
+        Key::ControlLeft => Keycode::LControl,
+        Key::ControlRight => Keycode::RControl,
+        Key::ShiftLeft => Keycode::LShift,
+        Key::ShiftRight => Keycode::RShift,
+        #[cfg(target_os = "macos")]
+        Key::Alt => Keycode::LOption,
+        #[cfg(target_os = "macos")]
+        Key::AltGr => Keycode::ROption,
+        #[cfg(target_os = "macos")]
+        Key::MetaLeft | Key::MetaRight => Keycode::Command,
+        #[cfg(not(target_os = "macos"))]
+        Key::Alt => Keycode::LAlt,
+        #[cfg(not(target_os = "macos"))]
+        Key::AltGr => Keycode::RAlt,
+        #[cfg(not(target_os = "macos"))]
+        Key::MetaLeft => Keycode::LMeta,
+        #[cfg(not(target_os = "macos"))]
+        Key::MetaRight => Keycode::RMeta,
+        Key::Return => Keycode::Enter,
+        Key::UpArrow => Keycode::Up,
+        Key::DownArrow => Keycode::Down,
+        Key::LeftArrow => Keycode::Left,
+        Key::RightArrow => Keycode::Right,
+        Key::Backspace => Keycode::Backspace,
+        Key::CapsLock => Keycode::CapsLock,
+        Key::Tab => Keycode::Tab,
+        Key::Home => Keycode::Home,
+        Key::End => Keycode::End,
+        Key::PageUp => Keycode::PageUp,
+        Key::PageDown => Keycode::PageDown,
+        Key::Insert => Keycode::Insert,
+        Key::Delete => Keycode::Delete,
+        Key::Kp0 => Keycode::Numpad0,
+        Key::Kp1 => Keycode::Numpad1,
+        Key::Kp2 => Keycode::Numpad2,
+        Key::Kp3 => Keycode::Numpad3,
+        Key::Kp4 => Keycode::Numpad4,
+        Key::Kp5 => Keycode::Numpad5,
+        Key::Kp6 => Keycode::Numpad6,
+        Key::Kp7 => Keycode::Numpad7,
+        Key::Kp8 => Keycode::Numpad8,
+        Key::Kp9 => Keycode::Numpad9,
+        Key::KpMinus => Keycode::NumpadSubtract,
+        Key::KpPlus => Keycode::NumpadAdd,
+        Key::KpDivide => Keycode::NumpadDivide,
+        Key::KpMultiply => Keycode::NumpadMultiply,
+        Key::KpReturn => Keycode::NumpadEnter,
+        Key::KpDelete => Keycode::NumpadDecimal,
+        Key::BackQuote => Keycode::Grave,
+        Key::Minus => Keycode::Minus,
+        Key::Equal => Keycode::Equal,
+        Key::LeftBracket => Keycode::LeftBracket,
+        Key::RightBracket => Keycode::RightBracket,
+        Key::BackSlash => Keycode::BackSlash,
+        Key::SemiColon => Keycode::Semicolon,
+        Key::Quote => Keycode::Apostrophe,
+        Key::Comma => Keycode::Comma,
+        Key::Dot => Keycode::Dot,
+        Key::Slash => Keycode::Slash,
+        _ => return None,
+    })
+}
@@ -0,0 +1,165 @@
+//! Time per app category, with each category marked productive, neutral or
+//! unproductive, in the spirit of RescueTime but computed from the recorded
+//! app names only. User rules from the config are checked before the
+//! built-in ones, so they can re-file any app.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{analysis, cli};
+use crate::config::Pattern;
+use crate::recorder::ActivityRecord;
+
+/// Category for apps no rule matches.
+pub const UNCATEGORIZED: &str = "Uncategorized";
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Productivity {
+    Productive,
+    #[default]
+    Neutral,
+    Unproductive,
+}
+
+impl Productivity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Productivity::Productive => "productive",
+            Productivity::Neutral => "neutral",
+            Productivity::Unproductive => "unproductive",
+        }
+    }
+}
+
+/// Files apps whose name matches one of `apps` under `category`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryRule {
+    pub category: String,
+    #[serde(default)]
+    pub productivity: Productivity,
+    pub apps: Vec<Pattern>,
+}
+
+/// Built-in rules for common editors, terminals, office, design,
+/// communication, browser and entertainment apps.
+pub fn default_rules() -> Vec<CategoryRule> {
+    [
+        ("Development", Productivity::Productive, r"(?i)\b(code|codium|cursor|zed|idea|pycharm|clion|goland|rustrover|webstorm|android studio|xcode|vim|nvim|gvim|emacs|sublime_text|sublime text)\b"),
+        ("Terminal", Productivity::Productive, r"(?i)\b(terminal|iterm2?|konsole|alacritty|kitty|wezterm|gnome-terminal|windowsterminal|powershell|cmd)\b"),
+        ("Writing & Office", Productivity::Productive, r"(?i)\b(winword|word|excel|powerpnt|powerpoint|libreoffice|soffice|pages|numbers|keynote|obsidian|notion)\b"),
+        ("Design", Productivity::Productive, r"(?i)\b(figma|gimp|inkscape|photoshop|illustrator|blender|krita)\b"),
+        ("Communication", Productivity::Neutral, r"(?i)\b(slack|teams|discord|zoom|outlook|thunderbird|mail|telegram|signal|whatsapp)\b"),
+        ("Browsing", Productivity::Neutral, r"(?i)\b(firefox|chrome|chromium|msedge|safari|brave|opera|vivaldi)\b"),
+        ("Entertainment", Productivity::Unproductive, r"(?i)\b(spotify|vlc|steam|netflix|music|tv)\b"),
+    ]
+    .into_iter()
+    .map(|(category, productivity, apps)| CategoryRule {
+        category: category.to_string(),
+        productivity,
+        apps: vec![Pattern::try_from(apps.to_string()).expect("default patterns are valid")],
+    })
+    .collect()
+}
+
+/// `rules` followed by the built-in ones.
+pub fn with_defaults(mut rules: Vec<CategoryRule>) -> Vec<CategoryRule> {
+    rules.extend(default_rules());
+    rules
+}
+
+/// The first rule matching `app`, if any.
+pub fn categorize<'a>(rules: impl IntoIterator<Item = &'a CategoryRule>, app: &str) -> Option<&'a CategoryRule> {
+    rules.into_iter().find(|rule| rule.apps.iter().any(|pattern| pattern.is_match(app)))
+}
+
+#[derive(Default, Serialize)]
+pub struct CategoryReport {
+    /// Time with a known app, i.e. the total of all categories.
+    pub total_secs: u64,
+    pub productive_secs: u64,
+    pub unproductive_secs: u64,
+    pub categories: Vec<CategoryTime>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryTime {
+    pub category: String,
+    pub productivity: Productivity,
+    pub secs: u64,
+    pub share: f64,
+}
+
+impl CategoryReport {
+    pub fn from_sessions<'a>(rules: &[CategoryRule], sessions: impl IntoIterator<Item = &'a [ActivityRecord]>) -> Self {
+        let mut times: HashMap<String, (Productivity, u64)> = HashMap::new();
+        for records in sessions {
+            for (app, secs) in analysis::app_time(records) {
+                let (category, productivity) = match categorize(rules, &app) {
+                    Some(rule) => (rule.category.clone(), rule.productivity),
+                    None => (UNCATEGORIZED.to_string(), Productivity::Neutral),
+                };
+                times.entry(category).or_insert((productivity, 0)).1 += secs;
+            }
+        }
+
+        let total_secs: u64 = times.values().map(|(_, secs)| secs).sum();
+        let secs_for = |wanted: Productivity| -> u64 {
+            times.values().filter(|(p, _)| *p == wanted).map(|(_, secs)| secs).sum()
+        };
+        let productive_secs = secs_for(Productivity::Productive);
+        let unproductive_secs = secs_for(Productivity::Unproductive);
+
+        let mut categories: Vec<CategoryTime> = times
+            .into_iter()
+            .map(|(category, (productivity, secs))| CategoryTime {
+                category,
+                productivity,
+                secs,
+                share: secs as f64 / total_secs.max(1) as f64,
+            })
+            .collect();
+        categories.sort_by(|a, b| b.secs.cmp(&a.secs).then_with(|| a.category.cmp(&b.category)));
+
+        CategoryReport { total_secs, productive_secs, unproductive_secs, categories }
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "category,productivity,secs,share")?;
+        for c in &self.categories {
+            writeln!(
+                out,
+                "\"{}\",{},{},{:.4}",
+                c.category.replace('"', "\"\""),
+                c.productivity.as_str(),
+                c.secs,
+                c.share
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    pub fn print(&self) {
+        let share = |secs: u64| secs as f64 / self.total_secs.max(1) as f64 * 100.0;
+        println!("Categorized:     {}", cli::format_duration(self.total_secs));
+        println!("Productive:      {} ({:.1}%)", cli::format_duration(self.productive_secs), share(self.productive_secs));
+        println!("Unproductive:    {} ({:.1}%)", cli::format_duration(self.unproductive_secs), share(self.unproductive_secs));
+        for c in &self.categories {
+            println!(
+                "{:<20} {:<13} {}  {:>5.1}%",
+                c.category,
+                c.productivity.as_str(),
+                cli::format_duration(c.secs),
+                c.share * 100.0
+            );
+        }
+    }
+}
@@ -0,0 +1,1444 @@
+use std::fs::{self, File};
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use crate::recorder::{ActivityRecord, Phase, Recorder};
+use crate::storage::SessionMeta;
+use crate::billing::{BillingRules, Period, Rounding};
+use crate::categories::CategoryReport;
+use crate::config::Config;
+use crate::edit::SplitBy;
+use crate::ergonomics::HourlyLoad;
+use crate::heatmap::Heatmap;
+use crate::intervals::Distributions;
+use crate::key_usage::KeyUsage;
+use crate::native_messaging::Browser;
+use crate::ngrams::NgramCounter;
+use crate::qr::QrCode;
+use crate::replay::ReplayOptions;
+use crate::shortcuts::ShortcutReport;
+use crate::timeseries::{Interval, TimeSeries};
+use crate::{analysis, anomaly, billing, categories, compare, edit, export, fatigue, ics, ipc, native_messaging, ngrams, outbox, overlay, replay, resample, shutdown, sqlite, stats, storage, team, timeline};
+
+/// Files and projects listed by `analyze`.
+const TOP_EDITED: usize = 5;
+
+/// Records mouse and keyboard activity per task. Starts the GUI when no
+/// subcommand is given.
+#[derive(Parser)]
+#[command(name = "activity-tracker", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    /// Run without the GUI, reading control commands from stdin and
+    /// answering each with a JSON line on stdout
+    #[arg(long)]
+    pub stdin_control: bool,
+    /// Overwrite existing output files without asking
+    #[arg(long, global = true)]
+    pub force: bool,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Record a task without the GUI until Ctrl-C, `stop` over IPC, or --duration
+    Record {
+        /// Name of the task being recorded; taken from the preset if omitted
+        #[arg(required_unless_present = "preset")]
+        task: Option<String>,
+        /// Start from a preset in the settings file
+        #[arg(long)]
+        preset: Option<String>,
+        /// Project the task belongs to
+        #[arg(long)]
+        project: Option<String>,
+        /// Expected time for the task, e.g. 45m or 1h30m
+        #[arg(long, value_parser = parse_duration)]
+        estimate: Option<u64>,
+        /// Tag the session; may be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Free-form notes kept with the session
+        #[arg(long)]
+        notes: Option<String>,
+        /// Stop automatically after recording this long, e.g. 25m or 1h
+        #[arg(long, value_parser = |text: &str| parse_duration(text).map(Duration::from_secs))]
+        duration: Option<Duration>,
+    },
+    /// Convert a recorded session to another format
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Output file; defaults to the input with the format's extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Drop mouse samples within this many pixels of the simplified path
+        #[arg(long, value_name = "PIXELS")]
+        simplify: Option<f64>,
+        /// Add pointer velocity and acceleration to every sample
+        #[arg(long, conflicts_with = "simplify")]
+        kinematics: bool,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Re-encode a session as CSV, JSON or an SQLite database, keeping its
+    /// metadata. Parquet is not supported, as no Parquet writer is bundled
+    Convert {
+        #[arg(long, value_enum)]
+        to: SessionFormat,
+        /// Output file; defaults to the input with the format's extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV, JSON export or SQLite database
+        file: PathBuf,
+    },
+    /// Join sessions of one task, e.g. after stopping by accident, into one
+    /// with the break between them marked as a gap
+    Merge {
+        /// Output CSV, JSON or SQLite file; defaults to a new session in the
+        /// output directory
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV, JSON or SQLite files
+        #[arg(required = true, num_args = 2..)]
+        files: Vec<PathBuf>,
+    },
+    /// Cut a long session into parts, written next to it as
+    /// `<name>-1.csv`, `<name>-2.csv`, ...
+    Split {
+        #[command(flatten)]
+        by: SplitArgs,
+        /// Session CSV, JSON or SQLite file
+        file: PathBuf,
+    },
+    /// Cut setup and teardown off a session, writing the rest to a new file
+    Trim {
+        /// How much to cut from the start, e.g. 2m
+        #[arg(long, value_parser = parse_duration, default_value = "0s")]
+        start: u64,
+        /// How much to cut from the end
+        #[arg(long, value_parser = parse_duration, default_value = "0s")]
+        end: u64,
+        /// Output CSV, JSON or SQLite file; defaults to `<name>-trimmed` next
+        /// to the input
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV, JSON or SQLite file
+        file: PathBuf,
+    },
+    /// Blank the keys, clicks, pointer and apps in part of a session, e.g.
+    /// while something sensitive was on screen; the file is changed in place
+    Redact {
+        /// Start of the stretch, as time since the session started, e.g. 12m
+        #[arg(long, value_parser = parse_duration)]
+        from: u64,
+        /// End of the stretch, as time since the session started
+        #[arg(long, value_parser = parse_duration)]
+        to: u64,
+        /// Overwrite the file without keeping a backup
+        #[arg(long)]
+        no_backup: bool,
+        /// Session CSV, JSON or SQLite file
+        file: PathBuf,
+    },
+    /// Downsample a session to one row per interval with averages and
+    /// peaks, for sharing and plotting
+    Resample {
+        /// Interval length, e.g. 1s, 10s or 1m
+        #[arg(long, value_parser = parse_duration, default_value = "1s")]
+        every: u64,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+        /// Write the result here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Upgrade session CSV and JSON exports written by older versions to
+    /// the current schema, keeping the originals as `.bak` files
+    Migrate {
+        /// Overwrite the files without keeping a backup
+        #[arg(long)]
+        no_backup: bool,
+        /// Session CSV or JSON files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Print summary statistics for recorded sessions
+    Analyze {
+        /// Print one JSON object per session instead, for scripts
+        #[arg(long)]
+        json: bool,
+        /// Window length for the busiest interval, e.g. 1m or 5m
+        #[arg(long, value_parser = parse_duration, default_value = "1m")]
+        interval: u64,
+        /// Session CSV files
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show fatigue indicators over the course of a session
+    Fatigue {
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Daily keystroke and click load against the strain limits
+    Ergonomics {
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Presses per key and keyboard coverage, for one session or all of them
+    Keys {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+        /// Write the report here instead of stdout; tables are written as CSV
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Time per app category and how much of it was productive
+    Categories {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+        /// Write the report here instead of stdout; tables are written as CSV
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Histograms of the time between key presses and between clicks, and
+    /// of how long keys and buttons are held
+    Intervals {
+        /// Bin width in milliseconds
+        #[arg(long, default_value_t = 100)]
+        bin_ms: u64,
+        /// Longest time binned; anything longer is counted as overflow
+        #[arg(long, default_value_t = 2000)]
+        max_ms: u64,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+        /// Write the report here instead of stdout; tables are written as CSV
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Aggregated key bigram/trigram counts; the key sequence itself is never output
+    Ngrams {
+        /// Keys per n-gram
+        #[arg(short, default_value_t = 2, value_parser = clap::value_parser!(u8).range(1..=5))]
+        n: u8,
+        /// Leave out n-grams seen fewer times than this
+        #[arg(long, default_value_t = 3)]
+        min_count: usize,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+        /// Write the report here instead of stdout; tables are written as CSV
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Which keyboard shortcuts were used, where, and which to try
+    Shortcuts {
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Render a session's mouse heatmap to an image file
+    Heatmap {
+        #[arg(long, value_enum, default_value_t = ImageFormat::Png)]
+        format: ImageFormat,
+        /// Image size as WIDTHxHEIGHT; defaults to the recorded screen area
+        #[arg(long, value_parser = parse_size)]
+        size: Option<(u32, u32)>,
+        /// Output file; defaults to the input with the format's extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Render the cursor path as a transparent PNG frame sequence for
+    /// compositing over a screen recording
+    Overlay {
+        /// Screen size as WIDTHxHEIGHT; defaults to the recorded screen area
+        #[arg(long, value_parser = parse_size)]
+        size: Option<(u32, u32)>,
+        /// Directory for the frames; defaults to the input without its extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Render an animated GIF of the cursor trace for quick sharing
+    Replay {
+        /// Width of the animation in pixels
+        #[arg(long, default_value_t = 640, value_parser = clap::value_parser!(u32).range(1..))]
+        width: u32,
+        /// Playback speed relative to real time
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+        speed: u32,
+        /// PNG screenshot to draw the trace over
+        #[arg(long)]
+        background: Option<PathBuf>,
+        /// Output file; defaults to the input with a .gif extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Export a session's timeline of activity, idle time, gaps and markers as SVG
+    Timeline {
+        /// Width of each activity bar, e.g. 30s or 5m
+        #[arg(long, default_value = "1m", value_parser = parse_duration)]
+        bucket: u64,
+        /// Output file; defaults to the input with a .svg extension
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV file
+        file: PathBuf,
+    },
+    /// Open two sessions side by side
+    Compare {
+        /// First session CSV file
+        first: PathBuf,
+        /// Second session CSV file
+        second: PathBuf,
+    },
+    /// Compare two tasks' sessions statistically: per-session keys, clicks
+    /// and pointer travel per minute, idle ratio and length, with Welch's
+    /// t-test
+    Stats {
+        #[arg(long, value_enum, default_value_t = ReportFormat::Table)]
+        format: ReportFormat,
+        /// Write the report here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// First group: a task name, or a folder of session files
+        first: String,
+        /// Second group: a task name, or a folder of session files
+        second: String,
+    },
+    /// List recorded sessions in the output directory
+    List,
+    /// Compare estimated and actual time per project across all sessions
+    Estimates,
+    /// Total billable hours per project and task as CSV
+    Bill {
+        #[command(flatten)]
+        rules: BillingArgs,
+        /// Write the CSV here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Sessions as an iCalendar (.ics) file, one event per session
+    Calendar {
+        /// Write the calendar here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Sign in to Google so saved sessions are added to Google Calendar
+    /// (see `google_calendar` in the settings file)
+    GoogleAuth,
+    /// Print a QR code of the dashboard link, to open the live view on a
+    /// phone (see `dashboard` in the settings file)
+    Pair,
+    /// Let a browser's activity-tracker extension report the active tab
+    /// (see `native_messaging.rs`)
+    InstallNativeHost {
+        browser: Browser,
+        /// The extension's ID, as shown on the browser's extensions page
+        extension_id: String,
+    },
+    /// Serve the browser extension over native messaging; started by the
+    /// browser
+    #[command(hide = true)]
+    NativeHost {
+        /// What the browser passes, e.g. the extension's origin; unused
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        browser_args: Vec<String>,
+    },
+    /// List integration requests waiting to be retried after a network
+    /// failure
+    Outbox {
+        /// Try them all now instead of waiting for their next attempt
+        #[arg(long)]
+        retry: bool,
+    },
+    /// Combined per-project report from several people's session folders
+    Aggregate {
+        /// Treat sessions of the same task whose starts differ by at most
+        /// this much as one, to absorb clock skew between machines
+        #[arg(long, value_parser = parse_duration, default_value = "5m")]
+        skew: u64,
+        /// Folders to scan; each subfolder is one team member
+        #[arg(required = true)]
+        dirs: Vec<PathBuf>,
+    },
+    /// Earnings from the configured hourly rates per day, week or project
+    Earnings {
+        #[arg(long, value_enum, default_value_t = Period::Week)]
+        by: Period,
+        #[command(flatten)]
+        rules: BillingArgs,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Activity per hour or day and project as time series for dashboards such as Grafana
+    Timeseries {
+        #[arg(long, value_enum, default_value_t = Interval::Day)]
+        interval: Interval,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Csv)]
+        format: ReportFormat,
+        /// Write the series here instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Only include sessions with this tag, e.g. one added by `tag_rules`
+        #[arg(long)]
+        tag: Option<String>,
+        /// Session CSV files; defaults to every session in the output directory
+        files: Vec<PathBuf>,
+    },
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate the script for
+        shell: Shell,
+    },
+    /// Print the man page (roff) to stdout
+    Man,
+}
+
+#[derive(Args)]
+pub struct BillingArgs {
+    /// Round to multiples of this, e.g. 15m or 6m
+    #[arg(long, value_parser = parse_duration, default_value = "15m")]
+    increment: u64,
+    #[arg(long, value_enum, default_value_t = Rounding::Up)]
+    rounding: Rounding,
+    /// Round every session separately instead of each task's total
+    #[arg(long)]
+    per_session: bool,
+}
+
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+pub struct SplitArgs {
+    /// Start a new part every this long, e.g. 30m or 1h
+    #[arg(long, value_parser = parse_duration)]
+    every: Option<u64>,
+    /// Start a new part after a break without input this long, e.g. 5m
+    #[arg(long, value_parser = parse_duration)]
+    idle: Option<u64>,
+    /// Start a new part at every marker
+    #[arg(long)]
+    markers: bool,
+}
+
+impl From<SplitArgs> for SplitBy {
+    fn from(args: SplitArgs) -> Self {
+        match (args.every, args.idle) {
+            (Some(secs), _) => SplitBy::Duration(secs),
+            (None, Some(secs)) => SplitBy::Idle(secs),
+            (None, None) => SplitBy::Marker,
+        }
+    }
+}
+
+impl From<BillingArgs> for BillingRules {
+    fn from(args: BillingArgs) -> Self {
+        BillingRules {
+            increment_secs: args.increment,
+            rounding: args.rounding,
+            per_session: args.per_session,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+}
+
+impl ImageFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Svg => "svg",
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+/// Formats a whole session can be stored in.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SessionFormat {
+    Csv,
+    Json,
+    Sqlite,
+}
+
+impl SessionFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SessionFormat::Csv => "csv",
+            SessionFormat::Json => "json",
+            SessionFormat::Sqlite => "db",
+        }
+    }
+
+    /// The format of a file, judged by its extension.
+    fn of(path: &Path) -> Result<Self, String> {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("csv") => Ok(SessionFormat::Csv),
+            Some("json") => Ok(SessionFormat::Json),
+            Some("db" | "sqlite" | "sqlite3") => Ok(SessionFormat::Sqlite),
+            Some("parquet") => Err(format!("{}: Parquet is not supported; convert to CSV, JSON or SQLite", path.display())),
+            _ => Err(format!("Can't tell the format of {} from its extension", path.display())),
+        }
+    }
+}
+
+pub fn run(command: Commands, force: bool) -> Result<(), String> {
+    match command {
+        Commands::Record { task, preset, project, estimate, tags, notes, duration } => {
+            let mut meta = match &preset {
+                Some(name) => Config::load()?.preset(name)?.meta(),
+                None => SessionMeta::default(),
+            };
+            if let Some(task) = task {
+                meta.task = task;
+            }
+            meta.project = project.or(meta.project);
+            meta.estimate_secs = estimate.or(meta.estimate_secs);
+            meta.tags.extend(tags);
+            meta.notes = notes;
+            record(meta, duration)
+        }
+        Commands::Export { format, output, simplify, kinematics, file } => {
+            let mut records = storage::load_csv(&file)?;
+            let interval = storage::load_meta(&file)?.sample_interval();
+            if let Some(tolerance) = simplify {
+                records = export::simplify_path(records, tolerance);
+            }
+            let output = writable(output.unwrap_or_else(|| file.with_extension(format.extension())), force)?;
+            match format {
+                ExportFormat::Json => export::write_json(&records, kinematics.then_some(interval), &output)?,
+            }
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Convert { to, output, file } => {
+            let (meta, records) = load_session(&file)?;
+            let output = output.unwrap_or_else(|| file.with_extension(to.extension()));
+            if output == file {
+                return Err(format!("{} is already {}", file.display(), to.extension()));
+            }
+            let output = writable(output, force)?;
+            save_session(&output, to, &meta, &records)?;
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Merge { output, files } => {
+            let sessions = files.iter().map(|file| load_session(file)).collect::<Result<Vec<_>, _>>()?;
+            let (meta, records) = edit::merge(sessions)?;
+            let output = match output {
+                Some(output) => {
+                    let output = writable(output, force)?;
+                    save_session(&output, SessionFormat::of(&output)?, &meta, &records)?;
+                    output
+                }
+                None => {
+                    let config = Config::load()?;
+                    let output = storage::save_csv(None, &meta, &config.filename_template, &records)?;
+                    storage::save_meta(&output, &meta)?;
+                    output
+                }
+            };
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Split { by, file } => {
+            let format = SessionFormat::of(&file)?;
+            let (meta, records) = load_session(&file)?;
+            let parts = edit::split(records, by.into());
+            if parts.len() < 2 {
+                return Err(format!("{} has nothing to split at", file.display()));
+            }
+            let stem = file.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+            for (i, records) in parts.iter().enumerate() {
+                let output = file.with_file_name(format!("{}-{}.{}", stem, i + 1, format.extension()));
+                let output = writable(output, force)?;
+                save_session(&output, format, &meta, records)?;
+                println!("{}", output.display());
+            }
+            Ok(())
+        }
+        Commands::Trim { start, end, output, file } => {
+            let (meta, records) = load_session(&file)?;
+            let duration = analysis::summarize(&records).duration_secs();
+            let records = edit::trim(records, start, duration.saturating_sub(end));
+            if records.is_empty() {
+                return Err("Nothing left after trimming".to_string());
+            }
+            let output = output.unwrap_or_else(|| {
+                let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+                file.with_file_name(format!("{}-trimmed.{}", stem, file.extension().unwrap_or_default().to_string_lossy()))
+            });
+            let output = writable(output, force)?;
+            save_session(&output, SessionFormat::of(&output)?, &meta, &records)?;
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Redact { from, to, no_backup, file } => {
+            if to < from {
+                return Err("--to is before --from".to_string());
+            }
+            let (meta, mut records) = load_session(&file)?;
+            edit::redact(&mut records, from, to);
+            let backup = if no_backup { None } else { Some(edit::backup(&file)?) };
+            save_session(&file, SessionFormat::of(&file)?, &meta, &records)?;
+            println!("Redacted {} to {} in {}", format_duration(from), format_duration(to), file.display());
+            if let Some(backup) = backup {
+                println!("The original is kept as {}", backup.display());
+            }
+            Ok(())
+        }
+        Commands::Resample { every, format, output, file } => {
+            let interval = storage::load_meta(&file)?.sample_interval();
+            let buckets = resample::resample(&storage::load_csv(&file)?, every, interval);
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Table | ReportFormat::Csv => resample::write_csv(&buckets, &mut out),
+                ReportFormat::Json => resample::write_json(&buckets, &mut out),
+            }
+            .map_err(|e| format!("Failed to write resampled session: {}", e))
+        }
+        Commands::Migrate { no_backup, files } => {
+            let files = if files.is_empty() { old_sessions()? } else { files };
+            let mut failed = false;
+            for file in files {
+                match migrate(&file, !no_backup) {
+                    Ok(true) => println!("Upgraded {}", file.display()),
+                    Ok(false) => println!("{} is up to date", file.display()),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        failed = true;
+                    }
+                }
+            }
+            if failed { Err("Some files could not be upgraded".to_string()) } else { Ok(()) }
+        }
+        Commands::Heatmap { format, size, output, file } => {
+            let heatmap = Heatmap::new(&storage::load_csv(&file)?, size)?;
+            let output = writable(output.unwrap_or_else(|| file.with_extension(format.extension())), force)?;
+            match format {
+                ImageFormat::Png => heatmap.write_png(&output)?,
+                ImageFormat::Svg => heatmap.write_svg(&output)?,
+            }
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Overlay { size, output, file } => {
+            let records = storage::load_csv(&file)?;
+            let interval = storage::load_meta(&file)?.sample_interval();
+            let output = writable(output.unwrap_or_else(|| file.with_extension("")), force)?;
+            let frames = overlay::write_frames(&records, interval, size, &output)?;
+            let fps = (1.0 / interval.as_secs_f64()).round();
+            println!("{} frames at {} fps in {}", frames, fps, output.display());
+            Ok(())
+        }
+        Commands::Replay { width, speed, background, output, file } => {
+            let records = storage::load_csv(&file)?;
+            let output = writable(output.unwrap_or_else(|| file.with_extension("gif")), force)?;
+            let options = ReplayOptions { width, speed, background: background.as_deref() };
+            replay::write_gif(&records, &options, &output)?;
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Timeline { bucket, output, file } => {
+            let records = storage::load_csv(&file)?;
+            let output = writable(output.unwrap_or_else(|| file.with_extension("svg")), force)?;
+            timeline::write_svg(&records, bucket, &output)?;
+            println!("{}", output.display());
+            Ok(())
+        }
+        Commands::Analyze { json, interval, files } => {
+            let config = Config::load()?;
+            let many = files.len() > 1;
+            for file in files {
+                let records = storage::load_csv(&file)?;
+                let meta = storage::load_meta(&file)?;
+                let summary = analysis::summarize(&records);
+                let busiest = analysis::busiest_interval(&records, interval);
+                if json {
+                    let busiest = busiest.map(|(start, active)| {
+                        serde_json::json!({ "start": start, "secs": interval, "active_samples": active })
+                    });
+                    let report = serde_json::json!({
+                        "file": file,
+                        "task": meta.task,
+                        "project": meta.project,
+                        "start": summary.start,
+                        "duration_secs": summary.duration_secs(),
+                        "tracked_secs": summary.tracked_secs(),
+                        "samples": summary.samples,
+                        "key_presses": summary.key_presses,
+                        "clicks": summary.clicks,
+                        "mouse_distance": summary.mouse_distance,
+                        "idle_ratio": summary.idle_ratio(),
+                        "gap_secs": summary.gap_secs(),
+                        "markers": summary.markers.len(),
+                        "busiest_interval": busiest,
+                    });
+                    println!("{}", report);
+                    continue;
+                }
+                if many {
+                    println!("== {} ==", file.display());
+                }
+                let sample_interval = meta.sample_interval();
+                print_summary(&summary, sample_interval);
+                if let Some((start, active)) = busiest {
+                    let share = active as f64 / (interval.max(1) as f64 / sample_interval.as_secs_f64()) * 100.0;
+                    println!("Busiest:         {} for {} ({:.0}% active)", start, format_duration(interval), share.min(100.0));
+                }
+                print_estimate(&meta, &summary);
+                print_distractions(&config, &records, &summary);
+                for (instance, secs) in analysis::instance_time(&records) {
+                    println!("Instance:        {} {}", format_duration(secs), instance);
+                }
+                for (project, secs) in analysis::project_time(&records).into_iter().take(TOP_EDITED) {
+                    println!("Project:         {} {}", format_duration(secs), project);
+                }
+                for (file, secs) in analysis::file_time(&records).into_iter().take(TOP_EDITED) {
+                    println!("File:            {} {}", format_duration(secs), file);
+                }
+                for anomaly in anomaly::detect(&records) {
+                    println!("Warning:         {} ({}-{})", anomaly.describe(), anomaly.start, anomaly.end);
+                }
+            }
+            Ok(())
+        }
+        Commands::Fatigue { file } => {
+            let config = Config::load()?;
+            let records = storage::load_csv(&file)?;
+            let windows = fatigue::windows(&records, config.fatigue.window_secs());
+            println!("start\tkeys/min\tcorrections\tmean pause");
+            for window in &windows {
+                println!(
+                    "{}\t{:.1}\t{:.1}%\t{:.1}s",
+                    window.start,
+                    window.keys_per_minute,
+                    window.correction_ratio * 100.0,
+                    window.mean_pause_secs
+                );
+            }
+            for warning in fatigue::warnings(&windows, &config.fatigue) {
+                println!("Warning: {}", warning);
+            }
+            Ok(())
+        }
+        Commands::Ergonomics { files } => {
+            let config = Config::load()?;
+            let mut load = HourlyLoad::default();
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => load.add_session(&records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+
+            println!("date\tkeys\tclicks\tpeak keys/h\tpeak clicks/h");
+            for day in load.days() {
+                println!(
+                    "{}\t{}\t{}\t{}\t{}",
+                    day.date, day.total.keys, day.total.clicks, day.peak_hour.keys, day.peak_hour.clicks
+                );
+                for warning in day.warnings(&config.ergonomics) {
+                    println!("  Over limit: {}", warning);
+                }
+            }
+            Ok(())
+        }
+        Commands::Keys { format, output, files } => {
+            let mut sessions = Vec::new();
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => sessions.push(records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            let usage = KeyUsage::from_sessions(sessions.iter().map(Vec::as_slice));
+
+            if format == ReportFormat::Table && output.is_none() {
+                usage.print();
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Json => usage.write_json(&mut out),
+                ReportFormat::Table | ReportFormat::Csv => usage.write_csv(&mut out),
+            }
+            .map_err(|e| format!("Failed to write key usage: {}", e))
+        }
+        Commands::Categories { format, output, files } => {
+            let rules = categories::with_defaults(Config::load()?.categories);
+            let mut sessions = Vec::new();
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => sessions.push(records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            let report = CategoryReport::from_sessions(&rules, sessions.iter().map(Vec::as_slice));
+
+            if format == ReportFormat::Table && output.is_none() {
+                report.print();
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Json => report.write_json(&mut out),
+                ReportFormat::Table | ReportFormat::Csv => report.write_csv(&mut out),
+            }
+            .map_err(|e| format!("Failed to write categories: {}", e))
+        }
+        Commands::Intervals { bin_ms, max_ms, format, output, files } => {
+            let mut sessions = Vec::new();
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => sessions.push(records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            let distributions = Distributions::from_sessions(sessions.iter().map(Vec::as_slice), bin_ms, max_ms);
+
+            if format == ReportFormat::Table && output.is_none() {
+                distributions.print();
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Json => distributions.write_json(&mut out),
+                ReportFormat::Table | ReportFormat::Csv => distributions.write_csv(&mut out),
+            }
+            .map_err(|e| format!("Failed to write histograms: {}", e))
+        }
+        Commands::Ngrams { n, min_count, format, output, files } => {
+            let mut counter = NgramCounter::new(n.into());
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => counter.add_session(&records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            let table = counter.table(min_count);
+
+            if format == ReportFormat::Table && output.is_none() {
+                for row in &table {
+                    println!("{:<40} {:>8}", row.ngram, row.count);
+                }
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Json => ngrams::write_json(&table, &mut out),
+                ReportFormat::Table | ReportFormat::Csv => ngrams::write_csv(&table, &mut out),
+            }
+            .map_err(|e| format!("Failed to write n-grams: {}", e))
+        }
+        Commands::Shortcuts { files } => {
+            let mut report = ShortcutReport::default();
+            for path in session_files(files)? {
+                match storage::load_csv(&path) {
+                    Ok(records) => report.add_session(&records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+
+            for (shortcut, count) in report.totals() {
+                println!("{}\t{}", shortcut, count);
+                for ((_, app), count) in report.usage.iter().filter(|((s, _), _)| *s == shortcut) {
+                    println!("  {}\t{}", app, count);
+                }
+            }
+            for suggestion in report.suggestions() {
+                println!("Tip: {}", suggestion);
+            }
+            Ok(())
+        }
+        Commands::Compare { first, second } => compare::run(&first, &second),
+        Commands::Stats { format, output, first, second } => {
+            let a = session_group(&first)?;
+            let b = session_group(&second)?;
+            let comparison = stats::Comparison::new(first, &a, second, &b);
+            if format == ReportFormat::Table && output.is_none() {
+                comparison.print();
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Table | ReportFormat::Csv => comparison.write_csv(&mut out),
+                ReportFormat::Json => comparison.write_json(&mut out),
+            }
+            .map_err(|e| format!("Failed to write comparison: {}", e))
+        }
+        Commands::List => {
+            for path in storage::list_sessions()? {
+                match storage::load_csv(&path) {
+                    Ok(records) => {
+                        let summary = analysis::summarize(&records);
+                        println!(
+                            "{}\t{} samples\t{}",
+                            path.display(),
+                            summary.samples,
+                            format_duration(summary.duration_secs())
+                        );
+                    }
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+            Ok(())
+        }
+        Commands::Estimates => {
+            let sessions = load_summaries(storage::list_sessions()?);
+            println!("project\tsessions\testimated\tactual\tmean error");
+            for accuracy in analysis::estimate_accuracy(&sessions) {
+                println!(
+                    "{}\t{}\t{}\t{}\t{:.0}%",
+                    accuracy.project,
+                    accuracy.sessions,
+                    format_duration(accuracy.estimated_secs),
+                    format_duration(accuracy.actual_secs),
+                    accuracy.mean_error * 100.0
+                );
+            }
+            Ok(())
+        }
+        Commands::Bill { rules, output, files } => {
+            let config = Config::load()?;
+            let lines = billing::bill(&load_summaries(session_files(files)?), &rules.into(), &config.billing);
+            let mut out = report_writer(output, force)?;
+            billing::write_csv(&lines, &mut out).map_err(|e| format!("Failed to write billing report: {}", e))
+        }
+        Commands::Calendar { output, files } => {
+            let sessions = load_summaries(session_files(files)?);
+            let mut out = report_writer(output, force)?;
+            ics::write_calendar(&sessions, &mut out).map_err(|e| format!("Failed to write calendar: {}", e))
+        }
+        Commands::GoogleAuth => {
+            let config = Config::load()?;
+            let google = config
+                .google_calendar
+                .ok_or("Add a google_calendar section with your OAuth client to the settings file first")?;
+            google.authorize()?;
+            println!("Signed in; saved sessions will be added to Google Calendar");
+            Ok(())
+        }
+        Commands::Outbox { retry } => {
+            if retry {
+                let (delivered, queued) = outbox::retry(true);
+                println!("Delivered {}, {} still queued", delivered, queued);
+            }
+            let pending = outbox::pending();
+            if pending.is_empty() && !retry {
+                println!("Nothing waiting to be sent");
+            }
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            for delivery in pending {
+                println!(
+                    "{:<20} {:<4} {}  attempts {}, next in {}\n    {}",
+                    delivery.integration,
+                    delivery.method,
+                    delivery.url,
+                    delivery.attempts,
+                    format_duration(delivery.next_attempt.saturating_sub(now)),
+                    delivery.last_error
+                );
+            }
+            Ok(())
+        }
+        Commands::InstallNativeHost { browser, extension_id } => {
+            let manifest = native_messaging::install(browser, &extension_id)?;
+            println!("Installed {}; restart the browser to connect the extension", manifest.display());
+            Ok(())
+        }
+        Commands::NativeHost { .. } => native_messaging::run(),
+        Commands::Pair => {
+            let dashboard = Config::load()?
+                .dashboard
+                .ok_or("Add a dashboard section to the settings file first")?;
+            if dashboard.local_only() {
+                eprintln!("The dashboard only accepts connections from this computer; bind it to 0.0.0.0 to reach it from a phone");
+            }
+            let url = dashboard.url();
+            print!("{}", QrCode::encode(&url)?.to_terminal());
+            println!("{}", url);
+            Ok(())
+        }
+        Commands::Aggregate { skew, dirs } => {
+            let (sessions, errors) = team::collect(&dirs);
+            for e in errors {
+                eprintln!("{}", e);
+            }
+            let (sessions, duplicates) = team::dedupe(sessions, skew);
+            for (duplicate, original) in &duplicates {
+                eprintln!("Skipping {} (duplicate of {})", duplicate.display(), original.display());
+            }
+
+            for project in team::report(&sessions) {
+                println!(
+                    "{}\t{} sessions\t{}",
+                    project.project,
+                    project.sessions,
+                    format_duration(project.tracked_secs)
+                );
+                for (member, secs) in &project.members {
+                    println!("  {}\t{}", member, format_duration(*secs));
+                }
+            }
+            Ok(())
+        }
+        Commands::Earnings { by, rules, files } => {
+            let config = Config::load()?;
+            let sessions = load_summaries(session_files(files)?);
+            let currency = &config.billing.currency;
+            println!("period\tbillable hours\tearnings");
+            for earnings in billing::earnings(sessions, by, &rules.into(), &config.billing) {
+                let note = match earnings.unpriced_lines {
+                    0 => String::new(),
+                    n => format!("\t({} task(s) without a rate)", n),
+                };
+                println!(
+                    "{}\t{}\t{:.2} {}{}",
+                    earnings.label,
+                    billing::hours(earnings.billable_secs),
+                    earnings.amount,
+                    currency,
+                    note
+                );
+            }
+            Ok(())
+        }
+        Commands::Timeseries { interval, format, output, tag, files } => {
+            let mut series = TimeSeries::new(interval, Config::load()?.distraction_apps);
+            for path in session_files(files)? {
+                match storage::load_csv(&path).and_then(|records| Ok((storage::load_meta(&path)?, records))) {
+                    Ok((meta, _)) if tag.as_ref().is_some_and(|tag| !meta.tags.contains(tag)) => {}
+                    Ok((meta, records)) => series.add_session(&meta, &records),
+                    Err(e) => eprintln!("{}", e),
+                }
+            }
+
+            if format == ReportFormat::Table && output.is_none() {
+                println!("time\tproject\ttracked\tactive\tkeys\tclicks\tdistracted");
+                for p in series.points() {
+                    println!(
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        p.time,
+                        p.project,
+                        format_duration(p.tracked_secs as u64),
+                        format_duration(p.active_secs as u64),
+                        p.key_presses,
+                        p.clicks,
+                        format_duration(p.distraction_secs as u64)
+                    );
+                }
+                return Ok(());
+            }
+            let mut out = report_writer(output, force)?;
+            match format {
+                ReportFormat::Json => series.write_json(&mut out),
+                ReportFormat::Table | ReportFormat::Csv => series.write_csv(&mut out),
+            }
+            .map_err(|e| format!("Failed to write time series: {}", e))
+        }
+        Commands::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut io::stdout());
+            Ok(())
+        }
+        Commands::Man => clap_mangen::Man::new(Cli::command())
+            .render(&mut io::stdout())
+            .map_err(|e| format!("Failed to render man page: {}", e)),
+    }
+}
+
+fn record(meta: SessionMeta, duration: Option<Duration>) -> Result<(), String> {
+    let task = meta.task.clone();
+    let recorder = Recorder::default();
+    shutdown::install_signal_handler(recorder.clone()).map_err(|e| e.to_string())?;
+    if let Err(e) = ipc::spawn_server(recorder.clone(), || {}) {
+        eprintln!("Control socket unavailable ({}): {}", ipc::endpoint_display(), e);
+    }
+    outbox::spawn_worker();
+    let config = Config::load().unwrap_or_default();
+    if let Some(dashboard) = config.dashboard {
+        match dashboard.spawn(recorder.clone()) {
+            Ok(()) => eprintln!("Dashboard at http://{}/", dashboard.bind),
+            Err(e) => eprintln!("Dashboard unavailable ({}): {}", dashboard.bind, e),
+        }
+    }
+    if let Some(editor) = config.editor {
+        if let Err(e) = editor.spawn(recorder.clone()) {
+            eprintln!("Editor endpoint unavailable ({}): {}", editor.bind, e);
+        }
+    }
+
+    recorder.start(meta)?;
+    eprintln!("{}", recorder.status());
+
+    let mut recording_since: Option<Instant> = None;
+    let mut last_status = recorder.status();
+    loop {
+        // Surface alerts and errors raised while recording
+        let status = recorder.status();
+        if status != last_status {
+            eprintln!("{}", status);
+            last_status = status;
+        }
+
+        match recorder.phase() {
+            // Stopped from outside, e.g. over IPC
+            Phase::Idle => break,
+            Phase::Countdown(_) => {}
+            Phase::Recording | Phase::Paused(_) => {
+                let since = *recording_since.get_or_insert_with(|| {
+                    eprintln!("Recording \"{}\"... press Ctrl-C to stop.", task);
+                    Instant::now()
+                });
+                if duration.is_some_and(|d| since.elapsed() >= d) {
+                    recorder.stop()?;
+                    break;
+                }
+            }
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    recorder.wait_for_integrations();
+    let status = recorder.status();
+    if status != last_status {
+        eprintln!("{}", status);
+    }
+    Ok(())
+}
+
+fn print_summary(summary: &analysis::Summary, interval: Duration) {
+    println!("Duration:        {}", format_duration(summary.duration_secs()));
+    println!("Samples:         {}", summary.samples);
+    println!("Key presses:     {}", summary.key_presses);
+    println!("Clicks:          {}", summary.clicks);
+    if let (Some(settle), Some(corrections)) = (summary.mean_settle_secs(interval), summary.corrections_per_click()) {
+        println!("Time to click:   {:.2}s after the pointer stops", settle);
+        println!("Corrections:     {:.1} per click", corrections);
+    }
+    println!("Mouse distance:  {:.0} px", summary.mouse_distance);
+    println!("Idle:            {:.1}%", summary.idle_ratio() * 100.0);
+    println!("Gaps:            {} ({})", summary.gaps.len(), format_duration(summary.gap_secs()));
+    for (start, end, reason) in &summary.gaps {
+        println!("Gap:             {}-{} {}", start, end, reason.as_str());
+    }
+    for (timestamp, label) in &summary.markers {
+        println!("Marker:          {} {}", timestamp, label);
+    }
+}
+
+/// Parses an image size such as "1920x1080".
+fn parse_size(input: &str) -> Result<(u32, u32), String> {
+    let invalid = || format!("invalid size {:?}, expected WIDTHxHEIGHT", input);
+    let (width, height) = input.split_once('x').ok_or_else(invalid)?;
+    let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+    let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+    if width == 0 || height == 0 {
+        return Err(invalid());
+    }
+    Ok((width, height))
+}
+
+/// `path` if nothing is there yet, or the user agreed to overwrite it:
+/// with `--force`, or when asked on a terminal.
+fn writable(path: PathBuf, force: bool) -> Result<PathBuf, String> {
+    if force || !path.exists() {
+        return Ok(path);
+    }
+    if io::stdin().is_terminal() {
+        eprint!("{} exists. Overwrite? [y/N] ", path.display());
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer).map_err(|e| e.to_string())?;
+        if matches!(answer.trim(), "y" | "Y" | "yes") {
+            return Ok(path);
+        }
+    }
+    Err(format!("{} already exists; pass --force to overwrite it", path.display()))
+}
+
+/// The output file, or stdout when none is given.
+fn report_writer(output: Option<PathBuf>, force: bool) -> Result<Box<dyn io::Write>, String> {
+    match output.map(|path| writable(path, force)).transpose()? {
+        Some(path) => File::create(&path)
+            .map(|file| Box::new(file) as Box<dyn io::Write>)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e)),
+        None => Ok(Box::new(io::stdout())),
+    }
+}
+
+/// Metadata and records of a session CSV, JSON export or SQLite database.
+fn load_session(path: &Path) -> Result<(SessionMeta, Vec<ActivityRecord>), String> {
+    match SessionFormat::of(path)? {
+        SessionFormat::Csv => Ok((storage::load_meta(path)?, storage::load_csv(path)?)),
+        SessionFormat::Json => Ok((storage::load_meta(path)?, export::load_json(path)?)),
+        SessionFormat::Sqlite => sqlite::read(path),
+    }
+}
+
+/// Writes a session in `format`, with its metadata next to it unless the
+/// format holds it.
+fn save_session(path: &Path, format: SessionFormat, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+    match format {
+        SessionFormat::Csv => storage::write_csv_file(path, records)?,
+        SessionFormat::Json => export::write_json(records, None, path)?,
+        SessionFormat::Sqlite => {
+            // The database is created from scratch
+            if path.exists() {
+                fs::remove_file(path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))?;
+            }
+            return sqlite::write(path, meta, records);
+        }
+    }
+    storage::save_meta(path, meta).map_err(String::from)
+}
+
+/// Sessions in the output directory that are not yet in the current schema.
+fn old_sessions() -> Result<Vec<PathBuf>, String> {
+    Ok(storage::list_sessions()?
+        .into_iter()
+        .filter(|path| !storage::is_current_schema(path))
+        .collect())
+}
+
+/// Rewrites a session CSV or JSON export in the current schema, filling in
+/// defaults for missing columns. Returns whether anything changed.
+fn migrate(path: &Path, backup: bool) -> Result<bool, String> {
+    let json = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let records = if json {
+        export::load_json(path)?
+    } else if storage::is_current_schema(path) {
+        return Ok(false);
+    } else {
+        storage::load_csv(path)?
+    };
+
+    if backup {
+        edit::backup(path)?;
+    }
+    if json {
+        export::write_json(&records, None, path)?;
+    } else {
+        storage::write_csv_file(path, &records)?;
+    }
+    Ok(true)
+}
+
+/// The given session files, or every session in the output directory.
+fn session_files(files: Vec<PathBuf>) -> Result<Vec<PathBuf>, String> {
+    if files.is_empty() {
+        storage::list_sessions()
+    } else {
+        Ok(files)
+    }
+}
+
+/// Summaries of the sessions in a folder, or else of every session of the
+/// task with that name.
+fn session_group(group: &str) -> Result<Vec<analysis::Summary>, String> {
+    let dir = Path::new(group);
+    let sessions = if dir.is_dir() {
+        let entries = fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+        let paths = entries
+            .filter_map(Result::ok)
+            .map(|entry| entry.path())
+            .filter(|path| storage::is_session_file(path))
+            .collect();
+        load_summaries(paths)
+    } else {
+        let mut sessions = load_summaries(storage::list_sessions()?);
+        sessions.retain(|(meta, _)| meta.task == group);
+        sessions
+    };
+    if sessions.is_empty() {
+        return Err(format!("No sessions found for \"{}\"", group));
+    }
+    Ok(sessions.into_iter().map(|(_, summary)| summary).collect())
+}
+
+/// Loads metadata and summaries for the sessions, reporting and skipping
+/// those that cannot be read.
+fn load_summaries(paths: Vec<PathBuf>) -> Vec<(SessionMeta, analysis::Summary)> {
+    let mut sessions = Vec::new();
+    for path in paths {
+        let loaded = storage::load_csv(&path).and_then(|records| Ok((storage::load_meta(&path)?, records)));
+        match loaded {
+            Ok((meta, records)) => sessions.push((meta, analysis::summarize(&records))),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    sessions
+}
+
+fn print_distractions(config: &Config, records: &[ActivityRecord], summary: &analysis::Summary) {
+    if config.distraction_apps.is_empty() {
+        return;
+    }
+    let apps: Vec<(String, u64)> = analysis::app_time(records)
+        .into_iter()
+        .filter(|(app, _)| config.is_distraction(app))
+        .collect();
+    let total: u64 = apps.iter().map(|(_, secs)| secs).sum();
+    let tracked = summary.tracked_secs().max(1);
+    println!(
+        "Distractions:    {} ({:.1}% of tracked time)",
+        format_duration(total),
+        total as f64 / tracked as f64 * 100.0
+    );
+    for (app, secs) in apps {
+        println!("Distraction:     {} {}", format_duration(secs), app);
+    }
+}
+
+fn print_estimate(meta: &SessionMeta, summary: &analysis::Summary) {
+    let Some(estimate) = meta.estimate_secs else {
+        return;
+    };
+    let actual = summary.tracked_secs();
+    let verdict = if actual > estimate { "over" } else { "under" };
+    println!(
+        "Estimate:        {} ({} by {})",
+        format_duration(estimate),
+        verdict,
+        format_duration(actual.abs_diff(estimate))
+    );
+}
+
+/// Parses `1h30m`, `45m` or `90s` into seconds. A bare number is rejected,
+/// as it could mean either seconds or minutes.
+pub fn parse_duration(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    if text.parse::<u64>().is_ok() {
+        return Err(format!("duration \"{}\" needs a unit, e.g. {}s or {}m", text, text, text));
+    }
+
+    let mut secs: u64 = 0;
+    let mut number = String::new();
+    for c in text.chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("invalid duration \"{}\"", text)),
+        };
+        let value: u64 = number.parse().map_err(|_| format!("invalid duration \"{}\"", text))?;
+        secs = value
+            .checked_mul(unit)
+            .and_then(|value| secs.checked_add(value))
+            .ok_or_else(|| format!("duration \"{}\" is too long", text))?;
+        number.clear();
+    }
+    if !number.is_empty() || text.is_empty() {
+        return Err(format!("invalid duration \"{}\"", text));
+    }
+
+    Ok(secs)
+}
+
+pub fn format_duration(secs: u64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Byte count in the largest fitting binary unit, e.g. "3.4 MiB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_duration_units() {
+        assert_eq!(parse_duration("45s"), Ok(45));
+        assert_eq!(parse_duration("25m"), Ok(1500));
+        assert_eq!(parse_duration(" 1h30m "), Ok(5400));
+        assert_eq!(parse_duration("0s"), Ok(0));
+    }
+
+    #[test]
+    fn parse_duration_rejects_malformed() {
+        assert!(parse_duration("30").unwrap_err().contains("needs a unit"));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("m").is_err());
+        assert!(parse_duration("5x").is_err());
+        assert!(parse_duration("1h30").is_err());
+        assert!(parse_duration("99999999999999999999h").is_err());
+        assert!(parse_duration("9999999999999999h").unwrap_err().contains("too long"));
+    }
+
+    #[test]
+    fn record_duration_option() {
+        let cli = Cli::try_parse_from(["activity-tracker", "record", "task", "--duration", "1h5m"]).unwrap();
+        match cli.command {
+            Some(Commands::Record { duration, .. }) => assert_eq!(duration, Some(Duration::from_secs(3900))),
+            _ => panic!("expected the record command"),
+        }
+    }
+
+    #[test]
+    fn format_duration_clock() {
+        assert_eq!(format_duration(0), "00:00:00");
+        assert_eq!(format_duration(3723), "01:02:03");
+    }
+}
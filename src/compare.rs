@@ -0,0 +1,157 @@
+use std::path::Path;
+
+use eframe::egui;
+use egui_plot::{Legend, Line, Plot};
+
+use crate::analysis::{self, Summary};
+use crate::cli::format_duration;
+use crate::storage;
+
+/// Resolution of the activity curves.
+const BUCKET_SECS: u64 = 60;
+const TOP_KEYS: usize = 15;
+
+struct Side {
+    name: String,
+    summary: Summary,
+    series: Vec<usize>,
+    speed: Vec<f64>,
+    top_keys: Vec<(String, usize)>,
+}
+
+impl Side {
+    fn load(path: &Path) -> Result<Self, String> {
+        let records = storage::load_csv(path)?;
+        let interval = storage::load_meta(path)?.sample_interval();
+        Ok(Side {
+            name: path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+            summary: analysis::summarize(&records),
+            series: analysis::activity_series(&records, BUCKET_SECS),
+            speed: analysis::speed_series(&records, BUCKET_SECS, interval),
+            top_keys: analysis::key_frequency(&records)
+                .into_iter()
+                .take(TOP_KEYS)
+                .map(|(key, count)| (format!("{:?}", key), count))
+                .collect(),
+        })
+    }
+
+    fn stats(&self) -> [(&'static str, String); 6] {
+        let summary = &self.summary;
+        [
+            ("Duration", format_duration(summary.duration_secs())),
+            ("Samples", summary.samples.to_string()),
+            ("Key presses", summary.key_presses.to_string()),
+            ("Keys per minute", format!("{:.1}", self.keys_per_minute())),
+            ("Mouse distance", format!("{:.0} px", summary.mouse_distance)),
+            ("Idle", format!("{:.1}%", summary.idle_ratio() * 100.0)),
+        ]
+    }
+
+    fn keys_per_minute(&self) -> f64 {
+        match self.summary.tracked_secs() {
+            0 => 0.0,
+            secs => self.summary.key_presses as f64 * 60.0 / secs as f64,
+        }
+    }
+}
+
+/// Two sessions side by side: headline stats, activity curves aligned on
+/// their starts, and each session's most pressed keys.
+pub struct ComparisonView {
+    sides: [Side; 2],
+}
+
+impl eframe::App for ComparisonView {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let [a, b] = &self.sides;
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Session Comparison");
+            ui.add_space(10.0);
+
+            egui::Grid::new("stats").striped(true).num_columns(3).show(ui, |ui| {
+                ui.label("");
+                ui.strong(&a.name);
+                ui.strong(&b.name);
+                ui.end_row();
+
+                for ((label, value_a), (_, value_b)) in a.stats().into_iter().zip(b.stats()) {
+                    ui.label(label);
+                    ui.label(value_a);
+                    ui.label(value_b);
+                    ui.end_row();
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.label("Active samples per minute");
+            Plot::new("activity")
+                .legend(Legend::default())
+                .height(200.0)
+                .x_axis_label("minutes from start")
+                .show(ui, |plot_ui| {
+                    for side in [a, b] {
+                        let points: Vec<[f64; 2]> = side
+                            .series
+                            .iter()
+                            .enumerate()
+                            .map(|(minute, &count)| [minute as f64, count as f64])
+                            .collect();
+                        plot_ui.line(Line::new(points).name(&side.name));
+                    }
+                });
+
+            ui.add_space(10.0);
+            ui.label("Mean pointer speed per minute (px/s)");
+            Plot::new("speed")
+                .legend(Legend::default())
+                .height(150.0)
+                .x_axis_label("minutes from start")
+                .show(ui, |plot_ui| {
+                    for side in [a, b] {
+                        let points: Vec<[f64; 2]> = side
+                            .speed
+                            .iter()
+                            .enumerate()
+                            .map(|(minute, &speed)| [minute as f64, speed])
+                            .collect();
+                        plot_ui.line(Line::new(points).name(&side.name));
+                    }
+                });
+
+            ui.add_space(10.0);
+            ui.columns(2, |columns| {
+                for (ui, side) in columns.iter_mut().zip([a, b]) {
+                    ui.strong(format!("Top keys: {}", side.name));
+                    let max = side.top_keys.first().map_or(1, |(_, count)| *count).max(1);
+                    for (key, count) in &side.top_keys {
+                        ui.add(
+                            egui::ProgressBar::new(*count as f32 / max as f32)
+                                .text(format!("{} ({})", key, count)),
+                        );
+                    }
+                }
+            });
+        });
+    }
+}
+
+pub fn run(first: &Path, second: &Path) -> Result<(), String> {
+    let view = ComparisonView {
+        sides: [Side::load(first)?, Side::load(second)?],
+    };
+
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size([800.0, 860.0]),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "Activity Tracker - Compare",
+        options,
+        Box::new(|_cc| Ok(Box::new(view))),
+    )
+    .map_err(|e| e.to_string())
+}
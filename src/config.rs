@@ -0,0 +1,347 @@
+//! User settings, read from `config.json` in the platform config directory
+//! (e.g. `~/.config/activity-tracker/config.json` on Linux). A missing file
+//! means defaults; settings are re-read at the start of every session.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use active_win_pos_rs::ActiveWindow;
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::airtable::AirtableConfig;
+use crate::billing::BillingConfig;
+use crate::caldav::CalDavConfig;
+use crate::capture::CaptureBackend;
+use crate::categories::CategoryRule;
+use crate::daily_note::DailyNoteConfig;
+use crate::discord::DiscordConfig;
+use crate::dashboard::DashboardConfig;
+use crate::editor::EditorConfig;
+use crate::email::EmailConfig;
+use crate::ergonomics::ErgonomicsConfig;
+use crate::fatigue::FatigueConfig;
+use crate::filter::FilterRule;
+use crate::google::GoogleCalendarConfig;
+use crate::hid::HidDeviceConfig;
+use crate::influx::InfluxConfig;
+use crate::logging::LogLevel;
+use crate::matrix::MatrixConfig;
+use crate::notion::NotionConfig;
+use crate::otel::OtlpConfig;
+use crate::plugin::PluginConfig;
+use crate::preset::Preset;
+use crate::push::PushConfig;
+use crate::recorder::{PauseReason, SAMPLE_INTERVAL};
+use crate::slack::SlackConfig;
+use crate::storage::FilenameTemplate;
+use crate::tagging::TagRule;
+use crate::{lock, window};
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where sessions are saved; Downloads when unset.
+    pub output_dir: Option<PathBuf>,
+    /// Name of saved session files, see [`FilenameTemplate`].
+    pub filename_template: FilenameTemplate,
+    /// Store only every Nth capture tick's sample, trading resolution for
+    /// smaller files.
+    pub sample_every: u32,
+    /// Milliseconds between two reads of the mouse and of the keyboard; see
+    /// `capture.rs` for how they are merged into samples.
+    pub mouse_poll_ms: u64,
+    pub keyboard_poll_ms: u64,
+    /// How input is captured: "poll" or, in builds with the `rdev`
+    /// feature, "events".
+    pub capture_backend: CaptureBackend,
+    /// HID devices whose raw reports are recorded, see `hid.rs`.
+    pub hid_devices: Vec<HidDeviceConfig>,
+    /// Record gamepad buttons and sticks, see `gamepad.rs`.
+    pub gamepads: bool,
+    /// Record stylus pressure, tilt and proximity, see `evdev_input.rs`.
+    pub pen: bool,
+    /// Record touchscreen and touchpad contacts and gestures, see
+    /// `evdev_input.rs`.
+    pub touch: bool,
+    /// Record which keyboards and pointing devices were used, see
+    /// `evdev_input.rs`.
+    pub identify_devices: bool,
+    /// Record when keyboards and pointing devices are plugged in or
+    /// removed, see `evdev_input.rs`.
+    pub hotplug: bool,
+    /// Record the battery level once a minute, see `context.rs`.
+    pub battery: bool,
+    /// Record network traffic on every sample, see `context.rs`.
+    pub network: bool,
+    /// Record CPU usage on every sample, see `context.rs`.
+    pub cpu: bool,
+    /// Record memory usage on every sample, see `context.rs`.
+    pub memory: bool,
+    /// Named starting points for sessions, see [`Preset`].
+    pub presets: Vec<Preset>,
+    /// External sinks and processors run when a session is saved.
+    pub plugins: Vec<PluginConfig>,
+    /// Rhai scripts whose hooks run during recording.
+    pub scripts: Vec<PathBuf>,
+    /// Rules for samples that must never be stored.
+    pub filters: Vec<FilterRule>,
+    /// Applications (regexes over the name or executable) during which
+    /// nothing is captured at all; the time shows as an `excluded` gap.
+    pub excluded_apps: Vec<Pattern>,
+    /// Window titles of private/incognito browser windows; capture pauses
+    /// while one has focus.
+    pub private_window_titles: Vec<Pattern>,
+    /// Pause capture while the screen is locked.
+    pub pause_on_lock: bool,
+    /// Store the focused application's name with every sample. Off unless
+    /// asked for, as app names tell a lot about what was being done.
+    pub record_app: bool,
+    /// Store the focused process's ID and executable path with every
+    /// sample, see `context.rs`.
+    pub record_process: bool,
+    pub billing: BillingConfig,
+    pub fatigue: FatigueConfig,
+    pub ergonomics: ErgonomicsConfig,
+    /// Write every saved session to InfluxDB.
+    pub influxdb: Option<InfluxConfig>,
+    /// Send every saved session as a trace to an OpenTelemetry collector.
+    pub otlp: Option<OtlpConfig>,
+    /// Create a Google Calendar event for every saved session.
+    pub google_calendar: Option<GoogleCalendarConfig>,
+    /// Store every saved session as an event in a CalDAV calendar.
+    pub caldav: Option<CalDavConfig>,
+    /// Append every saved session to a Markdown daily note.
+    pub daily_note: Option<DailyNoteConfig>,
+    /// Add every saved session as a row to a Notion database.
+    pub notion: Option<NotionConfig>,
+    /// Add every saved session as a record to an Airtable table.
+    pub airtable: Option<AirtableConfig>,
+    /// Email a report of every saved session.
+    pub email: Option<EmailConfig>,
+    /// Announce sessions in a Slack channel.
+    pub slack: Option<SlackConfig>,
+    /// Announce sessions in a Discord channel.
+    pub discord: Option<DiscordConfig>,
+    /// Announce sessions in a Matrix room.
+    pub matrix: Option<MatrixConfig>,
+    /// Notify a phone when a long session ends or a session fails to save.
+    pub push: Option<PushConfig>,
+    /// Serve a live view of the recording over HTTP.
+    pub dashboard: Option<DashboardConfig>,
+    /// Accept the active file from editor extensions over HTTP.
+    pub editor: Option<EditorConfig>,
+    /// Write a report with a backtrace to the crash directory on a panic.
+    pub crash_reports: bool,
+    /// Least severe messages written to the log file: off, error, warn,
+    /// info, debug or trace.
+    pub log_level: LogLevel,
+    /// GUI language code, e.g. "es"; defaults to the system locale.
+    pub language: Option<String>,
+    /// Play a chime when a task starts and ends, and a beep when its
+    /// countdown is over.
+    pub sounds: bool,
+    /// Announce aloud when recording starts and stops, using the system's
+    /// speech synthesizer.
+    pub speak: bool,
+    /// Silence notifications while recording; see `dnd.rs` for what this
+    /// does on each platform.
+    pub do_not_disturb: bool,
+    /// Apps counted as distractions in `analyze` and `timeseries`, matched
+    /// against the recorded app name (see `record_app`).
+    pub distraction_apps: Vec<Pattern>,
+    /// App categories for the `categories` report, checked before the
+    /// built-in ones.
+    pub categories: Vec<CategoryRule>,
+    /// Rules that tag a session when it ends, from how its time was spent.
+    pub tag_rules: Vec<TagRule>,
+    /// GUI zoom factor; 1.0 is egui's default size.
+    pub ui_scale: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            output_dir: None,
+            filename_template: FilenameTemplate::default(),
+            sample_every: 1,
+            mouse_poll_ms: SAMPLE_INTERVAL.as_millis() as u64,
+            keyboard_poll_ms: SAMPLE_INTERVAL.as_millis() as u64,
+            capture_backend: CaptureBackend::default(),
+            hid_devices: Vec::new(),
+            gamepads: false,
+            pen: false,
+            touch: false,
+            identify_devices: false,
+            hotplug: false,
+            battery: false,
+            network: false,
+            cpu: false,
+            memory: false,
+            presets: Vec::new(),
+            plugins: Vec::new(),
+            scripts: Vec::new(),
+            filters: Vec::new(),
+            excluded_apps: Vec::new(),
+            // Firefox, Chrome/Chromium, Edge and Brave respectively
+            private_window_titles: [r"Private Browsing", r"\bIncognito\b", r"InPrivate", r"\(Private\)"]
+            .into_iter()
+            .map(|p| Pattern::try_from(p.to_string()).expect("default patterns are valid"))
+            .collect(),
+            pause_on_lock: true,
+            record_app: false,
+            record_process: false,
+            billing: BillingConfig::default(),
+            fatigue: FatigueConfig::default(),
+            ergonomics: ErgonomicsConfig::default(),
+            influxdb: None,
+            otlp: None,
+            google_calendar: None,
+            caldav: None,
+            daily_note: None,
+            notion: None,
+            airtable: None,
+            email: None,
+            slack: None,
+            discord: None,
+            matrix: None,
+            push: None,
+            dashboard: None,
+            editor: None,
+            crash_reports: false,
+            log_level: LogLevel::default(),
+            language: None,
+            ui_scale: 1.0,
+            sounds: false,
+            speak: false,
+            do_not_disturb: false,
+            distraction_apps: Vec::new(),
+            categories: Vec::new(),
+            tag_rules: Vec::new(),
+        }
+    }
+}
+
+/// A regex written as a plain string in the settings file.
+#[derive(Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern).map(Pattern)
+    }
+}
+
+impl Deref for Pattern {
+    type Target = Regex;
+
+    fn deref(&self) -> &Regex {
+        &self.0
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("activity-tracker").join("config.json"))
+    }
+
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        serde_json::from_str(&text).map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))
+    }
+
+    pub fn preset(&self, name: &str) -> Result<&Preset, String> {
+        self.presets
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| format!("No preset named \"{}\".", name))
+    }
+
+    /// Takes the named preset out of the settings and applies it.
+    pub fn apply_preset(&mut self, name: &str) -> Result<(), String> {
+        let index = self
+            .presets
+            .iter()
+            .position(|p| p.name == name)
+            .ok_or_else(|| format!("No preset named \"{}\".", name))?;
+        self.presets.remove(index).apply(self);
+        Ok(())
+    }
+
+    /// Stores `dir` as `output_dir` in the settings file, leaving every other
+    /// setting as written.
+    pub fn save_output_dir(dir: &Path) -> Result<(), String> {
+        Self::save_setting("output_dir", dir.to_string_lossy().into_owned().into())
+    }
+
+    /// Stores the GUI zoom level as `ui_scale`, like `save_output_dir`.
+    pub fn save_ui_scale(scale: f32) -> Result<(), String> {
+        Self::save_setting("ui_scale", scale.into())
+    }
+
+    fn save_setting(key: &str, value: serde_json::Value) -> Result<(), String> {
+        let path = Self::path().ok_or("No config directory")?;
+        let mut settings = match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("Invalid settings in {}: {}", path.display(), e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => serde_json::Value::Object(Default::default()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        let serde_json::Value::Object(map) = &mut settings else {
+            return Err(format!("Invalid settings in {}: not an object", path.display()));
+        };
+        map.insert(key.to_string(), value);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let text = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Whether capture has to look up the focused window for every sample.
+    pub fn needs_window(&self) -> bool {
+        self.record_app
+            || self.record_process
+            || !self.excluded_apps.is_empty()
+            || !self.private_window_titles.is_empty()
+            || (self.pause_on_lock && lock::USES_FOREGROUND_WINDOW)
+            || self.filters.iter().any(|rule| rule.needs_window())
+    }
+
+    /// Why nothing may be captured while `window` has focus, if so.
+    pub fn pause_reason(&self, window: &ActiveWindow) -> Option<PauseReason> {
+        if self.is_excluded(window) {
+            return Some(PauseReason::ExcludedApp);
+        }
+        self.private_window_titles
+            .iter()
+            .any(|pattern| pattern.is_match(&window.title))
+            .then_some(PauseReason::PrivateWindow)
+    }
+
+    pub fn is_distraction(&self, app: &str) -> bool {
+        self.distraction_apps.iter().any(|pattern| pattern.is_match(app))
+    }
+
+    /// Time between two stored samples.
+    pub fn sample_interval(&self) -> Duration {
+        SAMPLE_INTERVAL * self.sample_every.max(1)
+    }
+
+    pub fn is_excluded(&self, window: &ActiveWindow) -> bool {
+        self.excluded_apps.iter().any(|pattern| window::app_matches(pattern, window))
+    }
+}
@@ -0,0 +1,199 @@
+//! The machine's state recorded alongside activity, to tell e.g. a laptop
+//! running down its battery from one on the desk. Each series is turned on
+//! in the settings file:
+//!
+//! - `"battery": true`, the charge and whether it is charging, once a
+//!   minute, e.g. `"battery": { "percent": 87.5, "state": "discharging" }`
+//! - `"network": true`, the bytes received and sent over all interfaces
+//!   but loopback since the previous sample, on every sample, e.g.
+//!   `"network": { "received": 183200, "sent": 9120 }`
+//! - `"cpu": true`, the percentage of all cores' time spent busy since the
+//!   previous sample, on every sample, so a machine compiling while its
+//!   user waits can be told from an idle one: `"cpu": 73.4`
+//! - `"memory": true`, the percentage of memory and swap in use, on every
+//!   sample: `"memory": { "percent": 81.2, "swap_percent": 12.0 }`
+//! - `"record_process": true`, the focused process, to tell apart several
+//!   instances of one app such as browser profiles:
+//!   `"process": { "pid": 4242, "exe": "/usr/lib/firefox/firefox" }`
+//!
+//! With the companion browser extension installed (see
+//! `native_messaging.rs`), the sample after the active tab changes also
+//! gets its `"tab": { "url": ..., "title": ... }`, if `record_app` is on.
+//! Likewise, with an editor extension reporting to the endpoint in
+//! `editor.rs`, samples get the file being worked on as `"editor"`.
+//!
+//! Stored as JSON in the `context` column of sessions, on the samples where
+//! a value was read.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+
+/// Time between two battery readings.
+const BATTERY_EVERY: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SystemContext {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub battery: Option<BatteryState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<NetworkUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub process: Option<ProcessInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab: Option<BrowserTab>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub editor: Option<EditorFile>,
+}
+
+impl SystemContext {
+    pub fn is_empty(&self) -> bool {
+        self.battery.is_none()
+            && self.network.is_none()
+            && self.cpu.is_none()
+            && self.memory.is_none()
+            && self.process.is_none()
+            && self.tab.is_none()
+            && self.editor.is_none()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BatteryState {
+    /// Charge, from 0 to 100; the average of all batteries if there are
+    /// several.
+    pub percent: f32,
+    /// "charging", "discharging", "full", "empty" or "unknown".
+    pub state: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct NetworkUsage {
+    pub received: u64,
+    pub sent: u64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MemoryUsage {
+    pub percent: f32,
+    /// Missing on machines without swap.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap_percent: Option<f32>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: u64,
+    pub exe: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct BrowserTab {
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct EditorFile {
+    pub file: String,
+    /// Workspace folder or repository root.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+}
+
+/// Reads the wanted series at their own pace.
+pub struct Sampler {
+    battery: Option<battery::Manager>,
+    next_battery: Instant,
+    networks: Option<sysinfo::Networks>,
+    /// Refreshed for the series that need it.
+    system: Option<sysinfo::System>,
+    cpu: bool,
+    memory: bool,
+}
+
+impl Sampler {
+    pub fn new(config: &Config) -> Self {
+        let battery = config.battery.then(battery::Manager::new).and_then(|manager| {
+            manager.map_err(|e| tracing::warn!("Battery level unavailable: {}", e)).ok()
+        });
+        let mut system = (config.cpu || config.memory).then(sysinfo::System::new);
+        if let Some(system) = system.as_mut().filter(|_| config.cpu) {
+            // Usage is measured between two refreshes
+            system.refresh_cpu_usage();
+        }
+        Sampler {
+            battery,
+            next_battery: Instant::now(),
+            networks: config.network.then(sysinfo::Networks::new_with_refreshed_list),
+            system,
+            cpu: config.cpu,
+            memory: config.memory,
+        }
+    }
+
+    /// The series due for a new value.
+    pub fn sample(&mut self) -> SystemContext {
+        let mut context = SystemContext::default();
+        if let Some(manager) = self.battery.as_ref().filter(|_| Instant::now() >= self.next_battery) {
+            self.next_battery = Instant::now() + BATTERY_EVERY;
+            context.battery = read_battery(manager);
+        }
+        if let Some(networks) = &mut self.networks {
+            // Also picks up interfaces that came up since
+            networks.refresh(true);
+            let (received, sent) = networks
+                .iter()
+                .filter(|(name, _)| !name.starts_with("lo"))
+                .fold((0, 0), |(received, sent), (_, data)| (received + data.received(), sent + data.transmitted()));
+            context.network = Some(NetworkUsage { received, sent });
+        }
+        if let Some(system) = &mut self.system {
+            if self.cpu {
+                system.refresh_cpu_usage();
+                context.cpu = Some(round(system.global_cpu_usage()));
+            }
+            if self.memory {
+                system.refresh_memory();
+                let percent = |used: u64, total: u64| (total > 0).then(|| round(used as f32 / total as f32 * 100.0));
+                context.memory = Some(MemoryUsage {
+                    percent: percent(system.used_memory(), system.total_memory()).unwrap_or_default(),
+                    swap_percent: percent(system.used_swap(), system.total_swap()),
+                });
+            }
+        }
+        context
+    }
+}
+
+fn read_battery(manager: &battery::Manager) -> Option<BatteryState> {
+    use battery::units::ratio::percent;
+
+    let batteries: Vec<battery::Battery> = match manager.batteries() {
+        Ok(batteries) => batteries.flatten().collect(),
+        Err(e) => {
+            tracing::warn!("Failed to read the battery: {}", e);
+            return None;
+        }
+    };
+    let first = batteries.first()?;
+    let charge = batteries.iter().map(|b| b.state_of_charge().get::<percent>()).sum::<f32>() / batteries.len() as f32;
+    Some(BatteryState {
+        percent: round(charge),
+        state: first.state().to_string(),
+    })
+}
+
+/// To one decimal, which is plenty and keeps files small.
+fn round(percent: f32) -> f32 {
+    (percent * 10.0).round() / 10.0
+}
@@ -0,0 +1,75 @@
+use crate::context::BrowserTab;
+use crate::recorder::{Phase, Recorder};
+use crate::storage::SessionMeta;
+
+/// A command accepted by the external control endpoints.
+pub enum Command {
+    Start(String),
+    Stop,
+    Status,
+    Mark(String),
+    /// The browser's active tab changed, as `tab {"url": ..., "title": ...}`.
+    Tab(BrowserTab),
+}
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim();
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, arg.trim()),
+            None => (line, ""),
+        };
+
+        match name {
+            "start" if arg.is_empty() => Err("usage: start <task name>".to_string()),
+            "start" => Ok(Command::Start(arg.to_string())),
+            "stop" => Ok(Command::Stop),
+            "status" => Ok(Command::Status),
+            "mark" => Ok(Command::Mark(arg.to_string())),
+            "tab" => serde_json::from_str(arg)
+                .map(Command::Tab)
+                .map_err(|e| format!("usage: tab {{\"url\": ..., \"title\": ...}} ({})", e)),
+            "" => Err("empty command".to_string()),
+            other => Err(format!("unknown command: {}", other)),
+        }
+    }
+
+    /// Runs the command against the recorder and returns a one-line reply.
+    pub fn execute(&self, recorder: &Recorder) -> Result<String, String> {
+        match self {
+            Command::Start(task_name) => {
+                recorder.start(SessionMeta::new(task_name.as_str()))?;
+                Ok(format!("started \"{}\"", task_name))
+            }
+            Command::Stop => {
+                let path = recorder.stop()?;
+                Ok(format!("saved {}", path.display()))
+            }
+            Command::Status => Ok(match recorder.phase() {
+                Phase::Idle => "idle".to_string(),
+                Phase::Countdown(remaining) => {
+                    format!("countdown {} \"{}\"", remaining, recorder.task_name())
+                }
+                Phase::Recording => format!(
+                    "recording \"{}\" samples={}",
+                    recorder.task_name(),
+                    recorder.sample_count()
+                ),
+                Phase::Paused(reason) => format!(
+                    "paused \"{}\" samples={} reason=\"{}\"",
+                    recorder.task_name(),
+                    recorder.sample_count(),
+                    reason.label()
+                ),
+            }),
+            Command::Mark(label) => {
+                recorder.mark(label)?;
+                Ok("marked".to_string())
+            }
+            Command::Tab(tab) => {
+                recorder.set_tab(tab.clone())?;
+                Ok("noted".to_string())
+            }
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! Opt-in crash reports. With `crash_reports` enabled in the settings file,
+//! a panic on any thread writes a report with a backtrace to the crash
+//! directory before the default handler runs, so there is something to
+//! attach to a bug report.
+
+use std::backtrace::Backtrace;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::PathBuf;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::Config;
+
+/// Where crash reports are written, e.g. `~/.local/share/activity-tracker/crashes`.
+fn dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("activity-tracker").join("crashes"))
+}
+
+/// Installs the panic hook if crash reports are enabled.
+pub fn install(config: &Config) {
+    if !config.crash_reports {
+        return;
+    }
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        match write_report(info) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Failed to write crash report: {}", e),
+        }
+        tracing::error!("panic: {}", info);
+        default_hook(info);
+    }));
+}
+
+fn write_report(info: &PanicHookInfo) -> Result<PathBuf, String> {
+    let dir = dir().ok_or("No data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let path = dir.join(format!("crash-{}-{}.txt", now.as_secs(), std::process::id()));
+    let message = info
+        .payload()
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| info.payload().downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "(no message)".to_string());
+    let report = format!(
+        "activity-tracker {}\nos: {} {}\ntime: {}\nthread: {}\nlocation: {}\nmessage: {}\n\nbacktrace:\n{}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        now.as_secs(),
+        thread::current().name().unwrap_or("(unnamed)"),
+        info.location().map_or("(unknown)".to_string(), |l| l.to_string()),
+        message,
+        Backtrace::force_capture()
+    );
+    fs::write(&path, report).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
@@ -0,0 +1,90 @@
+//! Markdown daily notes, e.g. in an Obsidian vault, configured under
+//! `daily_note` in the settings file:
+//!
+//! ```json
+//! "daily_note": { "folder": "~/Vault/Daily", "date_format": "%Y-%m-%d" }
+//! ```
+//!
+//! Each saved session is appended to the note of the day it started, named
+//! `<date>.md` like the daily notes plugin's default. Tags are written as
+//! `#tags` so they link up with the rest of the vault.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::cli;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DailyNoteConfig {
+    /// Folder holding the daily notes; `~` stands for the home directory.
+    pub folder: PathBuf,
+    /// Note name as a chrono format string, without `.md`.
+    pub date_format: String,
+}
+
+impl Default for DailyNoteConfig {
+    fn default() -> Self {
+        DailyNoteConfig {
+            folder: PathBuf::new(),
+            date_format: "%Y-%m-%d".to_string(),
+        }
+    }
+}
+
+impl DailyNoteConfig {
+    pub fn append(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let local = |timestamp: u64| {
+            DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default().with_timezone(&Local)
+        };
+        let start = local(summary.start);
+        let end = local(summary.end);
+
+        let mut entry = format!("\n### {}–{} {}\n", start.format("%H:%M"), end.format("%H:%M"), meta.task);
+        if let Some(project) = &meta.project {
+            entry.push_str(&format!("- Project: [[{}]]\n", project));
+        }
+        entry.push_str(&format!("- Duration: {}", cli::format_duration(summary.tracked_secs())));
+        if let Some(estimate) = meta.estimate_secs {
+            entry.push_str(&format!(" (estimate {})", cli::format_duration(estimate)));
+        }
+        entry.push('\n');
+        entry.push_str(&format!(
+            "- Activity: {} key presses, {} clicks, {:.1}% idle\n",
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        ));
+        if !meta.tags.is_empty() {
+            let tags: Vec<String> = meta.tags.iter().map(|tag| format!("#{}", tag.replace(' ', "-"))).collect();
+            entry.push_str(&format!("- Tags: {}\n", tags.join(" ")));
+        }
+        if let Some(notes) = &meta.notes {
+            entry.push_str(&format!("- Notes: {}\n", notes.trim().replace('\n', "\n  ")));
+        }
+
+        let folder = match self.folder.strip_prefix("~") {
+            Ok(rest) => dirs::home_dir().ok_or("No home directory")?.join(rest),
+            Err(_) => self.folder.clone(),
+        };
+        fs::create_dir_all(&folder).map_err(|e| format!("Failed to create {}: {}", folder.display(), e))?;
+        let path = folder.join(format!("{}.md", start.format(&self.date_format)));
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(entry.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
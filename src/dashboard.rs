@@ -0,0 +1,292 @@
+//! Built-in web dashboard, configured under `dashboard` in the settings file:
+//!
+//! ```json
+//! "dashboard": { "bind": "0.0.0.0:8765", "token": "a long random string" }
+//! ```
+//!
+//! While the app runs, `http://<host>:8765/` shows the session being
+//! recorded: its stats, recent activity and a live trail of the pointer and
+//! keys. The page is bundled into the binary and polls `/api/live` once a
+//! second. The default address only accepts connections from this machine;
+//! bind to `0.0.0.0` to watch from another device on the LAN.
+//!
+//! For monitoring from scripts or a phone, `GET /status` returns the
+//! recorder's state and `GET /summary/current` the running session's
+//! statistics, both as JSON. With a `token` set every request must carry it,
+//! as `Authorization: Bearer <token>` or `?token=<token>`; open the page as
+//! `http://<host>:8765/?token=<token>`, or scan the QR code of that link
+//! shown in the app or printed by `pair`.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::export::JsonRecord;
+use crate::http;
+use crate::recorder::{Phase, Recorder};
+
+const PAGE: &str = include_str!("../assets/dashboard.html");
+
+/// Samples sent with every update, enough to cover the poll interval.
+const RECENT_SAMPLES: usize = 20;
+
+/// Window and bucket of the activity chart.
+const ACTIVITY_WINDOW: Duration = Duration::from_secs(300);
+const ACTIVITY_BUCKET: Duration = Duration::from_secs(10);
+
+/// Longest request line and headers accepted, together.
+pub const MAX_HEAD: u64 = 8 * 1024;
+/// Connections served at once; further ones are closed straight away.
+const MAX_CLIENTS: usize = 16;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DashboardConfig {
+    /// Address and port to listen on.
+    pub bind: String,
+    /// Secret that requests must present; anyone who can connect may look
+    /// when unset.
+    pub token: Option<String>,
+}
+
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        DashboardConfig {
+            bind: "127.0.0.1:8765".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// What the page shows, as served by `/api/live`.
+#[derive(Serialize)]
+struct Live {
+    /// idle, countdown, recording or paused.
+    phase: &'static str,
+    status: String,
+    task: Option<String>,
+    project: Option<String>,
+    elapsed_secs: u64,
+    samples: usize,
+    key_presses: usize,
+    clicks: usize,
+    mouse_distance: f64,
+    idle_ratio: f64,
+    /// Samples with input per bucket of the activity window, oldest first.
+    activity: Vec<usize>,
+    recent: Vec<JsonRecord>,
+}
+
+fn phase_name(phase: Phase) -> &'static str {
+    match phase {
+        Phase::Idle => "idle",
+        Phase::Countdown(_) => "countdown",
+        Phase::Recording => "recording",
+        Phase::Paused(_) => "paused",
+    }
+}
+
+impl Live {
+    fn of(recorder: &Recorder) -> Self {
+        let phase = recorder.phase();
+        let meta = (phase != Phase::Idle).then(|| recorder.meta());
+        let summary = recorder.summary();
+        Live {
+            phase: phase_name(phase),
+            status: recorder.status(),
+            task: meta.as_ref().map(|m| m.task.clone()),
+            project: meta.and_then(|m| m.project),
+            elapsed_secs: recorder.elapsed().as_secs(),
+            samples: summary.samples,
+            key_presses: summary.key_presses,
+            clicks: summary.clicks,
+            mouse_distance: summary.mouse_distance,
+            idle_ratio: summary.idle_ratio(),
+            activity: recorder.recent_activity(ACTIVITY_WINDOW, ACTIVITY_BUCKET),
+            recent: recorder.recent_samples(RECENT_SAMPLES),
+        }
+    }
+}
+
+impl DashboardConfig {
+    /// Link to the page for other devices, including the token. When
+    /// listening on all interfaces, the host is this machine's LAN address.
+    pub fn url(&self) -> String {
+        let (host, port) = self.bind.rsplit_once(':').unwrap_or((&self.bind, ""));
+        let host = match host {
+            "0.0.0.0" | "[::]" => lan_address().unwrap_or_else(|| host.to_string()),
+            host => host.to_string(),
+        };
+        match &self.token {
+            Some(token) => format!("http://{}:{}/?token={}", host, port, http::encode(token)),
+            None => format!("http://{}:{}/", host, port),
+        }
+    }
+
+    /// Whether only this machine can connect, so the link is no use on a phone.
+    pub fn local_only(&self) -> bool {
+        self.bind
+            .to_socket_addrs()
+            .is_ok_and(|mut addrs| addrs.all(|addr| addr.ip().is_loopback()))
+    }
+
+    /// Starts serving the dashboard in a background thread.
+    pub fn spawn(&self, recorder: Recorder) -> io::Result<()> {
+        let listener = TcpListener::bind(&self.bind)?;
+        tracing::info!(address = %self.bind, "dashboard listening");
+        if self.token.is_none() && !listener.local_addr()?.ip().is_loopback() {
+            tracing::warn!("dashboard on {} has no token; anyone on the network can watch", self.bind);
+        }
+        let token = self.token.clone();
+        let clients = Arc::new(AtomicUsize::new(0));
+
+        thread::spawn(move || {
+            for conn in listener.incoming() {
+                let conn = match conn {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::warn!("dashboard: failed to accept a connection: {}", e);
+                        continue;
+                    }
+                };
+                if clients.fetch_add(1, Ordering::SeqCst) >= MAX_CLIENTS {
+                    clients.fetch_sub(1, Ordering::SeqCst);
+                    tracing::debug!("dashboard: too many connections, closing one");
+                    continue;
+                }
+                let recorder = recorder.clone();
+                let token = token.clone();
+                let clients = Arc::clone(&clients);
+                thread::spawn(move || {
+                    if let Err(e) = handle_client(conn, &recorder, token.as_deref()) {
+                        tracing::debug!("dashboard: client error: {}", e);
+                    }
+                    clients.fetch_sub(1, Ordering::SeqCst);
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Answers one request and closes the connection.
+fn handle_client(conn: TcpStream, recorder: &Recorder, token: Option<&str>) -> io::Result<()> {
+    conn.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(conn).take(MAX_HEAD);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut bearer = None;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 && reader.limit() > 0 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                bearer = value.trim().strip_prefix("Bearer ").map(str::to_string);
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let presented = bearer.or_else(|| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == "token")
+            .map(|(_, value)| http::decode(value))
+    });
+
+    let (status, content_type, body) = match (method, path) {
+        // Cut off before the blank line ending the headers
+        _ if reader.limit() == 0 => ("431 Request Header Fields Too Large", "text/plain", "Request too large".to_string()),
+        _ if token.is_some_and(|token| !presented.as_deref().is_some_and(|p| same(p, token))) => {
+            ("401 Unauthorized", "text/plain", "Missing or wrong token".to_string())
+        }
+        ("GET", "/") => ("200 OK", "text/html; charset=utf-8", PAGE.to_string()),
+        ("GET", "/api/live") => json_response(&Live::of(recorder)),
+        ("GET", "/status") => json_response(&status(recorder)),
+        ("GET", "/summary/current") => match current_summary(recorder) {
+            Some(summary) => json_response(&summary),
+            None => ("404 Not Found", "text/plain", "Not recording".to_string()),
+        },
+        ("GET", _) => ("404 Not Found", "text/plain", "Not found".to_string()),
+        _ => ("405 Method Not Allowed", "text/plain", "Method not allowed".to_string()),
+    };
+
+    let mut conn = reader.into_inner().into_inner();
+    write!(
+        conn,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\n{}Connection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        if status.starts_with("401") { "WWW-Authenticate: Bearer\r\n" } else { "" },
+        body
+    )?;
+    conn.flush()
+}
+
+fn json_response(value: &impl Serialize) -> (&'static str, &'static str, String) {
+    match serde_json::to_string(value) {
+        Ok(json) => ("200 OK", "application/json", json),
+        Err(e) => ("500 Internal Server Error", "text/plain", e.to_string()),
+    }
+}
+
+fn status(recorder: &Recorder) -> serde_json::Value {
+    let phase = recorder.phase();
+    let meta = (phase != Phase::Idle).then(|| recorder.meta());
+    json!({
+        "phase": phase_name(phase),
+        "task": meta.as_ref().map(|m| &m.task),
+        "project": meta.as_ref().and_then(|m| m.project.as_ref()),
+        "elapsed_secs": recorder.elapsed().as_secs(),
+        "samples": recorder.sample_count(),
+        "status": recorder.status(),
+    })
+}
+
+/// Statistics of the running session, as in `analyze --json`.
+fn current_summary(recorder: &Recorder) -> Option<serde_json::Value> {
+    if !recorder.is_active() {
+        return None;
+    }
+    let meta = recorder.meta();
+    let summary = recorder.summary();
+    Some(json!({
+        "task": meta.task,
+        "project": meta.project,
+        "tags": meta.tags,
+        "start": summary.start,
+        "duration_secs": summary.duration_secs(),
+        "tracked_secs": summary.tracked_secs(),
+        "samples": summary.samples,
+        "key_presses": summary.key_presses,
+        "clicks": summary.clicks,
+        "mouse_distance": summary.mouse_distance,
+        "idle_ratio": summary.idle_ratio(),
+        "gap_secs": summary.gap_secs(),
+        "markers": summary.markers.len(),
+    }))
+}
+
+/// The address this machine reaches other hosts from. Connecting a UDP
+/// socket only picks a route; nothing is sent.
+fn lan_address() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("192.0.2.1:80").ok()?;
+    Some(socket.local_addr().ok()?.ip().to_string())
+}
+
+/// Compares tokens in time independent of where they first differ.
+pub fn same(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
@@ -0,0 +1,101 @@
+//! Capture health, for finding out why inputs aren't being recorded.
+
+use std::env;
+use std::time::{Duration, Instant};
+
+use crate::recorder::SAMPLE_INTERVAL;
+
+/// Weight of the newest tick in the smoothed capture rate.
+const RATE_SMOOTHING: f64 = 0.1;
+
+/// Running measurements kept by the capture loop.
+#[derive(Default)]
+pub struct Health {
+    last_tick: Option<Instant>,
+    last_sample: Option<Instant>,
+    /// Smoothed time between capture ticks, in seconds.
+    tick_secs: Option<f64>,
+    /// Ticks the loop fell behind on, e.g. when polling the devices or the
+    /// foreground window blocked.
+    dropped: u64,
+    tick_work: Duration,
+    write_latency: Option<Duration>,
+}
+
+impl Health {
+    /// Forgets the previous session's capture measurements.
+    pub fn reset(&mut self) {
+        *self = Health { write_latency: self.write_latency, ..Health::default() };
+    }
+
+    /// Called at the start of every capture tick. A `resumed` tick follows a
+    /// suspend and does not count as falling behind.
+    pub fn tick(&mut self, now: Instant, resumed: bool) {
+        if let Some(last) = self.last_tick.replace(now) {
+            if resumed {
+                return;
+            }
+            let interval = now.duration_since(last);
+            let secs = interval.as_secs_f64();
+            self.tick_secs = Some(match self.tick_secs {
+                Some(avg) => avg + (secs - avg) * RATE_SMOOTHING,
+                None => secs,
+            });
+            let slots = (interval.as_secs_f64() / SAMPLE_INTERVAL.as_secs_f64()) as u64;
+            self.dropped += slots.saturating_sub(1);
+        }
+    }
+
+    pub fn sampled(&mut self, now: Instant) {
+        self.last_sample = Some(now);
+    }
+
+    pub fn set_tick_work(&mut self, work: Duration) {
+        self.tick_work = work;
+    }
+
+    pub fn set_write_latency(&mut self, latency: Duration) {
+        self.write_latency = Some(latency);
+    }
+
+    pub fn snapshot(&self, samples: usize) -> Diagnostics {
+        Diagnostics {
+            backend: backend(),
+            last_sample_age: self.last_sample.map(|t| t.elapsed()),
+            rate: self.tick_secs.filter(|s| *s > 0.0).map(|s| 1.0 / s),
+            target_rate: 1.0 / SAMPLE_INTERVAL.as_secs_f64(),
+            samples,
+            dropped: self.dropped,
+            tick_work: self.tick_work,
+            write_latency: self.write_latency,
+        }
+    }
+}
+
+pub struct Diagnostics {
+    pub backend: String,
+    pub last_sample_age: Option<Duration>,
+    /// Capture ticks per second actually achieved.
+    pub rate: Option<f64>,
+    pub target_rate: f64,
+    pub samples: usize,
+    pub dropped: u64,
+    /// Time spent polling and storing during the last tick.
+    pub tick_work: Duration,
+    /// How long saving the last session took.
+    pub write_latency: Option<Duration>,
+}
+
+/// The input backend device_query uses on this platform, with a note when
+/// it cannot see everything.
+fn backend() -> String {
+    if cfg!(target_os = "windows") {
+        "Win32 (GetAsyncKeyState)".to_string()
+    } else if cfg!(target_os = "macos") {
+        "Core Graphics (needs Input Monitoring permission)".to_string()
+    } else if env::var_os("WAYLAND_DISPLAY").is_some() {
+        "X11 under Wayland (only input to XWayland windows is seen)".to_string()
+    } else {
+        "X11".to_string()
+    }
+}
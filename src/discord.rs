@@ -0,0 +1,83 @@
+//! Discord announcements through a channel webhook, configured under
+//! `discord` in the settings file:
+//!
+//! ```json
+//! "discord": { "webhook_url": "https://discord.com/api/webhooks/.../...", "on_start": true }
+//! ```
+//!
+//! As with Slack, a message is posted when a session starts, unless
+//! `on_start` is off, and when it is saved, with its duration and headline
+//! stats. Names in the messages never ping anyone.
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::analysis;
+use crate::cli;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DiscordConfig {
+    pub webhook_url: String,
+    /// Announce sessions when they start, not only when they end.
+    pub on_start: bool,
+}
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        DiscordConfig {
+            webhook_url: String::new(),
+            on_start: true,
+        }
+    }
+}
+
+impl DiscordConfig {
+    pub fn session_started(&self, meta: &SessionMeta) -> Result<(), String> {
+        if !self.on_start {
+            return Ok(());
+        }
+        self.post(&format!(":red_circle: Recording started: {}", task(meta)))
+    }
+
+    pub fn session_ended(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        self.post(&format!(
+            ":white_check_mark: Recording finished: {}\n{} tracked · {} key presses · {} clicks · {:.0}% idle",
+            task(meta),
+            cli::format_duration(summary.tracked_secs()),
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        ))
+    }
+
+    fn post(&self, text: &str) -> Result<(), String> {
+        let body = json!({ "content": text, "allowed_mentions": { "parse": [] } });
+        let headers = [("Content-Type", "application/json")];
+        outbox::deliver("Discord", "POST", &self.webhook_url, &headers, body.to_string().as_bytes())
+    }
+}
+
+/// The task in bold, and its project, escaped for Discord's Markdown.
+fn task(meta: &SessionMeta) -> String {
+    let escape = |text: &str| {
+        text.chars().fold(String::new(), |mut escaped, c| {
+            if "\\*_~`|>#[]()-".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+    };
+    match &meta.project {
+        Some(project) => format!("**{}** ({})", escape(&meta.task), escape(project)),
+        None => format!("**{}**", escape(&meta.task)),
+    }
+}
@@ -0,0 +1,73 @@
+//! Do-Not-Disturb while recording, so notification popups don't end up in a
+//! usability-test session. GNOME's notification banners are switched off
+//! through `gsettings`; macOS has no command for Focus modes, so it runs the
+//! shortcuts "Activity Tracker Focus On" and "Activity Tracker Focus Off",
+//! which the user creates once in the Shortcuts app.
+
+use std::process::Command;
+
+/// Shortcuts run on macOS to turn Do Not Disturb on and off.
+#[cfg(target_os = "macos")]
+const MACOS_SHORTCUTS: (&str, &str) = ("Activity Tracker Focus On", "Activity Tracker Focus Off");
+
+/// Do-Not-Disturb switched on by [`DoNotDisturb::enable`], with what is needed
+/// to put things back as they were.
+pub struct DoNotDisturb {
+    #[cfg(target_os = "linux")]
+    show_banners: String,
+}
+
+impl DoNotDisturb {
+    #[cfg(target_os = "linux")]
+    pub fn enable() -> Result<Self, String> {
+        let show_banners = gsettings(&["get", "org.gnome.desktop.notifications", "show-banners"])?;
+        gsettings(&["set", "org.gnome.desktop.notifications", "show-banners", "false"])?;
+        Ok(DoNotDisturb { show_banners })
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn restore(self) -> Result<(), String> {
+        gsettings(&["set", "org.gnome.desktop.notifications", "show-banners", &self.show_banners]).map(|_| ())
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn enable() -> Result<Self, String> {
+        run(Command::new("shortcuts").args(["run", MACOS_SHORTCUTS.0]))?;
+        Ok(DoNotDisturb {})
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn restore(self) -> Result<(), String> {
+        run(Command::new("shortcuts").args(["run", MACOS_SHORTCUTS.1])).map(|_| ())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn enable() -> Result<Self, String> {
+        Err("Do Not Disturb can't be switched on automatically on this platform".to_string())
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    pub fn restore(self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn gsettings(args: &[&str]) -> Result<String, String> {
+    run(Command::new("gsettings").args(args))
+}
+
+/// Runs `command`, returning its trimmed output.
+#[cfg_attr(not(any(target_os = "linux", target_os = "macos")), allow(dead_code))]
+fn run(command: &mut Command) -> Result<String, String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let output = command.output().map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} failed: {}",
+            program,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,185 @@
+//! Editing recorded sessions after the fact.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::recorder::{ActivityRecord, Gap, GapReason};
+use crate::storage::{self, SessionMeta};
+
+/// Joins sessions of one task into a single session, in time order. Where
+/// one session ends and the next begins the first sample of the next is
+/// marked as following a restart, so the break shows up as a gap.
+pub fn merge(mut sessions: Vec<(SessionMeta, Vec<ActivityRecord>)>) -> Result<(SessionMeta, Vec<ActivityRecord>), String> {
+    sessions.retain(|(_, records)| !records.is_empty());
+    sessions.sort_by_key(|(_, records)| records[0].timestamp);
+    let Some((first, _)) = sessions.first() else {
+        return Err("No samples to merge".to_string());
+    };
+    if let Some((other, _)) = sessions.iter().find(|(meta, _)| meta.task != first.task) {
+        return Err(format!(
+            "Only sessions of the same task can be merged, not \"{}\" and \"{}\"",
+            first.task, other.task
+        ));
+    }
+
+    let mut meta = first.clone();
+    let mut merged: Vec<ActivityRecord> = Vec::new();
+    let mut notes = Vec::new();
+    for (session, records) in sessions {
+        for tag in session.tags {
+            if !meta.tags.contains(&tag) {
+                meta.tags.push(tag);
+            }
+        }
+        meta.project = meta.project.or(session.project);
+        meta.estimate_secs = meta.estimate_secs.or(session.estimate_secs);
+        notes.extend(session.notes);
+
+        let mut records = records.into_iter();
+        if let (Some(last), Some(mut next)) = (merged.last(), records.next()) {
+            if next.gap.is_none() {
+                next.gap = Some(Gap { reason: GapReason::Restart, start: last.timestamp });
+            }
+            merged.push(next);
+        }
+        merged.extend(records);
+    }
+    // Sessions that overlapped in time end up interleaved
+    merged.sort_by_key(|r| r.timestamp);
+    meta.notes = (!notes.is_empty()).then(|| notes.join("\n"));
+
+    Ok((meta, merged))
+}
+
+/// Where [`split`] starts a new part.
+#[derive(Clone, Copy)]
+pub enum SplitBy {
+    /// Every this many seconds from the start.
+    Duration(u64),
+    /// After at least this many seconds without input or capture.
+    Idle(u64),
+    /// At every marker.
+    Marker,
+}
+
+/// Cuts a session into consecutive parts. A gap that a part would begin
+/// with is dropped, since it started in the part before.
+pub fn split(records: Vec<ActivityRecord>, by: SplitBy) -> Vec<Vec<ActivityRecord>> {
+    let Some(start) = records.first().map(|r| r.timestamp) else {
+        return Vec::new();
+    };
+    let mut parts: Vec<Vec<ActivityRecord>> = vec![Vec::new()];
+    let mut last_active = start;
+    let mut previous_position: Option<(i32, i32)> = None;
+
+    for mut record in records {
+        let active = record.keys_pressed.len() + record.mouse_buttons.len() > 0
+            || previous_position.is_some_and(|position| position != (record.mouse_x, record.mouse_y));
+        let cut = match by {
+            SplitBy::Duration(secs) => {
+                let part = (record.timestamp - start) / secs.max(1);
+                part as usize >= parts.len()
+            }
+            SplitBy::Idle(secs) => active && record.timestamp - last_active >= secs,
+            SplitBy::Marker => record.marker.is_some(),
+        };
+        if active {
+            last_active = record.timestamp;
+        }
+        if cut && parts.last().is_some_and(|part| !part.is_empty()) {
+            parts.push(Vec::new());
+            record.gap = None;
+        }
+        previous_position = Some((record.mouse_x, record.mouse_y));
+        parts.last_mut().expect("starts with one part").push(record);
+    }
+
+    parts
+}
+
+/// The part of a session between `from_secs` and `to_secs` after its first
+/// sample, for cutting off setup and teardown.
+pub fn trim(records: Vec<ActivityRecord>, from_secs: u64, to_secs: u64) -> Vec<ActivityRecord> {
+    let Some(start) = records.first().map(|r| r.timestamp) else {
+        return records;
+    };
+    let mut trimmed: Vec<ActivityRecord> = records
+        .into_iter()
+        .filter(|r| (from_secs..=to_secs).contains(&(r.timestamp - start)))
+        .collect();
+    // A gap at the new start began in the part that was cut
+    if let Some(first) = trimmed.first_mut() {
+        first.gap = None;
+    }
+    trimmed
+}
+
+/// Writes the trimmed session next to the original as `<name>-trimmed.csv`,
+/// with the same metadata, and returns its path.
+pub fn write_trimmed(session: &Path, from_secs: u64, to_secs: u64) -> Result<PathBuf, String> {
+    let records = trim(storage::load_csv(session)?, from_secs, to_secs);
+    if records.is_empty() {
+        return Err("Nothing left after trimming".to_string());
+    }
+    let stem = session.file_stem().unwrap_or_default().to_string_lossy();
+    let path = session.with_file_name(format!("{}-trimmed.csv", stem));
+    storage::write_csv_file(&path, &records)?;
+    storage::save_meta(&path, &storage::load_meta(session)?)?;
+    Ok(path)
+}
+
+/// Marker placed where a redacted stretch starts.
+pub const REDACTED: &str = "REDACTED";
+
+/// Blanks the input captured between `from_secs` and `to_secs` after the
+/// session's first sample. The samples stay, so durations and timing are
+/// unchanged, but keys, buttons, app, process and markers are cleared and the pointer
+/// is held where it was before the stretch, with no path through it.
+pub fn redact(records: &mut [ActivityRecord], from_secs: u64, to_secs: u64) {
+    let Some(start) = records.first().map(|r| r.timestamp) else {
+        return;
+    };
+    let mut held: Option<(i32, i32)> = None;
+    let mut first = true;
+    for record in records.iter_mut() {
+        if !(from_secs..=to_secs).contains(&(record.timestamp - start)) {
+            held = Some((record.mouse_x, record.mouse_y));
+            // The path into the first sample after the span starts inside it
+            record
+                .path
+                .retain(|&(ms, _, _)| !(from_secs..=to_secs).contains(&(ms / 1000).saturating_sub(start)));
+            continue;
+        }
+        let (x, y) = *held.get_or_insert((record.mouse_x, record.mouse_y));
+        record.mouse_x = x;
+        record.mouse_y = y;
+        record.keys_pressed.clear();
+        record.mouse_buttons.clear();
+        record.path.clear();
+        record.app = None;
+        record.context.process = None;
+        record.context.tab = None;
+        record.context.editor = None;
+        record.marker = first.then(|| REDACTED.to_string());
+        first = false;
+    }
+}
+
+/// Redacts part of a saved session in place, keeping the original beside
+/// it; returns where.
+pub fn redact_file(session: &Path, from_secs: u64, to_secs: u64) -> Result<PathBuf, String> {
+    let mut records = storage::load_csv(session)?;
+    redact(&mut records, from_secs, to_secs);
+    let backup = backup(session)?;
+    storage::write_csv_file(session, &records)?;
+    Ok(backup)
+}
+
+/// Copies `path` to `path.bak` before it is overwritten.
+pub fn backup(path: &Path) -> Result<PathBuf, String> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    fs::copy(path, &backup).map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+    Ok(backup)
+}
@@ -0,0 +1,172 @@
+//! Endpoint for editor extensions to report the file being worked on, so
+//! sessions can attribute time to files and repositories. Configured under
+//! `editor` in the settings file:
+//!
+//! ```json
+//! "editor": { "bind": "127.0.0.1:8766", "token": "a long random string" }
+//! ```
+//!
+//! The protocol is plain HTTP with JSON bodies:
+//!
+//! - `GET /` answers `{ "protocol": 1, "recording": true }`, for the
+//!   extension to check it has found the right thing
+//! - `POST /active` with
+//!   `{ "file": "/src/app/main.rs", "project": "/src/app", "language": "rust", "focused": true }`
+//!   reports the active file. `project` is the workspace folder or
+//!   repository root and, like `language`, may be left out. Send it when
+//!   the active file changes, when the editor window gains or loses focus,
+//!   and at least once a minute while nothing changes; a report more than
+//!   two minutes old is taken to mean the editor went away. `"focused":
+//!   false` or no `file` says no file is being worked on.
+//!
+//! While recording, every sample carries the latest report as
+//! `"editor": { "file": ..., "project": ..., "language": ... }` in its
+//! context, if `record_app` is on. With a `token` set every request
+//! must carry `Authorization: Bearer <token>`.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::context::EditorFile;
+use crate::dashboard;
+use crate::recorder::Recorder;
+
+/// Bumped when the requests or replies change incompatibly.
+const PROTOCOL: u32 = 1;
+/// Largest request body accepted.
+const MAX_BODY: usize = 64 * 1024;
+/// How long a report counts without a newer one.
+pub const STALE_AFTER: Duration = Duration::from_secs(120);
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditorConfig {
+    /// Address and port to listen on.
+    pub bind: String,
+    /// Secret that requests must present; any local program may report
+    /// when unset.
+    pub token: Option<String>,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        EditorConfig {
+            bind: "127.0.0.1:8766".to_string(),
+            token: None,
+        }
+    }
+}
+
+/// The body of `POST /active`.
+#[derive(Deserialize)]
+struct Report {
+    file: Option<String>,
+    project: Option<String>,
+    language: Option<String>,
+    #[serde(default = "focused_by_default")]
+    focused: bool,
+}
+
+fn focused_by_default() -> bool {
+    true
+}
+
+impl EditorConfig {
+    /// Starts serving editor reports in a background thread.
+    pub fn spawn(&self, recorder: Recorder) -> io::Result<()> {
+        let listener = TcpListener::bind(&self.bind)?;
+        tracing::info!(address = %self.bind, "editor endpoint listening");
+        if !listener.local_addr()?.ip().is_loopback() {
+            tracing::warn!("editor endpoint on {} accepts reports from the network", self.bind);
+        }
+        let token = self.token.clone();
+
+        thread::spawn(move || {
+            for conn in listener.incoming() {
+                let conn = match conn {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        tracing::warn!("editor endpoint: failed to accept a connection: {}", e);
+                        continue;
+                    }
+                };
+                let recorder = recorder.clone();
+                let token = token.clone();
+                thread::spawn(move || {
+                    if let Err(e) = handle_client(conn, &recorder, token.as_deref()) {
+                        tracing::debug!("editor endpoint: client error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Answers one request and closes the connection.
+fn handle_client(conn: TcpStream, recorder: &Recorder, token: Option<&str>) -> io::Result<()> {
+    conn.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(conn).take(dashboard::MAX_HEAD);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut bearer = None;
+    let mut length = 0;
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 && reader.limit() > 0 {
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                bearer = value.trim().strip_prefix("Bearer ").map(str::to_string);
+            } else if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().unwrap_or(0);
+            }
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    let (status, body) = match (method, path) {
+        _ if reader.limit() == 0 => ("431 Request Header Fields Too Large", json!({ "error": "request too large" })),
+        _ if token.is_some_and(|token| !bearer.as_deref().is_some_and(|b| dashboard::same(b, token))) => {
+            ("401 Unauthorized", json!({ "error": "missing or wrong token" }))
+        }
+        ("GET", "/") => ("200 OK", json!({ "protocol": PROTOCOL, "recording": recorder.is_active() })),
+        ("POST", "/active") if length > MAX_BODY => ("413 Payload Too Large", json!({ "error": "report too large" })),
+        ("POST", "/active") => {
+            let mut body = vec![0; length];
+            reader.set_limit(length as u64);
+            reader.read_exact(&mut body)?;
+            match serde_json::from_slice::<Report>(&body) {
+                Ok(report) => {
+                    let file = report.file.filter(|_| report.focused).map(|file| EditorFile {
+                        file,
+                        project: report.project,
+                        language: report.language,
+                    });
+                    recorder.set_editor(file);
+                    ("200 OK", json!({ "recording": recorder.is_active() }))
+                }
+                Err(e) => ("400 Bad Request", json!({ "error": e.to_string() })),
+            }
+        }
+        (_, "/" | "/active") => ("405 Method Not Allowed", json!({ "error": "method not allowed" })),
+        _ => ("404 Not Found", json!({ "error": "not found" })),
+    };
+
+    let body = body.to_string();
+    let mut conn = reader.into_inner().into_inner();
+    write!(
+        conn,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    conn.flush()
+}
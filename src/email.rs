@@ -0,0 +1,194 @@
+//! Session reports by email, configured under `email` in the settings file:
+//!
+//! ```json
+//! "email": {
+//!     "server": "smtps://smtp.example.com:465",
+//!     "username": "lab@example.com",
+//!     "password": "...",
+//!     "from": "Activity Tracker <lab@example.com>",
+//!     "to": ["me@example.com"]
+//! }
+//! ```
+//!
+//! When a session is saved, a summary of it is mailed to `to` with the
+//! session file attached. `smtp://` servers are asked to upgrade with
+//! STARTTLS, and the mail is not sent if they can't unless `tls` is off.
+//! Mail goes through the system's `curl`, like HTTPS requests.
+
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::cli;
+use crate::http;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EmailConfig {
+    /// `smtps://host:465` or `smtp://host:587`.
+    pub server: String,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+    pub to: Vec<String>,
+    /// Refuse to send without encryption.
+    pub tls: bool,
+    /// Attach the saved session file.
+    pub attach: bool,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        EmailConfig {
+            server: String::new(),
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+            tls: true,
+            attach: true,
+        }
+    }
+}
+
+impl EmailConfig {
+    pub fn send_report(&self, meta: &SessionMeta, records: &[ActivityRecord], path: &Path) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        if self.to.is_empty() {
+            return Err("Email: no recipients in `to`".to_string());
+        }
+
+        let time = |timestamp: u64| {
+            DateTime::from_timestamp(timestamp as i64, 0)
+                .unwrap_or_default()
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+        let mut text = format!("Task:            {}\n", meta.task);
+        if let Some(project) = &meta.project {
+            text.push_str(&format!("Project:         {}\n", project));
+        }
+        if !meta.tags.is_empty() {
+            text.push_str(&format!("Tags:            {}\n", meta.tags.join(", ")));
+        }
+        text.push_str(&format!("Start:           {}\n", time(summary.start)));
+        text.push_str(&format!("End:             {}\n", time(summary.end)));
+        text.push_str(&format!("Tracked:         {}\n", cli::format_duration(summary.tracked_secs())));
+        text.push_str(&format!("Key presses:     {}\n", summary.key_presses));
+        text.push_str(&format!("Clicks:          {}\n", summary.clicks));
+        text.push_str(&format!("Mouse distance:  {:.0} px\n", summary.mouse_distance));
+        text.push_str(&format!("Idle:            {:.1}%\n", summary.idle_ratio() * 100.0));
+        text.push_str(&format!("Gaps:            {} ({})\n", summary.gaps.len(), cli::format_duration(summary.gap_secs())));
+        if let Some(notes) = &meta.notes {
+            text.push_str(&format!("\n{}\n", notes));
+        }
+        text.push_str(&format!("\nSaved to {}\n", path.display()));
+
+        let attachment = if self.attach {
+            let data = fs::read(path).map_err(|e| format!("Email: failed to read {}: {}", path.display(), e))?;
+            Some((path.file_name().unwrap_or_default().to_string_lossy().into_owned(), data))
+        } else {
+            None
+        };
+        let message = self.message(&format!("Session report: {}", meta.task), &text, attachment);
+        self.send(&message).map_err(|e| format!("Email: {}", e))
+    }
+
+    /// A MIME message with a plain text body and an optional attachment.
+    fn message(&self, subject: &str, text: &str, attachment: Option<(String, Vec<u8>)>) -> String {
+        let boundary = format!("activity-tracker-{}", Local::now().timestamp_nanos_opt().unwrap_or_default());
+        let mut message = format!(
+            "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n",
+            self.from,
+            self.to.join(", "),
+            header_text(subject),
+            Local::now().to_rfc2822()
+        );
+        message.push_str(&format!("Content-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n", boundary));
+        message.push_str(&format!(
+            "--{}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+            boundary,
+            wrapped_base64(text.as_bytes())
+        ));
+        if let Some((name, data)) = attachment {
+            message.push_str(&format!(
+                "--{}\r\nContent-Type: text/csv; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+                boundary,
+                header_text(&name),
+                header_text(&name),
+                wrapped_base64(&data)
+            ));
+        }
+        message.push_str(&format!("--{}--\r\n", boundary));
+        message
+    }
+
+    fn send(&self, message: &str) -> Result<(), String> {
+        let message_path = http::temp_file("eml", message.as_bytes())?;
+
+        let mut config = format!(
+            "url = {}\nmail-from = {}\nupload-file = {}\nsilent\nshow-error\nmax-time = 60\n",
+            http::curl_string(&self.server),
+            http::curl_string(&address(&self.from)),
+            http::curl_string(&message_path.display().to_string())
+        );
+        for to in &self.to {
+            config.push_str(&format!("mail-rcpt = {}\n", http::curl_string(&address(to))));
+        }
+        if !self.username.is_empty() {
+            config.push_str(&format!("user = {}\n", http::curl_string(&format!("{}:{}", self.username, self.password))));
+        }
+        if self.tls {
+            config.push_str("ssl-reqd\n");
+        }
+
+        let result = http::run_curl(&config);
+        let _ = fs::remove_file(&message_path);
+        let output = result.map_err(|e| format!("Failed to send mail via {}: {}", self.server, e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to send mail via {}: {}",
+                self.server,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The bare address in `Name <address>`.
+fn address(mailbox: &str) -> String {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(start), Some(end)) if start < end => mailbox[start + 1..end].to_string(),
+        _ => mailbox.trim().to_string(),
+    }
+}
+
+/// `text` as-is if it is ASCII, otherwise as an RFC 2047 encoded word.
+fn header_text(text: &str) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        format!("=?utf-8?B?{}?=", http::base64(text.as_bytes()))
+    }
+}
+
+/// Base64 in lines of 76 characters, as MIME requires.
+fn wrapped_base64(data: &[u8]) -> String {
+    let encoded = http::base64(data);
+    let mut wrapped = String::with_capacity(encoded.len() + encoded.len() / 38);
+    for line in encoded.as_bytes().chunks(76) {
+        wrapped.push_str(std::str::from_utf8(line).expect("base64 is ASCII"));
+        wrapped.push_str("\r\n");
+    }
+    wrapped
+}
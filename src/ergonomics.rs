@@ -0,0 +1,144 @@
+//! Input load against repetitive-strain limits: keystrokes and clicks per
+//! clock hour and per day, compared with thresholds from the `ergonomics`
+//! section of the settings file. The defaults are conservative office-work
+//! figures; adjust them to what your physiotherapist recommends.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::recorder::ActivityRecord;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ErgonomicsConfig {
+    /// Remind during recording when the last hour was over a limit.
+    pub alerts: bool,
+    pub keys_per_hour: usize,
+    pub clicks_per_hour: usize,
+    pub keys_per_day: usize,
+    pub clicks_per_day: usize,
+}
+
+impl Default for ErgonomicsConfig {
+    fn default() -> Self {
+        ErgonomicsConfig {
+            alerts: true,
+            keys_per_hour: 12_000,
+            clicks_per_hour: 1_500,
+            keys_per_day: 60_000,
+            clicks_per_day: 10_000,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Load {
+    pub keys: usize,
+    pub clicks: usize,
+}
+
+impl Load {
+    fn add(&mut self, other: Load) {
+        self.keys += other.keys;
+        self.clicks += other.clicks;
+    }
+}
+
+/// Load per clock hour, keyed by the hour's Unix start.
+#[derive(Default)]
+pub struct HourlyLoad(BTreeMap<u64, Load>);
+
+impl HourlyLoad {
+    pub fn add_session(&mut self, records: &[ActivityRecord]) {
+        let mut previous: Option<&ActivityRecord> = None;
+        for record in records {
+            let load = Load {
+                keys: analysis::new_key_presses(previous, record),
+                clicks: analysis::new_clicks(previous, record),
+            };
+            self.0.entry(record.timestamp / 3600 * 3600).or_default().add(load);
+            previous = Some(record);
+        }
+    }
+
+    pub fn days(&self) -> Vec<DayLoad> {
+        let mut days: BTreeMap<NaiveDate, DayLoad> = BTreeMap::new();
+        for (&hour, &load) in &self.0 {
+            let date = DateTime::from_timestamp(hour as i64, 0)
+                .unwrap_or_default()
+                .with_timezone(&Local)
+                .date_naive();
+            let day = days.entry(date).or_insert_with(|| DayLoad { date, ..Default::default() });
+            day.total.add(load);
+            day.peak_hour.keys = day.peak_hour.keys.max(load.keys);
+            day.peak_hour.clicks = day.peak_hour.clicks.max(load.clicks);
+        }
+        days.into_values().collect()
+    }
+}
+
+#[derive(Default)]
+pub struct DayLoad {
+    pub date: NaiveDate,
+    pub total: Load,
+    /// The busiest clock hour's keys and clicks (not necessarily the same
+    /// hour for both).
+    pub peak_hour: Load,
+}
+
+impl DayLoad {
+    pub fn warnings(&self, config: &ErgonomicsConfig) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if self.total.keys > config.keys_per_day {
+            warnings.push(format!("{} keystrokes (limit {})", self.total.keys, config.keys_per_day));
+        }
+        if self.total.clicks > config.clicks_per_day {
+            warnings.push(format!("{} clicks (limit {})", self.total.clicks, config.clicks_per_day));
+        }
+        if self.peak_hour.keys > config.keys_per_hour {
+            warnings.push(format!(
+                "{} keystrokes in one hour (limit {})",
+                self.peak_hour.keys, config.keys_per_hour
+            ));
+        }
+        if self.peak_hour.clicks > config.clicks_per_hour {
+            warnings.push(format!(
+                "{} clicks in one hour (limit {})",
+                self.peak_hour.clicks, config.clicks_per_hour
+            ));
+        }
+        warnings
+    }
+}
+
+/// A gentle reminder when the last hour of `records` exceeds the hourly
+/// limits.
+pub fn recent_strain(records: &[ActivityRecord], config: &ErgonomicsConfig) -> Option<String> {
+    let last = records.last()?.timestamp;
+    let from = records.partition_point(|r| r.timestamp + 3600 <= last);
+    let recent = &records[from..];
+
+    let mut load = Load::default();
+    for (i, record) in recent.iter().enumerate() {
+        let previous = i.checked_sub(1).map(|p| &recent[p]);
+        load.keys += analysis::new_key_presses(previous, record);
+        load.clicks += analysis::new_clicks(previous, record);
+    }
+
+    if load.keys > config.keys_per_hour {
+        Some(format!(
+            "{} keystrokes in the last hour, above your limit of {}. A short break to stretch your hands would help.",
+            load.keys, config.keys_per_hour
+        ))
+    } else if load.clicks > config.clicks_per_hour {
+        Some(format!(
+            "{} clicks in the last hour, above your limit of {}. A short break to rest your mouse hand would help.",
+            load.clicks, config.clicks_per_hour
+        ))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,480 @@
+//! Input read straight from Linux's evdev devices, for what the display
+//! server doesn't pass on:
+//!
+//! - with `"pen": true`, a stylus's pressure, tilt and proximity
+//! - with `"touch": true`, the contacts on touchscreens and touchpads, and
+//!   the taps, swipes and pinches they make, rather than only the pointer
+//!   movement they are turned into
+//! - with `"identify_devices": true`, which keyboards and pointing devices
+//!   were used, e.g. to tell a laptop's own keyboard from an external one.
+//!   Devices are named as the kernel knows them, with their USB vendor and
+//!   product IDs: `Logitech K380 (046d:b342)`
+//! - with `"hotplug": true`, when keyboards and pointing devices are
+//!   plugged in or removed, e.g. a Bluetooth keyboard reconnecting
+//!
+//! Devices that are unplugged and come back are read again; whether or not
+//! `hotplug` is on, the ones being read are recorded as `connected` or
+//! `disconnected` in the sample where that happened.
+//!
+//! Needs read access to `/dev/input/event*`, which usually means being in
+//! the `input` group. Other platforms don't expose these to a background
+//! process, so nothing is recorded there.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::capture::DeviceInput;
+
+/// A stylus within range of its tablet.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PenState {
+    /// "pen", or "eraser" for the back end.
+    pub tool: String,
+    /// Whether the tip touched the surface at any time since the previous
+    /// sample.
+    pub touching: bool,
+    /// Latest tip pressure, from 0 to 1.
+    pub pressure: f32,
+    /// Tilt in degrees to the right and towards the user, where the tablet
+    /// reports it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilt_x: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tilt_y: Option<f32>,
+}
+
+/// A touch surface in use.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TouchState {
+    pub device: String,
+    /// A touchscreen rather than a touchpad.
+    pub screen: bool,
+    /// Fingers on the surface, as fractions of its width and height from
+    /// the top left.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub points: Vec<(f32, f32)>,
+    /// Gestures finished since the previous sample.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gestures: Vec<Gesture>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Gesture {
+    pub kind: GestureKind,
+    pub fingers: u8,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GestureKind {
+    Tap,
+    SwipeLeft,
+    SwipeRight,
+    SwipeUp,
+    SwipeDown,
+    PinchIn,
+    PinchOut,
+}
+
+/// Which kinds of devices to read.
+#[derive(Clone, Copy)]
+pub struct Wanted {
+    pub pen: bool,
+    pub touch: bool,
+    pub identify: bool,
+    pub hotplug: bool,
+}
+
+impl Wanted {
+    pub fn any(self) -> bool {
+        self.pen || self.touch || self.identify || self.hotplug
+    }
+}
+
+/// State kept between samples.
+#[derive(Default)]
+pub struct Evdev {
+    /// `None` while no stylus is in range.
+    pen: Option<PenState>,
+    pen_touched: bool,
+    /// One per touch device.
+    touch: Vec<TouchState>,
+    /// Devices used since the last sample.
+    keyboards: Vec<String>,
+    pointers: Vec<String>,
+    /// Devices plugged in or removed since the last sample.
+    connected: Vec<String>,
+    disconnected: Vec<String>,
+}
+
+impl Evdev {
+    /// Adds what was seen since the last call to `input`.
+    pub fn take(&mut self, input: &mut DeviceInput) {
+        input.pen = self.pen.clone().map(|mut pen| {
+            pen.touching |= self.pen_touched;
+            pen
+        });
+        self.pen_touched = false;
+        input.touch = self
+            .touch
+            .iter_mut()
+            .filter(|touch| !touch.points.is_empty() || !touch.gestures.is_empty())
+            .map(|touch| TouchState {
+                device: touch.device.clone(),
+                screen: touch.screen,
+                points: touch.points.clone(),
+                gestures: std::mem::take(&mut touch.gestures),
+            })
+            .collect();
+        input.keyboards = std::mem::take(&mut self.keyboards);
+        input.pointers = std::mem::take(&mut self.pointers);
+        input.connected.append(&mut self.connected);
+        input.disconnected.append(&mut self.disconnected);
+    }
+}
+
+fn add(list: &mut Vec<String>, device: &str) {
+    if !list.iter().any(|d| d == device) {
+        list.push(device.to_string());
+    }
+}
+
+/// Farthest, as a fraction of the surface, and longest a finger may move
+/// and stay down for a tap.
+const TAP_TRAVEL: f32 = 0.03;
+const TAP_MILLIS: u128 = 300;
+/// Least distance fingers must move together for a swipe.
+const SWIPE_TRAVEL: f32 = 0.15;
+/// Change in the distance between two fingers that makes a pinch.
+const PINCH_RATIO: f32 = 1.3;
+
+/// Where a finger came down and where it is now.
+type Path = ((f32, f32), (f32, f32));
+
+/// One touch, from the first finger down to the last one up.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+struct Stroke {
+    began: std::time::Instant,
+    fingers: usize,
+    /// First and last position of each finger, by slot.
+    paths: Vec<Option<Path>>,
+    /// Distance between the first two fingers when they both came down,
+    /// and the last time both were down.
+    spread: Option<(f32, f32)>,
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+impl Stroke {
+    fn gesture(&self) -> Option<Gesture> {
+        let paths: Vec<_> = self.paths.iter().flatten().collect();
+        let travel = |(start, end): &&Path| (end.0 - start.0, end.1 - start.1);
+        let fingers = self.fingers.min(u8::MAX as usize) as u8;
+
+        if let Some((start, end)) = self.spread.filter(|(start, _)| *start > 0.0) {
+            if end / start > PINCH_RATIO {
+                return Some(Gesture { kind: GestureKind::PinchOut, fingers });
+            }
+            if start / end.max(f32::EPSILON) > PINCH_RATIO {
+                return Some(Gesture { kind: GestureKind::PinchIn, fingers });
+            }
+        }
+        let farthest = paths.iter().map(travel).map(|(dx, dy)| dx.hypot(dy)).fold(0.0, f32::max);
+        if farthest < TAP_TRAVEL && self.began.elapsed().as_millis() < TAP_MILLIS {
+            return Some(Gesture { kind: GestureKind::Tap, fingers });
+        }
+        let count = paths.len().max(1) as f32;
+        let (dx, dy) = paths.iter().map(travel).fold((0.0, 0.0), |sum, (dx, dy)| (sum.0 + dx / count, sum.1 + dy / count));
+        if dx.hypot(dy) < SWIPE_TRAVEL {
+            return None;
+        }
+        let kind = match (dx.abs() > dy.abs(), dx > 0.0, dy > 0.0) {
+            (true, true, _) => GestureKind::SwipeRight,
+            (true, false, _) => GestureKind::SwipeLeft,
+            (false, _, true) => GestureKind::SwipeDown,
+            (false, _, false) => GestureKind::SwipeUp,
+        };
+        Some(Gesture { kind, fingers })
+    }
+}
+
+/// Reads the wanted devices on a thread until `stop` is set.
+#[cfg(target_os = "linux")]
+pub fn spawn(wanted: Wanted, stop: Arc<AtomicBool>, state: Arc<Mutex<Evdev>>) {
+    use std::io;
+    use std::path::PathBuf;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use evdev::{AbsoluteAxisCode, Device, EventSummary, KeyCode, PropType, RelativeAxisCode, SynchronizationCode};
+
+    /// Time between two reads of every device.
+    const POLL: Duration = Duration::from_millis(5);
+    /// Time between two looks for devices that were plugged in.
+    const RESCAN: Duration = Duration::from_secs(2);
+    /// Contacts tracked when a device doesn't say how many it supports.
+    const DEFAULT_SLOTS: usize = 10;
+
+    enum Kind {
+        Pen {
+            pressure: (i32, i32),
+            /// Units per degree of tilt.
+            tilt: f32,
+        },
+        Touch {
+            /// Index into `Evdev::touch`.
+            index: usize,
+            x: (i32, i32),
+            y: (i32, i32),
+            slot: usize,
+            /// Position of the finger in each slot; `Some((None, None))`
+            /// for one that is down but hasn't reported where yet.
+            contacts: Vec<Option<(Option<i32>, Option<i32>)>>,
+            stroke: Option<Stroke>,
+            /// Set for touchpads when pointing devices are identified.
+            label: Option<String>,
+        },
+        /// A keyboard or pointing device read only to tell when it is used.
+        Identify { label: String },
+        /// A device only watched for being unplugged.
+        Watch,
+    }
+
+    struct Open {
+        path: PathBuf,
+        label: String,
+        device: Device,
+        kind: Kind,
+    }
+
+    /// Opens the wanted devices that aren't open yet, returning their labels.
+    fn attach(open: &mut Vec<Open>, wanted: Wanted, state: &Mutex<Evdev>) -> Vec<String> {
+        let range = |min: i32, max: i32| (min, max.max(min + 1));
+        let mut attached = Vec::new();
+        for (path, device) in evdev::enumerate() {
+            if open.iter().any(|open| open.path == path) {
+                continue;
+            }
+            let name = device.name().unwrap_or("unnamed").to_string();
+            let has_key = |key| device.supported_keys().is_some_and(|keys| keys.contains(key));
+            let has_axis = |axis| device.supported_absolute_axes().is_some_and(|axes| axes.contains(axis));
+            let absinfo: Vec<_> = device.get_absinfo().into_iter().flatten().collect();
+            let info = |axis| absinfo.iter().find(|(code, _)| *code == axis).map(|(_, info)| *info);
+            let id = device.input_id();
+            let label = format!("{} ({:04x}:{:04x})", name, id.vendor(), id.product());
+
+            let kind = if has_key(KeyCode::BTN_TOOL_PEN) && has_axis(AbsoluteAxisCode::ABS_PRESSURE) && wanted.pen {
+                // Resolution is in units per radian; most tablets report whole degrees
+                let tilt = info(AbsoluteAxisCode::ABS_TILT_X)
+                    .filter(|info| info.resolution() > 0)
+                    .map_or(1.0, |info| info.resolution() as f32 * std::f32::consts::PI / 180.0);
+                let pressure = info(AbsoluteAxisCode::ABS_PRESSURE).map_or((0, 1), |info| range(info.minimum(), info.maximum()));
+                tracing::info!("reading stylus {} ({})", name, path.display());
+                Kind::Pen { pressure, tilt }
+            } else if has_axis(AbsoluteAxisCode::ABS_MT_POSITION_X) && wanted.touch {
+                let (Some(x), Some(y)) = (info(AbsoluteAxisCode::ABS_MT_POSITION_X), info(AbsoluteAxisCode::ABS_MT_POSITION_Y)) else {
+                    continue;
+                };
+                let slots = info(AbsoluteAxisCode::ABS_MT_SLOT).map_or(DEFAULT_SLOTS, |info| info.maximum().max(0) as usize + 1);
+                let screen = device.properties().contains(PropType::DIRECT);
+                let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                // One plugged back in keeps its entry
+                let index = match state.touch.iter().position(|touch| touch.device == name && touch.screen == screen) {
+                    Some(index) => index,
+                    None => {
+                        state.touch.push(TouchState {
+                            device: name.clone(),
+                            screen,
+                            points: Vec::new(),
+                            gestures: Vec::new(),
+                        });
+                        state.touch.len() - 1
+                    }
+                };
+                tracing::info!("reading {} {} ({})", if screen { "touchscreen" } else { "touchpad" }, name, path.display());
+                Kind::Touch {
+                    index,
+                    x: range(x.minimum(), x.maximum()),
+                    y: range(y.minimum(), y.maximum()),
+                    slot: 0,
+                    contacts: vec![None; slots],
+                    stroke: None,
+                    label: (wanted.identify && !screen).then(|| label.clone()),
+                }
+            } else if (wanted.identify || wanted.hotplug)
+                && (has_key(KeyCode::KEY_A)
+                    || has_key(KeyCode::BTN_LEFT)
+                    || device.supported_relative_axes().is_some_and(|axes| axes.contains(RelativeAxisCode::REL_X)))
+            {
+                if wanted.identify {
+                    tracing::info!("identifying input from {} ({})", label, path.display());
+                    Kind::Identify { label: label.clone() }
+                } else {
+                    Kind::Watch
+                }
+            } else {
+                continue;
+            };
+            if let Err(e) = device.set_nonblocking(true) {
+                tracing::warn!("Failed to read {} ({}): {}", name, path.display(), e);
+                continue;
+            }
+            attached.push(label.clone());
+            open.push(Open { path, label, device, kind });
+        }
+        attached
+    }
+
+    let mut devices = Vec::new();
+    attach(&mut devices, wanted, &state);
+    if devices.is_empty() {
+        tracing::warn!("No input devices to read; is the user allowed to read /dev/input?");
+    }
+
+    thread::spawn(move || {
+        let mut scanned = Instant::now();
+        while !stop.load(Ordering::SeqCst) {
+            if scanned.elapsed() >= RESCAN {
+                scanned = Instant::now();
+                for label in attach(&mut devices, wanted, &state) {
+                    tracing::info!("input device connected: {}", label);
+                    add(&mut state.lock().unwrap_or_else(|e| e.into_inner()).connected, &label);
+                }
+            }
+            devices.retain_mut(|Open { label, device, kind, .. }| {
+                let events = match device.fetch_events() {
+                    Ok(events) => events,
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => return true,
+                    // Unplugged; `attach` opens it again when it's back
+                    Err(e) => {
+                        tracing::info!("input device disconnected: {} ({})", label, e);
+                        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                        match kind {
+                            Kind::Pen { .. } => state.pen = None,
+                            Kind::Touch { index, .. } => state.touch[*index].points.clear(),
+                            _ => {}
+                        }
+                        add(&mut state.disconnected, label);
+                        return false;
+                    }
+                };
+                let mut guard = state.lock().unwrap_or_else(|e| e.into_inner());
+                let state = &mut *guard;
+                for event in events {
+                    match (&mut *kind, event.destructure()) {
+                        (Kind::Pen { .. }, EventSummary::Key(_, tool @ (KeyCode::BTN_TOOL_PEN | KeyCode::BTN_TOOL_RUBBER), value)) => {
+                            state.pen = (value != 0).then(|| PenState {
+                                tool: if tool == KeyCode::BTN_TOOL_PEN { "pen" } else { "eraser" }.to_string(),
+                                touching: false,
+                                pressure: 0.0,
+                                tilt_x: None,
+                                tilt_y: None,
+                            });
+                        }
+                        (Kind::Pen { .. }, EventSummary::Key(_, KeyCode::BTN_TOUCH, value)) => {
+                            if let Some(pen) = &mut state.pen {
+                                pen.touching = value != 0;
+                            }
+                            state.pen_touched |= value != 0;
+                        }
+                        (Kind::Pen { pressure, tilt }, EventSummary::AbsoluteAxis(_, axis, value)) => {
+                            let Some(pen) = &mut state.pen else {
+                                continue;
+                            };
+                            match axis {
+                                AbsoluteAxisCode::ABS_PRESSURE => {
+                                    let (min, max) = *pressure;
+                                    pen.pressure = ((value - min) as f32 / (max - min) as f32).clamp(0.0, 1.0);
+                                }
+                                AbsoluteAxisCode::ABS_TILT_X => pen.tilt_x = Some(value as f32 / *tilt),
+                                AbsoluteAxisCode::ABS_TILT_Y => pen.tilt_y = Some(value as f32 / *tilt),
+                                _ => {}
+                            }
+                        }
+                        (Kind::Touch { slot, contacts, .. }, EventSummary::AbsoluteAxis(_, axis, value)) => match axis {
+                            AbsoluteAxisCode::ABS_MT_SLOT => *slot = value.max(0) as usize,
+                            AbsoluteAxisCode::ABS_MT_TRACKING_ID => {
+                                if let Some(contact) = contacts.get_mut(*slot) {
+                                    *contact = (value >= 0).then_some((None, None));
+                                }
+                            }
+                            AbsoluteAxisCode::ABS_MT_POSITION_X => {
+                                if let Some(Some(contact)) = contacts.get_mut(*slot) {
+                                    contact.0 = Some(value);
+                                }
+                            }
+                            AbsoluteAxisCode::ABS_MT_POSITION_Y => {
+                                if let Some(Some(contact)) = contacts.get_mut(*slot) {
+                                    contact.1 = Some(value);
+                                }
+                            }
+                            _ => {}
+                        },
+                        // A frame is complete
+                        (
+                            Kind::Touch { index, x, y, contacts, stroke, label, .. },
+                            EventSummary::Synchronization(_, SynchronizationCode::SYN_REPORT, _),
+                        ) => {
+                            let normalize = |value: i32, (min, max): (i32, i32)| (value - min) as f32 / (max - min) as f32;
+                            let down: Vec<(usize, (f32, f32))> = contacts
+                                .iter()
+                                .enumerate()
+                                .filter_map(|(slot, contact)| match contact {
+                                    Some((Some(px), Some(py))) => Some((slot, (normalize(*px, *x), normalize(*py, *y)))),
+                                    _ => None,
+                                })
+                                .collect();
+                            let touch = &mut state.touch[*index];
+                            touch.points = down.iter().map(|(_, point)| *point).collect();
+                            if let Some(label) = label.as_deref().filter(|_| !down.is_empty()) {
+                                add(&mut state.pointers, label);
+                            }
+
+                            if down.is_empty() {
+                                if let Some(gesture) = stroke.take().and_then(|stroke| stroke.gesture()) {
+                                    touch.gestures.push(gesture);
+                                }
+                                continue;
+                            }
+                            let stroke = stroke.get_or_insert_with(|| Stroke {
+                                began: Instant::now(),
+                                fingers: 0,
+                                paths: vec![None; contacts.len()],
+                                spread: None,
+                            });
+                            stroke.fingers = stroke.fingers.max(down.len());
+                            for (slot, point) in &down {
+                                let path = stroke.paths[*slot].get_or_insert((*point, *point));
+                                path.1 = *point;
+                            }
+                            if let [(_, a), (_, b), ..] = down[..] {
+                                let distance = (a.0 - b.0).hypot(a.1 - b.1);
+                                let spread = stroke.spread.get_or_insert((distance, distance));
+                                spread.1 = distance;
+                            }
+                        }
+                        // Mouse buttons are in the key range too
+                        (Kind::Identify { label }, EventSummary::Key(_, key, value)) if value != 0 => {
+                            if (KeyCode::BTN_LEFT.0..=KeyCode::BTN_TASK.0).contains(&key.0) {
+                                add(&mut state.pointers, label);
+                            } else {
+                                add(&mut state.keyboards, label);
+                            }
+                        }
+                        (Kind::Identify { label }, EventSummary::RelativeAxis(..)) => add(&mut state.pointers, label),
+                        _ => {}
+                    }
+                }
+                true
+            });
+            thread::sleep(POLL);
+        }
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn spawn(_wanted: Wanted, _stop: Arc<AtomicBool>, _state: Arc<Mutex<Evdev>>) {
+    tracing::warn!("Stylus, touch and device capture are only available on Linux");
+}
@@ -0,0 +1,226 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::Duration;
+
+use device_query::Keycode;
+use serde::{Deserialize, Serialize};
+
+use crate::analysis;
+use crate::capture::DeviceInput;
+use crate::context::SystemContext;
+use crate::recorder::{ActivityRecord, Gap, GapReason};
+use crate::storage::StorageError;
+
+/// One sample as it appears in JSON exports and the plugin protocol.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JsonRecord {
+    pub timestamp: u64,
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub keys_pressed: Vec<String>,
+    #[serde(default)]
+    pub mouse_buttons: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marker: Option<String>,
+    /// Nothing was captured between `start` and this sample's timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gap: Option<JsonGap>,
+    #[serde(default, skip_serializing_if = "DeviceInput::is_empty")]
+    pub devices: DeviceInput,
+    #[serde(default, skip_serializing_if = "SystemContext::is_empty")]
+    pub context: SystemContext,
+    /// Positions the pointer passed through since the previous sample, as
+    /// `[milliseconds since the epoch, x, y]`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<(u64, i32, i32)>,
+    /// Pointer speed in px/s; only in exports that ask for kinematics.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub velocity: Option<f64>,
+    /// Change in pointer speed in px/s².
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acceleration: Option<f64>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct JsonGap {
+    pub reason: GapReason,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl From<&ActivityRecord> for JsonRecord {
+    fn from(r: &ActivityRecord) -> Self {
+        JsonRecord {
+            timestamp: r.timestamp,
+            mouse_x: r.mouse_x,
+            mouse_y: r.mouse_y,
+            keys_pressed: r.keys_pressed.iter().map(|k| format!("{:?}", k)).collect(),
+            mouse_buttons: r.mouse_buttons.clone(),
+            app: r.app.clone(),
+            marker: r.marker.clone(),
+            gap: r.gap.map(|gap| JsonGap {
+                reason: gap.reason,
+                start: gap.start,
+                end: r.timestamp,
+            }),
+            devices: r.devices.clone(),
+            context: r.context.clone(),
+            path: r.path.clone(),
+            velocity: None,
+            acceleration: None,
+        }
+    }
+}
+
+impl JsonRecord {
+    pub fn into_record(self) -> Result<ActivityRecord, String> {
+        let keys_pressed = self
+            .keys_pressed
+            .iter()
+            .map(|k| k.parse::<Keycode>().map_err(|_| format!("unknown key {}", k)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ActivityRecord {
+            timestamp: self.timestamp,
+            mouse_x: self.mouse_x,
+            mouse_y: self.mouse_y,
+            keys_pressed,
+            mouse_buttons: self.mouse_buttons,
+            app: self.app,
+            marker: self.marker,
+            gap: self.gap.map(|gap| Gap { reason: gap.reason, start: gap.start }),
+            devices: self.devices,
+            context: self.context,
+            path: self.path,
+        })
+    }
+}
+
+/// Writes the records as a JSON array, one object per sample. Given the
+/// interval samples were taken at as `kinematics`, each sample also gets
+/// its pointer velocity and acceleration.
+pub fn write_json(records: &[ActivityRecord], kinematics: Option<Duration>, path: &Path) -> Result<(), StorageError> {
+    let file = File::create(path).map_err(|e| StorageError::Create(path.to_path_buf(), e))?;
+
+    let mut json_records: Vec<JsonRecord> = records.iter().map(JsonRecord::from).collect();
+    if let Some(interval) = kinematics {
+        for (json, motion) in json_records.iter_mut().zip(analysis::motion_series(records, interval)) {
+            json.velocity = motion.velocity;
+            json.acceleration = motion.acceleration;
+        }
+    }
+
+    let mut out = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut out, &json_records)
+        .map_err(io::Error::from)
+        .and_then(|_| out.flush())
+        .map_err(|e| StorageError::Write(path.to_path_buf(), e))
+}
+
+/// Reads a JSON export back into records. Exports from before `mouse_buttons`,
+/// `app`, `marker`, `gap`, `devices`, `context` and `path` existed are
+/// accepted too.
+pub fn load_json(path: &Path) -> Result<Vec<ActivityRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let json_records: Vec<JsonRecord> = serde_json::from_reader(io::BufReader::new(file))
+        .map_err(|e| format!("{} is not a JSON session export: {}", path.display(), e))?;
+    json_records.into_iter().map(JsonRecord::into_record).collect()
+}
+
+/// Douglas-Peucker simplification of the mouse trajectory: samples that lie
+/// within `tolerance` pixels of the simplified polyline are dropped. Samples
+/// that carry anything besides a position (keys, buttons, a marker, a gap,
+/// other devices' input, system context or a change of app) are always
+/// kept and split the path into separately simplified stretches, so only
+/// redundant movement is lost. The pointer paths polled between samples
+/// are dropped too.
+pub fn simplify_path(records: Vec<ActivityRecord>, tolerance: f64) -> Vec<ActivityRecord> {
+    let mut keep = vec![false; records.len()];
+    let mut stretch_start = 0;
+
+    for i in 0..records.len() {
+        let eventful = i == 0
+            || !records[i].keys_pressed.is_empty()
+            || !records[i].mouse_buttons.is_empty()
+            || records[i].marker.is_some()
+            || records[i].gap.is_some()
+            || !records[i].devices.is_empty()
+            || !records[i].context.is_empty()
+            || records[i].app != records[i - 1].app;
+        if eventful || i == records.len() - 1 {
+            keep[i] = true;
+            douglas_peucker(&records, stretch_start, i, tolerance, &mut keep);
+            stretch_start = i;
+        }
+    }
+
+    records
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, kept)| *kept)
+        .map(|(r, _)| ActivityRecord { path: Vec::new(), ..r })
+        .collect()
+}
+
+/// Marks the points between `first` and `last` that the simplified path needs.
+fn douglas_peucker(records: &[ActivityRecord], first: usize, last: usize, tolerance: f64, keep: &mut [bool]) {
+    let mut stack = vec![(first, last)];
+    while let Some((a, b)) = stack.pop() {
+        let farthest = (a + 1..b)
+            .map(|i| (i, distance_to_segment(&records[i], &records[a], &records[b])))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+        if let Some((i, dist)) = farthest {
+            if dist > tolerance {
+                keep[i] = true;
+                stack.push((a, i));
+                stack.push((i, b));
+            }
+        }
+    }
+}
+
+fn distance_to_segment(p: &ActivityRecord, a: &ActivityRecord, b: &ActivityRecord) -> f64 {
+    let (px, py) = (p.mouse_x as f64, p.mouse_y as f64);
+    let (ax, ay) = (a.mouse_x as f64, a.mouse_y as f64);
+    let (bx, by) = (b.mouse_x as f64, b.mouse_y as f64);
+    let (dx, dy) = (bx - ax, by - ay);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq == 0.0 { 0.0 } else { (((px - ax) * dx + (py - ay) * dy) / len_sq).clamp(0.0, 1.0) };
+    (px - ax - t * dx).hypot(py - ay - t * dy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, mouse_x: i32, mouse_y: i32) -> ActivityRecord {
+        ActivityRecord {
+            timestamp,
+            mouse_x,
+            mouse_y,
+            keys_pressed: Vec::new(),
+            mouse_buttons: Vec::new(),
+            app: None,
+            marker: None,
+            gap: None,
+            devices: DeviceInput::default(),
+            context: SystemContext::default(),
+            path: vec![(timestamp * 1000 - 500, mouse_x, mouse_y)],
+        }
+    }
+
+    #[test]
+    fn simplify_path_drops_straight_runs_but_keeps_events() {
+        let mut records: Vec<ActivityRecord> = (0..10).map(|i| sample(100 + i, i as i32 * 10, 0)).collect();
+        records[5].keys_pressed.push(Keycode::A);
+        records.push(sample(110, 90, 50));
+
+        let simplified = simplify_path(records, 1.0);
+        let kept: Vec<u64> = simplified.iter().map(|r| r.timestamp).collect();
+        assert_eq!(kept, [100, 105, 109, 110]);
+        assert!(simplified.iter().all(|r| r.path.is_empty()));
+    }
+}
@@ -0,0 +1,159 @@
+//! Fatigue indicators: the session is cut into fixed windows and each is
+//! compared with the first, looking for slower typing, more corrections
+//! (Backspace/Delete) and longer pauses.
+
+use device_query::Keycode;
+use serde::Deserialize;
+
+use crate::recorder::ActivityRecord;
+
+/// Shortest run without input that counts as a pause.
+const PAUSE_SECS: u64 = 2;
+/// Windows with fewer key presses say too little about typing to compare.
+const MIN_KEYS: usize = 20;
+
+/// `fatigue` section of the settings file.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FatigueConfig {
+    /// Warn during recording when a threshold is crossed.
+    pub alerts: bool,
+    pub window_minutes: u64,
+    /// Fractional drop in keys per minute against the first window.
+    pub speed_drop: f64,
+    /// Fractional rise in the correction-key ratio.
+    pub correction_rise: f64,
+    /// Fractional rise in the mean pause length.
+    pub pause_rise: f64,
+}
+
+impl Default for FatigueConfig {
+    fn default() -> Self {
+        FatigueConfig {
+            alerts: false,
+            window_minutes: 15,
+            speed_drop: 0.25,
+            correction_rise: 0.5,
+            pause_rise: 0.5,
+        }
+    }
+}
+
+impl FatigueConfig {
+    pub fn window_secs(&self) -> u64 {
+        self.window_minutes.max(1) * 60
+    }
+}
+
+pub struct FatigueWindow {
+    pub start: u64,
+    pub key_presses: usize,
+    pub keys_per_minute: f64,
+    /// Share of key presses that were Backspace or Delete.
+    pub correction_ratio: f64,
+    pub mean_pause_secs: f64,
+}
+
+pub fn windows(records: &[ActivityRecord], window_secs: u64) -> Vec<FatigueWindow> {
+    let Some(first) = records.first() else {
+        return Vec::new();
+    };
+    let window_secs = window_secs.max(1);
+
+    let mut windows = Vec::new();
+    let mut rest = records;
+    while let Some(head) = rest.first() {
+        let start = first.timestamp + head.timestamp.saturating_sub(first.timestamp) / window_secs * window_secs;
+        let len = rest
+            .iter()
+            .position(|r| r.timestamp >= start + window_secs)
+            .unwrap_or(rest.len());
+        let (chunk, tail) = rest.split_at(len);
+        rest = tail;
+
+        let span = if rest.is_empty() {
+            chunk.last().map_or(0, |r| r.timestamp.saturating_sub(start))
+        } else {
+            window_secs
+        };
+        // A short trailing window would skew the rates
+        if rest.is_empty() && !windows.is_empty() && span * 2 < window_secs {
+            break;
+        }
+        windows.push(measure(chunk, start, span.max(1)));
+    }
+
+    windows
+}
+
+fn measure(records: &[ActivityRecord], start: u64, span_secs: u64) -> FatigueWindow {
+    let mut key_presses = 0;
+    let mut corrections = 0;
+    let mut pauses = Vec::new();
+    let mut idle_since: Option<u64> = None;
+
+    let mut previous: Option<&ActivityRecord> = None;
+    for record in records {
+        let held: &[Keycode] = previous.map_or(&[], |p| &p.keys_pressed);
+        for key in record.keys_pressed.iter().filter(|k| !held.contains(k)) {
+            key_presses += 1;
+            if matches!(key, Keycode::Backspace | Keycode::Delete) {
+                corrections += 1;
+            }
+        }
+
+        let moved = previous.is_some_and(|p| p.mouse_x != record.mouse_x || p.mouse_y != record.mouse_y);
+        if moved || !record.keys_pressed.is_empty() {
+            if let Some(since) = idle_since.take() {
+                let length = record.timestamp.saturating_sub(since);
+                if length >= PAUSE_SECS {
+                    pauses.push(length);
+                }
+            }
+        } else if idle_since.is_none() {
+            idle_since = Some(record.timestamp);
+        }
+        previous = Some(record);
+    }
+
+    FatigueWindow {
+        start,
+        key_presses,
+        keys_per_minute: key_presses as f64 * 60.0 / span_secs as f64,
+        correction_ratio: if key_presses == 0 { 0.0 } else { corrections as f64 / key_presses as f64 },
+        mean_pause_secs: if pauses.is_empty() {
+            0.0
+        } else {
+            pauses.iter().sum::<u64>() as f64 / pauses.len() as f64
+        },
+    }
+}
+
+/// Compares the latest window against the first and describes every
+/// indicator past its threshold.
+pub fn warnings(windows: &[FatigueWindow], config: &FatigueConfig) -> Vec<String> {
+    let (Some(first), Some(last)) = (windows.first(), windows.last()) else {
+        return Vec::new();
+    };
+    if windows.len() < 2 || first.key_presses < MIN_KEYS || last.key_presses < MIN_KEYS {
+        return Vec::new();
+    }
+
+    let change = |before: f64, after: f64| if before > 0.0 { after / before - 1.0 } else { 0.0 };
+    let mut warnings = Vec::new();
+
+    let speed = change(first.keys_per_minute, last.keys_per_minute);
+    if -speed >= config.speed_drop {
+        warnings.push(format!("typing is {:.0}% slower than at the start", -speed * 100.0));
+    }
+    let corrections = change(first.correction_ratio, last.correction_ratio);
+    if corrections >= config.correction_rise {
+        warnings.push(format!("{:.0}% more corrections than at the start", corrections * 100.0));
+    }
+    let pauses = change(first.mean_pause_secs, last.mean_pause_secs);
+    if pauses >= config.pause_rise {
+        warnings.push(format!("pauses are {:.0}% longer than at the start", pauses * 100.0));
+    }
+
+    warnings
+}
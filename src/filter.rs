@@ -0,0 +1,85 @@
+//! Capture filters, configured under `filters` in the settings file. A
+//! sample matching any rule is dropped before it is stored, so it never
+//! reaches disk. A rule holds one or more conditions and matches when all
+//! of them do:
+//!
+//! ```json
+//! "filters": [
+//!     { "app": "(?i)keepass" },
+//!     { "region": { "x": 0, "y": 0, "width": 400, "height": 300 } },
+//!     { "app": "^firefox$", "keys": ["digits", "symbols"] }
+//! ]
+//! ```
+//!
+//! `app` is a regex over the focused application's name or executable; `keys` matches a
+//! sample holding any key from the listed categories.
+
+use active_win_pos_rs::ActiveWindow;
+use serde::Deserialize;
+
+use crate::config::Pattern;
+use crate::keys::{self, KeyCategory};
+use crate::recorder::ActivityRecord;
+use crate::window;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterRule {
+    app: Option<Pattern>,
+    region: Option<Region>,
+    #[serde(default)]
+    keys: Vec<KeyCategory>,
+}
+
+/// Screen rectangle in the same coordinates as the recorded mouse position.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Region {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+impl Region {
+    fn contains(&self, x: i32, y: i32) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+impl FilterRule {
+    /// A rule matching any sample that holds a key from `categories`.
+    pub fn keys(categories: Vec<KeyCategory>) -> Self {
+        FilterRule { app: None, region: None, keys: categories }
+    }
+
+    pub fn needs_window(&self) -> bool {
+        self.app.is_some()
+    }
+
+    pub fn matches(&self, record: &ActivityRecord, window: Option<&ActiveWindow>) -> bool {
+        if self.app.is_none() && self.region.is_none() && self.keys.is_empty() {
+            return false;
+        }
+
+        let app_matches = self.app.as_ref().is_none_or(|pattern| {
+            window.is_some_and(|w| window::app_matches(pattern, w))
+        });
+        let region_matches = self
+            .region
+            .as_ref()
+            .is_none_or(|region| region.contains(record.mouse_x, record.mouse_y));
+        let keys_matches = self.keys.is_empty()
+            || record
+                .keys_pressed
+                .iter()
+                .any(|&k| self.keys.contains(&keys::category(k)));
+
+        app_matches && region_matches && keys_matches
+    }
+}
+
+/// True when the sample should not be recorded.
+pub fn is_filtered(rules: &[FilterRule], record: &ActivityRecord, window: Option<&ActiveWindow>) -> bool {
+    rules.iter().any(|rule| rule.matches(record, window))
+}
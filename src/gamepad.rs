@@ -0,0 +1,168 @@
+//! Gamepad and controller capture through gilrs, for playtest sessions.
+//! Turned on with `"gamepads": true` in a build with the `gamepad` feature.
+//!
+//! Each sample gets, for every gamepad in use, the buttons held since the
+//! previous sample and the sticks and triggers that are off centre, e.g.
+//!
+//! ```json
+//! "gamepads": [{ "id": 0, "name": "Xbox Controller", "buttons": ["South"], "axes": { "LeftStickX": -0.42 } }]
+//! ```
+//!
+//! Gamepads left alone are omitted. Button and axis names are gilrs', which
+//! follow the SDL layout: `South` is A on an Xbox pad and ✕ on a PlayStation one.
+//! Gamepads connecting and disconnecting are recorded under `connected` and
+//! `disconnected`.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::capture::DeviceInput;
+
+/// One gamepad's input since the previous sample.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GamepadState {
+    /// gilrs' number for the gamepad, which it keeps across reconnects.
+    pub id: usize,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<String>,
+    /// Positions from -1 to 1 for sticks, 0 to 1 for analog triggers.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub axes: BTreeMap<String, f32>,
+}
+
+/// The connected gamepads' current state.
+#[derive(Default)]
+pub struct Gamepads {
+    pads: Vec<Pad>,
+    /// Gamepads connected or disconnected since the last sample.
+    connected: Vec<String>,
+    disconnected: Vec<String>,
+}
+
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+struct Pad {
+    id: usize,
+    name: String,
+    held: Vec<String>,
+    held_since: Vec<String>,
+    axes: BTreeMap<String, f32>,
+}
+
+impl Gamepads {
+    /// Adds what every gamepad in use did since the last call to `input`.
+    pub fn take(&mut self, input: &mut DeviceInput) {
+        input.connected.append(&mut self.connected);
+        input.disconnected.append(&mut self.disconnected);
+        input.gamepads = self
+            .pads
+            .iter_mut()
+            .filter_map(|pad| {
+                let buttons = std::mem::replace(&mut pad.held_since, pad.held.clone());
+                let axes: BTreeMap<String, f32> = pad
+                    .axes
+                    .iter()
+                    // Two decimals are plenty and keep files small
+                    .map(|(axis, &value)| (axis.clone(), (value * 100.0).round() / 100.0))
+                    .filter(|(_, value)| *value != 0.0)
+                    .collect();
+                (!buttons.is_empty() || !axes.is_empty()).then(|| GamepadState {
+                    id: pad.id,
+                    name: pad.name.clone(),
+                    buttons,
+                    axes,
+                })
+            })
+            .collect();
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn pad(&mut self, id: usize, name: &str) -> &mut Pad {
+        let index = match self.pads.iter().position(|pad| pad.id == id) {
+            Some(index) => index,
+            None => {
+                self.pads.push(Pad {
+                    id,
+                    name: name.to_string(),
+                    held: Vec::new(),
+                    held_since: Vec::new(),
+                    axes: BTreeMap::new(),
+                });
+                self.pads.len() - 1
+            }
+        };
+        &mut self.pads[index]
+    }
+}
+
+/// Follows the gamepads on a thread until `stop` is set.
+#[cfg(feature = "gamepad")]
+pub fn spawn(stop: Arc<AtomicBool>, gamepads: Arc<Mutex<Gamepads>>) {
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    use gilrs::{Button, EventType, Gilrs};
+
+    /// Longest wait for an event before checking `stop` again.
+    const WAIT: Duration = Duration::from_millis(100);
+
+    thread::spawn(move || {
+        // Gilrs can't be moved across threads, so it's created on this one
+        let mut gilrs = match Gilrs::new() {
+            Ok(gilrs) => gilrs,
+            Err(e) => {
+                tracing::warn!("gamepads: {}", e);
+                return;
+            }
+        };
+        while !stop.load(Ordering::SeqCst) {
+            let Some(event) = gilrs.next_event_blocking(Some(WAIT)) else {
+                continue;
+            };
+            let name = gilrs.gamepad(event.id).name().to_string();
+            let mut gamepads = gamepads.lock().unwrap_or_else(|e| e.into_inner());
+            let id = usize::from(event.id);
+            match event.event {
+                EventType::ButtonPressed(button, _) => {
+                    let pad = gamepads.pad(id, &name);
+                    let button = format!("{:?}", button);
+                    if !pad.held.contains(&button) {
+                        pad.held.push(button.clone());
+                    }
+                    if !pad.held_since.contains(&button) {
+                        pad.held_since.push(button);
+                    }
+                }
+                EventType::ButtonReleased(button, _) => {
+                    let button = format!("{:?}", button);
+                    gamepads.pad(id, &name).held.retain(|held| *held != button);
+                }
+                EventType::ButtonChanged(button @ (Button::LeftTrigger2 | Button::RightTrigger2), value, _) => {
+                    gamepads.pad(id, &name).axes.insert(format!("{:?}", button), value);
+                }
+                EventType::AxisChanged(axis, value, _) => {
+                    gamepads.pad(id, &name).axes.insert(format!("{:?}", axis), value);
+                }
+                EventType::Connected => {
+                    tracing::info!("gamepad connected: {}", name);
+                    gamepads.connected.push(name);
+                }
+                EventType::Disconnected => {
+                    tracing::info!("gamepad disconnected: {}", name);
+                    gamepads.pads.retain(|pad| pad.id != id);
+                    gamepads.disconnected.push(name);
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(not(feature = "gamepad"))]
+pub fn spawn(_stop: Arc<AtomicBool>, _gamepads: Arc<Mutex<Gamepads>>) {
+    tracing::warn!("This build can't read gamepads; rebuild it with `--features gamepad`");
+}
@@ -0,0 +1,237 @@
+//! Google Calendar integration, configured under `google_calendar` in the
+//! settings file:
+//!
+//! ```json
+//! "google_calendar": {
+//!     "client_id": "....apps.googleusercontent.com",
+//!     "client_secret": "...",
+//!     "calendar": "primary",
+//!     "calendars": { "acme": "c_123...@group.calendar.google.com" }
+//! }
+//! ```
+//!
+//! The client is a "Desktop app" OAuth client from the Google Cloud console.
+//! `activity-tracker google-auth` signs in once and keeps the refresh token
+//! in the data directory; after that every saved session becomes an event,
+//! in the calendar mapped to its project or else in `calendar`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::analysis;
+use crate::cli;
+use crate::http;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GoogleCalendarConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// Calendar ID for sessions whose project has no entry in `calendars`.
+    pub calendar: String,
+    /// Calendar ID per project.
+    pub calendars: BTreeMap<String, String>,
+}
+
+impl Default for GoogleCalendarConfig {
+    fn default() -> Self {
+        GoogleCalendarConfig {
+            client_id: String::new(),
+            client_secret: String::new(),
+            calendar: "primary".to_string(),
+            calendars: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+}
+
+impl GoogleCalendarConfig {
+    /// Signs in through the browser and stores the refresh token. Google
+    /// redirects back to a one-off listener on the loopback interface.
+    pub fn authorize(&self) -> Result<(), String> {
+        if self.client_id.is_empty() {
+            return Err("google_calendar has no client_id".to_string());
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| format!("Failed to listen for the sign-in: {}", e))?;
+        let port = listener.local_addr().map_err(|e| e.to_string())?.port();
+        let redirect_uri = format!("http://127.0.0.1:{}", port);
+        let state = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos().to_string();
+        let url = format!(
+            "{}?{}",
+            AUTH_URL,
+            http::form(&[
+                ("client_id", &self.client_id),
+                ("redirect_uri", &redirect_uri),
+                ("response_type", "code"),
+                ("scope", SCOPE),
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+                ("state", &state),
+            ])
+        );
+        println!("Sign in to Google in your browser; if it does not open, visit:\n{}", url);
+        open_browser(&url);
+
+        let code = receive_code(&listener, &state)?;
+        let response = self.token(&[
+            ("code", &code),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("redirect_uri", &redirect_uri),
+            ("grant_type", "authorization_code"),
+        ])?;
+        let refresh_token = response
+            .refresh_token
+            .ok_or("Google did not return a refresh token; remove the app's access in your Google account and try again")?;
+        let path = token_path().ok_or("No data directory")?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let text = json!({ "refresh_token": refresh_token }).to_string();
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Creates an event spanning the session in its project's calendar.
+    pub fn create_event(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let access_token = self.access_token().map_err(|e| format!("Google Calendar: {}", e))?;
+        let calendar = meta
+            .project
+            .as_ref()
+            .and_then(|project| self.calendars.get(project))
+            .unwrap_or(&self.calendar);
+        let time = |timestamp: u64| {
+            DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default().to_rfc3339()
+        };
+        let mut description = format!(
+            "Tracked {} · {} key presses · {} clicks · {:.1}% idle",
+            cli::format_duration(summary.tracked_secs()),
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        );
+        if let Some(project) = &meta.project {
+            description = format!("Project: {}\n{}", project, description);
+        }
+        let event = json!({
+            "summary": meta.task,
+            "description": description,
+            "start": { "dateTime": time(summary.start) },
+            "end": { "dateTime": time(summary.end.max(summary.start + 1)) },
+        });
+        let url = format!("{}/{}/events", EVENTS_URL, http::encode(calendar));
+        let authorization = format!("Bearer {}", access_token);
+        let headers = [("Authorization", authorization.as_str()), ("Content-Type", "application/json")];
+        http::post(&url, &headers, event.to_string().as_bytes()).map_err(|e| format!("Google Calendar: {}", e))
+    }
+
+    fn access_token(&self) -> Result<String, String> {
+        let path = token_path().ok_or("No data directory")?;
+        let text = fs::read_to_string(&path)
+            .map_err(|_| "not signed in; run `activity-tracker google-auth` first".to_string())?;
+        let stored: serde_json::Value =
+            serde_json::from_str(&text).map_err(|e| format!("Invalid token in {}: {}", path.display(), e))?;
+        let refresh_token = stored["refresh_token"]
+            .as_str()
+            .ok_or_else(|| format!("Invalid token in {}", path.display()))?;
+        let response = self.token(&[
+            ("refresh_token", refresh_token),
+            ("client_id", &self.client_id),
+            ("client_secret", &self.client_secret),
+            ("grant_type", "refresh_token"),
+        ])?;
+        Ok(response.access_token)
+    }
+
+    fn token(&self, fields: &[(&str, &str)]) -> Result<TokenResponse, String> {
+        let body = http::form(fields);
+        let response = http::request(
+            "POST",
+            TOKEN_URL,
+            &[("Content-Type", "application/x-www-form-urlencoded")],
+            body.as_bytes(),
+        )?;
+        serde_json::from_slice(&response).map_err(|e| format!("Unexpected token response: {}", e))
+    }
+}
+
+fn token_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("activity-tracker").join("google_token.json"))
+}
+
+/// Waits for the browser to come back with `?code=...&state=...`.
+fn receive_code(listener: &TcpListener, state: &str) -> Result<String, String> {
+    let (mut stream, _) = listener.accept().map_err(|e| format!("Sign-in failed: {}", e))?;
+    let mut request_line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .map_err(|e| format!("Sign-in failed: {}", e))?;
+    let query = request_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|target| target.split_once('?'))
+        .map_or("", |(_, query)| query);
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| http::decode(value))
+    };
+
+    let result = match (param("code"), param("error")) {
+        _ if param("state").as_deref() != Some(state) => Err("Sign-in failed: unexpected response".to_string()),
+        (Some(code), _) => Ok(code),
+        (None, Some(error)) => Err(format!("Sign-in failed: {}", error)),
+        (None, None) => Err("Sign-in failed: no authorization code".to_string()),
+    };
+    let page = match &result {
+        Ok(_) => "Signed in. You can close this window.",
+        Err(_) => "Sign-in failed. See the terminal for details.",
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        page.len(),
+        page
+    );
+    result
+}
+
+fn open_browser(url: &str) {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    // The URL is printed as well, so a missing opener is not an error
+    let _ = command.arg(url).spawn();
+}
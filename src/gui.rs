@@ -0,0 +1,874 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use device_query::{DeviceQuery, DeviceState};
+use eframe::{egui, App, CreationContext};
+
+use crate::config::Config;
+use crate::recorder::{self, PauseReason, Phase, Recorder, SaveError};
+use crate::storage::{self, SessionMeta};
+use crate::history::{self, RecentTask};
+use crate::qr::QrCode;
+use crate::i18n::tr;
+use crate::{analysis, cli, edit, i18n, ipc, logging, outbox, resample, shutdown};
+
+/// Suggestions shown under the task name while typing.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Initial size of the full window, before zooming.
+const WINDOW_SIZE: egui::Vec2 = egui::vec2(500.0, 520.0);
+
+/// Span and resolution of the activity sparkline.
+const SPARKLINE_WINDOW: Duration = Duration::from_secs(300);
+const SPARKLINE_BUCKET: Duration = Duration::from_secs(10);
+
+/// Time without a new sample after which capture is shown as stalled.
+const STALL_AFTER: Duration = Duration::from_secs(3);
+
+/// Intervals offered for resampled exports, in seconds.
+const RESAMPLE_INTERVALS: [u64; 4] = [1, 5, 10, 60];
+
+/// Bounds for the `ui_scale` setting, as accepted by egui.
+const MIN_SCALE: f32 = 0.2;
+const MAX_SCALE: f32 = 5.0;
+
+#[derive(Default)]
+pub struct ActivityTracker {
+    task_name: String,
+    project: String,
+    estimate: String,
+    notes: String,
+    /// Previously recorded tasks, most recent first.
+    recent_tasks: Vec<RecentTask>,
+    /// Names of the presets in the settings, each started with one click.
+    presets: Vec<String>,
+    /// Whether suggestions from `recent_tasks` are shown under the task name.
+    suggest_open: bool,
+    /// Folder typed into the save error banner.
+    retry_dir: String,
+    remember_dir: bool,
+    /// Moves keyboard focus to the task field on the next frame.
+    focus_task: bool,
+    recorder: Recorder,
+    timer_complete: bool,
+    permission_checked: bool,
+    is_macos: bool,
+    /// Zoom factor last written to the config.
+    ui_scale: f32,
+    /// Sample count at a point in time, to work out the rate since.
+    rate_probe: Option<(Instant, usize)>,
+    /// Samples captured per second over the last probe interval.
+    sample_rate: Option<f64>,
+    /// Window title last sent, so it is only updated when it changes.
+    title: String,
+    /// Showing the small always-on-top window instead of the full one.
+    compact: bool,
+    /// Size of the full window, restored when leaving compact mode.
+    full_size: Option<egui::Vec2>,
+    /// Interval picked for resampled exports of the last session.
+    resample_secs: u64,
+    /// Part of the last saved session picked for trimming or redaction.
+    range: Option<SessionRange>,
+    /// Where the dashboard can be opened, while it is running.
+    dashboard_link: Option<DashboardLink>,
+}
+
+struct DashboardLink {
+    url: String,
+    qr: QrCode,
+    local_only: bool,
+}
+
+struct SessionRange {
+    path: PathBuf,
+    duration: u64,
+    from: u64,
+    to: u64,
+    /// Redaction was asked for and waits to be confirmed.
+    confirm_redact: bool,
+}
+
+impl App for ActivityTracker {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.handle_keys(ctx);
+        self.persist_zoom(ctx);
+        self.tick_stopwatch(ctx);
+        // Closing the window mid-session shrinks it instead of ending the task
+        if ctx.input(|i| i.viewport().close_requested()) && self.recorder.is_active() && !self.compact {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.set_compact(ctx, true);
+        }
+        if self.compact {
+            if self.recorder.is_active() {
+                self.compact_window(ctx);
+                return;
+            }
+            self.set_compact(ctx, false);
+        }
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(tr!("app-title"));
+            
+            // Display macOS permission message if needed
+            if !self.permission_checked && self.is_macos {
+                self.permission_checked = true;
+                // Request initial mouse position to trigger permission dialog on macOS
+                let device_state = DeviceState::new();
+                let _ = device_state.get_mouse();
+                
+                self.recorder.set_status(tr!("macos-permission-note"));
+            }
+            
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("task-name"));
+                let field = ui.text_edit_singleline(&mut self.task_name).labelled_by(label.id);
+                if std::mem::take(&mut self.focus_task) {
+                    field.request_focus();
+                }
+                if field.has_focus() && !self.recorder.is_active() {
+                    self.suggest_open = true;
+                }
+                self.submit_on_enter(ui, &field);
+                egui::ComboBox::from_id_salt("recent_tasks")
+                    .selected_text(tr!("recent"))
+                    .width(70.0)
+                    .show_ui(ui, |ui| {
+                        for recent in self.recent_tasks.clone() {
+                            if ui.selectable_label(false, &recent.task).clicked() {
+                                self.pick_recent(&recent);
+                            }
+                        }
+                    });
+            });
+            if self.suggest_open && !self.task_name.trim().is_empty() {
+                let suggestions: Vec<RecentTask> = history::matching(&self.recent_tasks, &self.task_name)
+                    .into_iter()
+                    .take(MAX_SUGGESTIONS)
+                    .cloned()
+                    .collect();
+                for recent in suggestions {
+                    let row = ui.selectable_label(false, format!("↳ {}", recent.task));
+                    if speak_as(row, &tr!("suggestion", task = recent.task)).clicked() {
+                        self.pick_recent(&recent);
+                    }
+                }
+            }
+            if !self.task_name.is_empty() {
+                match storage::validate_task_name(&self.task_name) {
+                    Err(e) => {
+                        announce(&ui.colored_label(ui.visuals().error_fg_color, e));
+                    }
+                    Ok(()) if storage::sanitize(&self.task_name) != self.task_name.replace(' ', "_") => {
+                        ui.weak(tr!("saved-as", name = storage::sanitize(&self.task_name)));
+                    }
+                    Ok(()) => {}
+                }
+            }
+            
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("project"));
+                let field = ui.text_edit_singleline(&mut self.project).labelled_by(label.id);
+                self.submit_on_enter(ui, &field);
+            });
+            
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("estimate"));
+                let field = ui.add(egui::TextEdit::singleline(&mut self.estimate).hint_text(tr!("estimate-hint")))
+                    .labelled_by(label.id);
+                self.submit_on_enter(ui, &field);
+            });
+            
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("notes"));
+                let field = ui.text_edit_singleline(&mut self.notes).labelled_by(label.id);
+                self.submit_on_enter(ui, &field);
+            });
+            
+            ui.add_space(10.0);
+            
+            ui.horizontal(|ui| {
+                if !self.recorder.is_active() {
+                    if ui.button(tr!("create-task")).clicked() {
+                        self.start_task();
+                    }
+                    for name in self.presets.clone() {
+                        let button = ui.button(format!("▶ {}", name)).on_hover_text(tr!("start-preset-hint"));
+                        if speak_as(button, &tr!("start-preset", name = name)).clicked() {
+                            self.start_preset(&name);
+                        }
+                    }
+                } else {
+                    if let Phase::Countdown(_) = self.recorder.phase() {
+                        if ui.button(tr!("cancel")).on_hover_text("Esc").clicked() {
+                            self.cancel_countdown();
+                        }
+                    } else if ui.button(tr!("end-task")).on_hover_text("Enter").clicked() {
+                        self.stop_task();
+                    }
+                    if ui.button(tr!("compact")).on_hover_text(tr!("compact-hint")).clicked() {
+                        self.set_compact(ctx, true);
+                    }
+                }
+            });
+            ui.weak(tr!("keyboard-help"));
+            
+            ui.add_space(20.0);
+            
+            // Show timer countdown if recording but timer not complete
+            match self.recorder.phase() {
+                Phase::Countdown(remaining) => {
+                    self.timer_complete = false;
+                    self.recorder.set_status(tr!("countdown", seconds = remaining));
+                    // Request repaint to update timer countdown
+                    ctx.request_repaint();
+                }
+                Phase::Recording if !self.timer_complete => {
+                    self.timer_complete = true;
+                    self.recorder.set_status(tr!("recording"));
+                }
+                Phase::Paused(reason) => {
+                    // Show "in progress" again once capture resumes
+                    self.timer_complete = false;
+                    self.recorder.set_status(tr!("paused", reason = pause_reason(reason)));
+                    // Capture resumes on its own; poll so the status follows
+                    ctx.request_repaint_after(Duration::from_millis(500));
+                }
+                _ => {}
+            }
+            
+            // Countdown, start and stop all surface here, so read it out as it changes
+            announce(&ui.label(self.recorder.status()));
+            if let Some(suggestion) = self.recorder.suggested_task() {
+                self.task_suggestion(ui, &suggestion);
+            }
+            
+            match self.recorder.save_error() {
+                Some(error) if error.needs_folder => self.choose_folder_dialog(ctx),
+                Some(error) => self.save_error_banner(ui, &error),
+                None => {}
+            }
+            
+            // Stopwatch, and estimate vs elapsed, while the task runs
+            if matches!(self.recorder.phase(), Phase::Recording | Phase::Paused(_)) {
+                ui.add_space(10.0);
+                ui.heading(cli::format_duration(self.recorder.elapsed().as_secs()));
+                self.sample_counter(ui);
+                self.sparkline(ui);
+                let size = self.recorder.estimated_size();
+                let elapsed = self.recorder.elapsed().as_secs_f64();
+                if elapsed >= 1.0 {
+                    let per_hour = (size as f64 / elapsed * 3600.0) as u64;
+                    ui.label(tr!("file-size", size = cli::format_size(size), per_hour = cli::format_size(per_hour)));
+                    if let Some(estimate) = self.recorder.meta().estimate_secs.filter(|&e| e as f64 > elapsed) {
+                        let projected = (size as f64 / elapsed * estimate as f64) as u64;
+                        ui.weak(tr!("file-size-at-estimate", size = cli::format_size(projected)));
+                    }
+                }
+                if let Some(estimate) = self.recorder.meta().estimate_secs {
+                    let elapsed = self.recorder.elapsed().as_secs();
+                    ui.label(tr!(
+                        "elapsed",
+                        elapsed = cli::format_duration(elapsed),
+                        estimate = cli::format_duration(estimate),
+                    ));
+                    if estimate > 0 {
+                        ui.add(egui::ProgressBar::new(elapsed as f32 / estimate as f32));
+                    }
+                }
+            }
+            
+            if self.recorder.phase() == Phase::Idle {
+                if let Some(path) = self.recorder.last_saved() {
+                    self.export_section(ui, &path);
+                }
+            }
+            
+            // Show macOS specific note if needed
+            if self.is_macos {
+                ui.add_space(10.0);
+                ui.label(tr!("macos-inputs-note"));
+            }
+            
+            if let Some(link) = &self.dashboard_link {
+                ui.add_space(10.0);
+                egui::CollapsingHeader::new(tr!("phone-view")).show(ui, |ui| {
+                    if link.local_only {
+                        ui.colored_label(ui.visuals().warn_fg_color, tr!("dashboard-local-only"));
+                    } else {
+                        ui.label(tr!("phone-view-hint"));
+                    }
+                    qr_code(ui, &link.qr);
+                    ui.add(egui::Label::new(&link.url).selectable(true));
+                });
+            }
+            
+            ui.add_space(10.0);
+            egui::CollapsingHeader::new(tr!("diagnostics")).show(ui, |ui| {
+                let diagnostics = self.recorder.diagnostics();
+                let millis = |d: Duration| format!("{:.1} ms", d.as_secs_f64() * 1000.0);
+                egui::Grid::new("diagnostics").num_columns(2).show(ui, |ui| {
+                    ui.label(tr!("backend"));
+                    ui.label(&diagnostics.backend);
+                    ui.end_row();
+                    ui.label(tr!("last-sample"));
+                    ui.label(diagnostics.last_sample_age.map_or(tr!("never"), |age| {
+                        tr!("seconds-ago", seconds = format!("{:.1}", age.as_secs_f64()))
+                    }));
+                    ui.end_row();
+                    ui.label(tr!("sample-rate"));
+                    ui.label(match diagnostics.rate {
+                        Some(rate) => tr!("rate-of-target", rate = format!("{:.1}", rate), target = diagnostics.target_rate.round()),
+                        None => tr!("rate-of-target", rate = "-", target = diagnostics.target_rate.round()),
+                    });
+                    ui.end_row();
+                    ui.label(tr!("samples"));
+                    ui.label(diagnostics.samples.to_string());
+                    ui.end_row();
+                    ui.label(tr!("dropped"));
+                    ui.label(diagnostics.dropped.to_string());
+                    ui.end_row();
+                    ui.label(tr!("tick-work"));
+                    ui.label(millis(diagnostics.tick_work));
+                    ui.end_row();
+                    ui.label(tr!("write-latency"));
+                    ui.label(diagnostics.write_latency.map_or("-".to_string(), millis));
+                    ui.end_row();
+                });
+                ctx.request_repaint_after(Duration::from_millis(500));
+            });
+            ui.horizontal(|ui| {
+                if ui.button(tr!("view-log")).clicked() {
+                    if let Err(e) = logging::open() {
+                        self.recorder.set_status(e);
+                    }
+                }
+                ui.separator();
+                ui.label(tr!("text-size", percent = (ctx.zoom_factor() * 100.0).round()));
+                if speak_as(ui.button("−").on_hover_text("Ctrl −"), &tr!("zoom-out")).clicked() {
+                    egui::gui_zoom::zoom_out(ctx);
+                }
+                if speak_as(ui.button("+").on_hover_text("Ctrl +"), &tr!("zoom-in")).clicked() {
+                    egui::gui_zoom::zoom_in(ctx);
+                }
+            });
+        });
+    }
+    
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // Window closed (or the session is ending) while a task was running
+        shutdown::finalize(&self.recorder);
+    }
+}
+
+impl ActivityTracker {
+    /// Records captured so far and how fast they are coming in, flagging a
+    /// capture that has stopped delivering samples.
+    fn sample_counter(&mut self, ui: &mut egui::Ui) {
+        let samples = self.recorder.sample_count();
+        let now = Instant::now();
+        match self.rate_probe {
+            Some((since, count)) if now.duration_since(since) >= Duration::from_secs(1) => {
+                self.sample_rate = Some(samples.saturating_sub(count) as f64 / now.duration_since(since).as_secs_f64());
+                self.rate_probe = Some((now, samples));
+            }
+            // A new session started since the last probe
+            Some((_, count)) if samples < count => self.rate_probe = Some((now, samples)),
+            Some(_) => {}
+            None => self.rate_probe = Some((now, samples)),
+        }
+        let rate = self.sample_rate.map_or("-".to_string(), |rate| format!("{:.1}", rate));
+        ui.label(tr!("sample-count", samples = samples, rate = rate));
+        let stalled = self.recorder.diagnostics().last_sample_age.is_none_or(|age| age > STALL_AFTER);
+        if self.recorder.phase() == Phase::Recording && stalled && self.recorder.elapsed() > STALL_AFTER {
+            announce(&ui.colored_label(ui.visuals().warn_fg_color, tr!("capture-stalled")));
+        }
+    }
+    
+    /// Share of samples with input over the last few minutes, as a small
+    /// line chart that fills in from the right.
+    fn sparkline(&self, ui: &mut egui::Ui) {
+        let activity = self.recorder.recent_activity(SPARKLINE_WINDOW, SPARKLINE_BUCKET);
+        // Samples a bucket holds at the session's own rate
+        let full = (SPARKLINE_BUCKET.as_secs_f64() / self.recorder.meta().sample_interval().as_secs_f64()) as f32;
+        let size = egui::vec2(ui.available_width().min(300.0), 32.0);
+        let (rect, response) = ui.allocate_exact_size(size, egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let step = rect.width() / activity.len().saturating_sub(1).max(1) as f32;
+        let points: Vec<egui::Pos2> = activity
+            .iter()
+            .enumerate()
+            .map(|(i, &count)| {
+                let level = (count as f32 / full).min(1.0);
+                egui::pos2(rect.left() + i as f32 * step, rect.bottom() - 2.0 - level * (rect.height() - 4.0))
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, ui.visuals().selection.bg_fill)));
+        speak_as(response.on_hover_text(tr!("sparkline")), &tr!("sparkline"));
+    }
+    
+    /// Puts the elapsed time in the window title and repaints as the
+    /// displayed second changes, rather than every frame.
+    fn tick_stopwatch(&mut self, ctx: &egui::Context) {
+        let title = match self.recorder.phase() {
+            Phase::Recording | Phase::Paused(_) => {
+                let elapsed = self.recorder.elapsed();
+                ctx.request_repaint_after(Duration::from_secs(1) - Duration::from_nanos(elapsed.subsec_nanos().into()));
+                format!("{} – {}", cli::format_duration(elapsed.as_secs()), tr!("app-title"))
+            }
+            _ => tr!("app-title"),
+        };
+        if title != self.title {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title.clone()));
+            self.title = title;
+        }
+    }
+    
+    /// Remembers zoom changes from the buttons or Ctrl +/- for next time.
+    fn persist_zoom(&mut self, ctx: &egui::Context) {
+        let scale = ctx.zoom_factor();
+        if scale == self.ui_scale {
+            return;
+        }
+        self.ui_scale = scale;
+        if let Err(e) = Config::save_ui_scale(scale) {
+            tracing::warn!("could not save text size: {}", e);
+        }
+    }
+    
+    /// Keyboard shortcuts that work while no control has focus; Enter in a
+    /// text field is handled by `submit_on_enter`.
+    fn handle_keys(&mut self, ctx: &egui::Context) {
+        let (enter, escape) = ctx.input(|i| (i.key_pressed(egui::Key::Enter), i.key_pressed(egui::Key::Escape)));
+        if escape && matches!(self.recorder.phase(), Phase::Countdown(_)) {
+            self.cancel_countdown();
+        }
+        // An unsaved session must not be dropped by a stray Enter
+        if !enter || ctx.memory(|m| m.focused().is_some()) || self.recorder.save_error().is_some() {
+            return;
+        }
+        match self.recorder.phase() {
+            Phase::Idle => self.start_task(),
+            Phase::Recording | Phase::Paused(_) => self.stop_task(),
+            Phase::Countdown(_) => {}
+        }
+    }
+    
+    /// Starts the task when Enter is pressed in one of the task fields.
+    fn submit_on_enter(&mut self, ui: &egui::Ui, field: &egui::Response) {
+        if field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) && !self.recorder.is_active() {
+            self.start_task();
+        }
+    }
+    
+    fn start_task(&mut self) {
+        self.suggest_open = false;
+        if let Err(e) = self.session_meta().and_then(|meta| self.recorder.start(meta)) {
+            self.recorder.set_status(e);
+        }
+        self.recent_tasks = history::load();
+    }
+    
+    /// Swaps between the full window and a small frameless one that stays
+    /// on top of other windows.
+    fn set_compact(&mut self, ctx: &egui::Context, compact: bool) {
+        let size = if compact {
+            self.full_size = ctx.input(|i| i.viewport().inner_rect).map(|rect| rect.size());
+            egui::vec2(220.0, 44.0)
+        } else {
+            self.full_size.take().unwrap_or(WINDOW_SIZE)
+        };
+        let level = if compact { egui::WindowLevel::AlwaysOnTop } else { egui::WindowLevel::Normal };
+        self.compact = compact;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Decorations(!compact));
+        ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+    }
+    
+    /// Recording indicator, elapsed time and stop button; drag anywhere else
+    /// to move the window.
+    fn compact_window(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let background = ui.interact(ui.max_rect(), ui.id().with("drag"), egui::Sense::drag());
+            if background.drag_started() {
+                ctx.send_viewport_cmd(egui::ViewportCommand::StartDrag);
+            }
+            ui.horizontal_centered(|ui| {
+                let phase = self.recorder.phase();
+                match phase {
+                    Phase::Countdown(remaining) => {
+                        ui.label(format!("⏱ {}", remaining));
+                    }
+                    Phase::Paused(_) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, "⏸");
+                        ui.label(cli::format_duration(self.recorder.elapsed().as_secs()));
+                    }
+                    _ => {
+                        ui.colored_label(egui::Color32::RED, "⏺").on_hover_text(tr!("recording"));
+                        ui.label(cli::format_duration(self.recorder.elapsed().as_secs()));
+                    }
+                }
+                let stop = ui.button("⏹").on_hover_text(tr!("end-task"));
+                if speak_as(stop, &tr!("end-task")).clicked() {
+                    match phase {
+                        Phase::Countdown(_) => self.cancel_countdown(),
+                        _ => self.stop_task(),
+                    }
+                }
+                let expand = ui.button("⤢").on_hover_text(tr!("expand"));
+                if speak_as(expand, &tr!("expand")).clicked() {
+                    self.set_compact(ctx, false);
+                }
+            });
+        });
+        ctx.request_repaint_after(Duration::from_secs(1));
+    }
+    
+    fn stop_task(&mut self) {
+        if let Err(e) = self.recorder.stop() {
+            self.recorder.set_status(e);
+        }
+        self.focus_task = true;
+    }
+    
+    fn cancel_countdown(&mut self) {
+        if let Err(e) = self.recorder.cancel() {
+            self.recorder.set_status(e);
+        }
+        self.focus_task = true;
+    }
+    
+    fn start_preset(&mut self, name: &str) {
+        self.suggest_open = false;
+        let result = Config::load().and_then(|config| {
+            let meta = config.preset(name)?.meta();
+            self.task_name = meta.task.clone();
+            self.project = meta.project.clone().unwrap_or_default();
+            self.recorder.start(meta)
+        });
+        match result {
+            Ok(()) => self.recent_tasks = history::load(),
+            Err(e) => self.recorder.set_status(e),
+        }
+    }
+    
+    /// Fills in a recent task and the project it was last recorded under.
+    fn pick_recent(&mut self, recent: &RecentTask) {
+        self.task_name = recent.task.clone();
+        self.project = recent.project.clone().unwrap_or_default();
+        self.suggest_open = false;
+    }
+    
+    fn session_meta(&self) -> Result<SessionMeta, String> {
+        let estimate = self.estimate.trim();
+        let project = self.project.trim();
+        let notes = self.notes.trim();
+        // A name is suggested for untitled sessions once they have run a while
+        let task = if self.task_name.trim().is_empty() { recorder::UNTITLED_TASK } else { &self.task_name };
+        Ok(SessionMeta {
+            task: task.to_string(),
+            project: (!project.is_empty()).then(|| project.to_string()),
+            estimate_secs: if estimate.is_empty() { None } else { Some(cli::parse_duration(estimate)?) },
+            notes: (!notes.is_empty()).then(|| notes.to_string()),
+            ..Default::default()
+        })
+    }
+    
+    /// Tools for the last saved session: copies resampled for sharing or
+    /// plotting or trimmed to leave out setup and teardown, and redaction of
+    /// something sensitive.
+    fn export_section(&mut self, ui: &mut egui::Ui, path: &Path) {
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new(tr!("export")).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("resample-every"));
+                egui::ComboBox::from_id_salt("resample-every")
+                    .selected_text(tr!("seconds", seconds = self.resample_secs))
+                    .show_ui(ui, |ui| {
+                        for secs in RESAMPLE_INTERVALS {
+                            ui.selectable_value(&mut self.resample_secs, secs, tr!("seconds", seconds = secs));
+                        }
+                    })
+                    .response
+                    .labelled_by(label.id);
+                if ui.button(tr!("export-resampled")).clicked() {
+                    match resample::write_beside(path, self.resample_secs) {
+                        Ok(output) => self.recorder.set_status(tr!("exported-to", path = output.display().to_string())),
+                        Err(e) => self.recorder.set_status(e),
+                    }
+                }
+            });
+            
+            // The session's length is read once per saved session
+            if self.range.as_ref().is_none_or(|range| range.path != path) {
+                let duration = storage::load_csv(path)
+                    .map(|records| analysis::summarize(&records).duration_secs())
+                    .unwrap_or(0);
+                self.range = Some(SessionRange {
+                    path: path.to_path_buf(),
+                    duration,
+                    from: 0,
+                    to: duration,
+                    confirm_redact: false,
+                });
+            }
+            let Some(range) = self.range.as_mut().filter(|range| range.duration > 0) else {
+                return;
+            };
+            let format = |secs: f64, _: std::ops::RangeInclusive<usize>| cli::format_duration(secs as u64);
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("range-from"));
+                ui.add(egui::Slider::new(&mut range.from, 0..=range.duration).custom_formatter(format))
+                    .labelled_by(label.id);
+            });
+            ui.horizontal(|ui| {
+                let label = ui.label(tr!("range-to"));
+                ui.add(egui::Slider::new(&mut range.to, 0..=range.duration).custom_formatter(format))
+                    .labelled_by(label.id);
+            });
+            range.to = range.to.max(range.from);
+            ui.horizontal(|ui| {
+                if ui.button(tr!("export-trimmed")).clicked() {
+                    match edit::write_trimmed(path, range.from, range.to) {
+                        Ok(output) => self.recorder.set_status(tr!("exported-to", path = output.display().to_string())),
+                        Err(e) => self.recorder.set_status(e),
+                    }
+                }
+                if ui.button(tr!("redact-range")).on_hover_text(tr!("redact-range-hint")).clicked() {
+                    range.confirm_redact = true;
+                }
+            });
+            // Redaction overwrites the saved file, so it is asked for twice
+            if range.confirm_redact {
+                announce(&ui.label(tr!("redact-confirm", path = path.display().to_string())));
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("redact-range")).clicked() {
+                        range.confirm_redact = false;
+                        match edit::redact_file(path, range.from, range.to) {
+                            Ok(backup) => self.recorder.set_status(tr!(
+                                "redacted",
+                                path = path.display().to_string(),
+                                backup = backup.display().to_string()
+                            )),
+                            Err(e) => self.recorder.set_status(e),
+                        }
+                    }
+                    if ui.button(tr!("cancel")).clicked() {
+                        range.confirm_redact = false;
+                    }
+                });
+            }
+        });
+    }
+    
+    /// Offers a name for a session started without one.
+    fn task_suggestion(&mut self, ui: &mut egui::Ui, suggestion: &str) {
+        ui.add_space(10.0);
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            announce(&ui.label(tr!("task-suggestion", task = suggestion)));
+            ui.horizontal(|ui| {
+                if ui.button(tr!("accept")).clicked() {
+                    match self.recorder.rename(suggestion) {
+                        Ok(()) => self.task_name = suggestion.to_string(),
+                        Err(e) => self.recorder.set_status(e),
+                    }
+                }
+                if ui.button(tr!("dismiss")).clicked() {
+                    self.recorder.dismiss_suggestion();
+                }
+            });
+        });
+    }
+    
+    /// Offers another attempt at saving a session that failed to save,
+    /// optionally in a different folder, e.g. when the disk is full.
+    fn save_error_banner(&mut self, ui: &mut egui::Ui, error: &SaveError) {
+        ui.add_space(10.0);
+        egui::Frame::group(ui.style())
+            .fill(ui.visuals().extreme_bg_color)
+            .show(ui, |ui| {
+                announce(&ui.colored_label(ui.visuals().error_fg_color, tr!("session-not-saved", error = error.message)));
+                ui.label(tr!("unsaved-kept"));
+                self.retry_controls(ui, &tr!("retry"));
+            });
+    }
+    
+    /// Asks where to save when no output folder could be found at all.
+    fn choose_folder_dialog(&mut self, ctx: &egui::Context) {
+        egui::Window::new(tr!("choose-folder-title"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!("no-output-folder"));
+                ui.label(tr!("enter-folder"));
+                ui.checkbox(&mut self.remember_dir, tr!("always-save-here"));
+                self.retry_controls(ui, &tr!("save"));
+            });
+    }
+    
+    fn retry_controls(&mut self, ui: &mut egui::Ui, retry_label: &str) {
+        ui.horizontal(|ui| {
+            let label = ui.label(tr!("folder"));
+            ui.add(egui::TextEdit::singleline(&mut self.retry_dir).hint_text(tr!("folder-hint")))
+                .labelled_by(label.id);
+        });
+        ui.horizontal(|ui| {
+            if ui.button(retry_label).clicked() {
+                if let Err(e) = self.retry_in_chosen_dir() {
+                    self.recorder.set_status(e);
+                }
+            }
+            if ui.button(tr!("discard")).clicked() {
+                self.recorder.discard_unsaved();
+            }
+        });
+    }
+    
+    fn retry_in_chosen_dir(&self) -> Result<(), String> {
+        let dir = self.retry_dir.trim();
+        let dir = (!dir.is_empty()).then(|| PathBuf::from(dir));
+        if let Some(dir) = &dir {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+            if self.remember_dir {
+                Config::save_output_dir(dir)?;
+            }
+        }
+        // The outcome itself is reported through the status
+        let _ = self.recorder.retry_save(dir.as_deref());
+        Ok(())
+    }
+    
+    pub fn new(cc: &CreationContext<'_>, config: &Config) -> Self {
+        // Detect OS at runtime
+        #[allow(unused_mut)]
+        let mut is_macos = false;
+        
+        #[cfg(target_os = "macos")]
+        {
+            is_macos = true;
+        }
+        
+        let ui_scale = config.ui_scale.clamp(MIN_SCALE, MAX_SCALE);
+        cc.egui_ctx.set_zoom_factor(ui_scale);
+        
+        // Make the keyboard focus easy to follow when tabbing through controls
+        cc.egui_ctx.all_styles_mut(|style| {
+            let accent = style.visuals.selection.bg_fill;
+            style.visuals.selection.stroke.width = 2.0;
+            style.visuals.widgets.active.bg_stroke = egui::Stroke::new(2.0, accent);
+        });
+        
+        let recorder = Recorder::default();
+        
+        if let Err(e) = shutdown::install_signal_handler(recorder.clone()) {
+            tracing::warn!("could not install termination handler: {}", e);
+            eprintln!("Could not install termination handler: {}", e);
+        }
+        
+        // Let scripts drive the running instance; the UI keeps working without it
+        let egui_ctx = cc.egui_ctx.clone();
+        if let Err(e) = ipc::spawn_server(recorder.clone(), move || egui_ctx.request_repaint()) {
+            tracing::warn!("control socket unavailable ({}): {}", ipc::endpoint_display(), e);
+            recorder.set_status(format!("Control socket unavailable ({}): {}", ipc::endpoint_display(), e));
+        }
+        // Integration requests that failed while offline are sent once the network is back
+        outbox::spawn_worker();
+        
+        let mut dashboard_link = None;
+        if let Some(dashboard) = &config.dashboard {
+            match dashboard.spawn(recorder.clone()) {
+                Ok(()) => {
+                    let url = dashboard.url();
+                    match QrCode::encode(&url) {
+                        Ok(qr) => dashboard_link = Some(DashboardLink { url, qr, local_only: dashboard.local_only() }),
+                        Err(e) => tracing::warn!("no QR code for the dashboard: {}", e),
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("dashboard unavailable ({}): {}", dashboard.bind, e);
+                    recorder.set_status(format!("Dashboard unavailable ({}): {}", dashboard.bind, e));
+                }
+            }
+        }
+        if let Some(editor) = &config.editor {
+            if let Err(e) = editor.spawn(recorder.clone()) {
+                tracing::warn!("editor endpoint unavailable ({}): {}", editor.bind, e);
+                recorder.set_status(format!("Editor endpoint unavailable ({}): {}", editor.bind, e));
+            }
+        }
+        
+        Self {
+            recorder,
+            recent_tasks: history::load(),
+            presets: config.presets.iter().map(|p| p.name.clone()).collect(),
+            is_macos,
+            permission_checked: false,
+            focus_task: true,
+            ui_scale,
+            resample_secs: RESAMPLE_INTERVALS[0],
+            dashboard_link,
+            ..Default::default()
+        }
+    }
+}
+
+fn pause_reason(reason: PauseReason) -> String {
+    match reason {
+        PauseReason::Sleep => tr!("pause-sleep"),
+        PauseReason::ScreenLock => tr!("pause-screen-lock"),
+        PauseReason::PrivateWindow => tr!("pause-private-window"),
+        PauseReason::ExcludedApp => tr!("pause-excluded-app"),
+    }
+}
+
+/// Draws a QR code dark on white, with the quiet zone scanners need.
+fn qr_code(ui: &mut egui::Ui, qr: &QrCode) {
+    const MODULE: f32 = 4.0;
+    const QUIET: usize = 4;
+    let side = (qr.size() + 2 * QUIET) as f32 * MODULE;
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(side, side), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, egui::Color32::WHITE);
+    for y in 0..qr.size() {
+        for x in 0..qr.size() {
+            if qr.dark(x, y) {
+                let min = rect.min + egui::vec2((x + QUIET) as f32, (y + QUIET) as f32) * MODULE;
+                painter.rect_filled(egui::Rect::from_min_size(min, egui::Vec2::splat(MODULE)), 0.0, egui::Color32::BLACK);
+            }
+        }
+    }
+    speak_as(response, &tr!("phone-view-qr"));
+}
+
+/// Has screen readers read out changes to a widget's text, e.g. the status line.
+fn announce(response: &egui::Response) {
+    response.ctx.accesskit_node_builder(response.id, |node| {
+        node.set_live(egui::accesskit::Live::Polite);
+    });
+}
+
+/// Gives a widget a spoken name in place of its visible text, for labels
+/// that lean on symbols.
+fn speak_as(response: egui::Response, name: &str) -> egui::Response {
+    response.ctx.accesskit_node_builder(response.id, |node| node.set_label(name));
+    response
+}
+
+pub fn run() -> Result<(), String> {
+    let config = Config::load().unwrap_or_default();
+    i18n::init(config.language.as_deref());
+    let scale = config.ui_scale.clamp(MIN_SCALE, MAX_SCALE);
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size(WINDOW_SIZE * scale),
+        ..Default::default()
+    };
+    
+    eframe::run_native(
+        &tr!("app-title"),
+        options,
+        Box::new(|cc| Ok(Box::new(ActivityTracker::new(cc, &config)))),
+    )
+    .map_err(|e| format!("Failed to start the window: {}", e))
+}
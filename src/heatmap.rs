@@ -0,0 +1,147 @@
+//! Mouse heatmaps rendered to image files.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::recorder::ActivityRecord;
+
+/// Radius in output pixels over which each sample's weight is spread.
+const BLUR_RADIUS: usize = 6;
+/// Side of the squares an SVG heatmap is built from, in output pixels.
+const SVG_CELL: usize = 8;
+
+/// Time the pointer spent over each pixel of a `width` x `height` image
+/// covering the recorded screen area.
+pub struct Heatmap {
+    width: usize,
+    height: usize,
+    weights: Vec<f64>,
+}
+
+impl Heatmap {
+    /// Scales the session's pointer positions into the image. Without a
+    /// size the image covers the recorded area at one pixel per pixel.
+    pub fn new(records: &[ActivityRecord], size: Option<(u32, u32)>) -> Result<Self, String> {
+        if records.is_empty() {
+            return Err("Session has no samples".to_string());
+        }
+        let min_x = records.iter().map(|r| r.mouse_x).min().unwrap_or(0).min(0);
+        let min_y = records.iter().map(|r| r.mouse_y).min().unwrap_or(0).min(0);
+        let span_x = (records.iter().map(|r| r.mouse_x).max().unwrap_or(0) - min_x + 1) as f64;
+        let span_y = (records.iter().map(|r| r.mouse_y).max().unwrap_or(0) - min_y + 1) as f64;
+        let (width, height) = match size {
+            Some((w, h)) => (w as usize, h as usize),
+            None => (span_x as usize, span_y as usize),
+        };
+        if width == 0 || height == 0 {
+            return Err("Heatmap size must not be zero".to_string());
+        }
+
+        let mut counts = vec![0.0; width * height];
+        for record in records {
+            let x = ((record.mouse_x - min_x) as f64 / span_x * width as f64) as usize;
+            let y = ((record.mouse_y - min_y) as f64 / span_y * height as f64) as usize;
+            counts[y.min(height - 1) * width + x.min(width - 1)] += 1.0;
+        }
+
+        let weights = box_blur(&box_blur(&counts, width, height, true), width, height, false);
+        Ok(Heatmap { width, height, weights })
+    }
+
+    /// Each pixel's weight on a log scale from 0 to 1, so brief visits stay
+    /// visible next to where the pointer rested for hours.
+    fn intensity(&self) -> Vec<f64> {
+        let max = self.weights.iter().copied().fold(0.0, f64::max);
+        let scale = (1.0 + max).ln().max(f64::EPSILON);
+        self.weights.iter().map(|w| (1.0 + w).ln() / scale).collect()
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let pixels: Vec<u8> = self.intensity().into_iter().flat_map(color).collect();
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    pub fn write_svg(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        self.svg(&mut BufWriter::new(file))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn svg(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let intensity = self.intensity();
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+            self.width, self.height
+        )?;
+        for cell_y in (0..self.height).step_by(SVG_CELL) {
+            for cell_x in (0..self.width).step_by(SVG_CELL) {
+                let mut peak: f64 = 0.0;
+                for y in cell_y..(cell_y + SVG_CELL).min(self.height) {
+                    for x in cell_x..(cell_x + SVG_CELL).min(self.width) {
+                        peak = peak.max(intensity[y * self.width + x]);
+                    }
+                }
+                let [r, g, b, a] = color(peak);
+                if a == 0 {
+                    continue;
+                }
+                writeln!(
+                    out,
+                    r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#{:02x}{:02x}{:02x}" fill-opacity="{:.3}"/>"##,
+                    cell_x,
+                    cell_y,
+                    SVG_CELL,
+                    SVG_CELL,
+                    r,
+                    g,
+                    b,
+                    a as f64 / 255.0
+                )?;
+            }
+        }
+        writeln!(out, "</svg>")
+    }
+}
+
+/// One pass of a box blur along rows or columns.
+fn box_blur(values: &[f64], width: usize, height: usize, horizontal: bool) -> Vec<f64> {
+    let (lines, len) = if horizontal { (height, width) } else { (width, height) };
+    let index = |line: usize, pos: usize| if horizontal { line * width + pos } else { pos * width + line };
+    let mut out = vec![0.0; values.len()];
+
+    for line in 0..lines {
+        for pos in 0..len {
+            let from = pos.saturating_sub(BLUR_RADIUS);
+            let to = (pos + BLUR_RADIUS).min(len - 1);
+            let sum: f64 = (from..=to).map(|p| values[index(line, p)]).sum();
+            out[index(line, pos)] = sum / (2 * BLUR_RADIUS + 1) as f64;
+        }
+    }
+    out
+}
+
+/// Transparent through blue and red to yellow.
+fn color(intensity: f64) -> [u8; 4] {
+    let t = intensity.clamp(0.0, 1.0);
+    if t == 0.0 {
+        return [0, 0, 0, 0];
+    }
+    let (r, g, b) = if t < 0.5 {
+        let s = t * 2.0;
+        (s, 0.0, 1.0 - s)
+    } else {
+        (1.0, (t - 0.5) * 2.0, 0.0)
+    };
+    let alpha = (0.25 + 0.75 * t).min(1.0);
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8, (alpha * 255.0) as u8]
+}
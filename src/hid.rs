@@ -0,0 +1,138 @@
+//! Raw HID capture, for devices that don't show up as a mouse or keyboard,
+//! e.g. foot pedals, 3D mice or button boxes. Configured under
+//! `hid_devices` in the settings file:
+//!
+//! ```json
+//! "hid_devices": [{ "name": "pedal", "vendor_id": 1452, "product_id": 591 }]
+//! ```
+//!
+//! Every input report the devices send is stored, as hex, with the next
+//! sample under the device's `name`. Devices that aren't plugged in, or are
+//! unplugged during a session, are looked for again every few seconds, and
+//! their coming and going is recorded under `connected` and `disconnected`.
+//! Needs a build with the `hid` feature; on Linux the user also needs read
+//! access to the device's `/dev/hidraw*`.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+#[cfg_attr(not(feature = "hid"), allow(dead_code))]
+pub struct HidDeviceConfig {
+    /// Label for the device's reports.
+    pub name: String,
+    pub vendor_id: u16,
+    pub product_id: u16,
+    /// Picks one interface of a device that has several.
+    #[serde(default)]
+    pub usage_page: Option<u16>,
+}
+
+/// One input report.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HidReport {
+    pub device: String,
+    /// The report's bytes in hex, starting with its report ID if the
+    /// device uses them.
+    pub data: String,
+}
+
+/// What a device did.
+#[cfg_attr(not(feature = "hid"), allow(dead_code))]
+pub enum HidEvent {
+    Report(HidReport),
+    /// A device that was missing was plugged in.
+    Connected(String),
+    Disconnected(String),
+}
+
+/// Reads the devices on a thread until `stop` is set, handing everything
+/// that happens to `on_event`. Devices that can't be opened at first are
+/// warned about.
+#[cfg(feature = "hid")]
+pub fn spawn(devices: &[HidDeviceConfig], stop: Arc<AtomicBool>, mut on_event: impl FnMut(HidEvent) + Send + 'static) {
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    /// Largest report read.
+    const REPORT_SIZE: usize = 256;
+    /// How long each device is waited on before moving to the next.
+    const READ_TIMEOUT_MS: i32 = 5;
+    /// Time between two looks for missing devices.
+    const RESCAN: Duration = Duration::from_secs(2);
+
+    let wanted: Vec<(String, u16, u16, Option<u16>)> = devices
+        .iter()
+        .map(|d| (d.name.clone(), d.vendor_id, d.product_id, d.usage_page))
+        .collect();
+    thread::spawn(move || {
+        let mut api = match hidapi::HidApi::new() {
+            Ok(api) => api,
+            Err(e) => {
+                tracing::warn!("HID: {}", e);
+                return;
+            }
+        };
+        let mut open: Vec<(String, hidapi::HidDevice)> = Vec::new();
+        let mut buf = [0u8; REPORT_SIZE];
+        let mut scanned: Option<Instant> = None;
+        while !stop.load(Ordering::SeqCst) {
+            if scanned.is_none_or(|scanned| scanned.elapsed() >= RESCAN) {
+                let first = scanned.is_none();
+                if !first && open.len() < wanted.len() {
+                    if let Err(e) = api.refresh_devices() {
+                        tracing::warn!("HID: {}", e);
+                    }
+                }
+                scanned = Some(Instant::now());
+                for (name, vendor_id, product_id, usage_page) in &wanted {
+                    if open.iter().any(|(open, _)| open == name) {
+                        continue;
+                    }
+                    let info = api.device_list().find(|info| {
+                        info.vendor_id() == *vendor_id
+                            && info.product_id() == *product_id
+                            && usage_page.is_none_or(|page| info.usage_page() == page)
+                    });
+                    match info.map(|info| info.open_device(&api)) {
+                        Some(Ok(device)) => {
+                            if !first {
+                                tracing::info!("HID: {} connected", name);
+                                on_event(HidEvent::Connected(name.clone()));
+                            }
+                            open.push((name.clone(), device));
+                        }
+                        Some(Err(e)) if first => tracing::warn!("HID: could not open {}: {}", name, e),
+                        None if first => tracing::warn!("HID: {} ({:04x}:{:04x}) is not connected", name, vendor_id, product_id),
+                        _ => {}
+                    }
+                }
+            }
+            if open.is_empty() {
+                thread::sleep(Duration::from_millis(READ_TIMEOUT_MS as u64));
+            }
+            open.retain(|(name, device)| match device.read_timeout(&mut buf, READ_TIMEOUT_MS) {
+                Ok(0) => true,
+                Ok(len) => {
+                    let data = buf[..len].iter().map(|byte| format!("{:02x}", byte)).collect();
+                    on_event(HidEvent::Report(HidReport { device: name.clone(), data }));
+                    true
+                }
+                Err(e) => {
+                    tracing::warn!("HID: stopped reading {}: {}", name, e);
+                    on_event(HidEvent::Disconnected(name.clone()));
+                    false
+                }
+            });
+        }
+    });
+}
+
+#[cfg(not(feature = "hid"))]
+pub fn spawn(_devices: &[HidDeviceConfig], _stop: Arc<AtomicBool>, _on_event: impl FnMut(HidEvent) + Send + 'static) {
+    tracing::warn!("This build can't read HID devices; rebuild it with `--features hid`");
+}
@@ -0,0 +1,73 @@
+//! Recently recorded task names, offered for autocompletion so recurring
+//! tasks keep the same name. Kept in `recent_tasks.json` in the app's data
+//! directory.
+
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+use crate::storage::SessionMeta;
+
+const MAX_RECENT: usize = 30;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecentTask {
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// Local date the task was last started, as YYYY-MM-DD.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+}
+
+fn path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("activity-tracker").join("recent_tasks.json"))
+}
+
+/// Recent tasks, most recent first. A missing or unreadable file is an
+/// empty history.
+pub fn load() -> Vec<RecentTask> {
+    path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Moves the session's task to the front of the history.
+pub fn remember(meta: &SessionMeta) -> Result<(), String> {
+    let path = path().ok_or("No data directory")?;
+    let mut recent = load();
+    recent.retain(|r| r.task != meta.task);
+    recent.insert(
+        0,
+        RecentTask {
+            task: meta.task.clone(),
+            project: meta.project.clone(),
+            preset: meta.preset.clone(),
+            date: Some(Local::now().format("%Y-%m-%d").to_string()),
+        },
+    );
+    recent.truncate(MAX_RECENT);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&recent).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Recent tasks containing `text`, ignoring case, those starting with it
+/// first.
+pub fn matching<'a>(recent: &'a [RecentTask], text: &str) -> Vec<&'a RecentTask> {
+    let needle = text.trim().to_lowercase();
+    let mut matches: Vec<&RecentTask> = recent
+        .iter()
+        .filter(|r| r.task.to_lowercase().contains(&needle) && r.task != text)
+        .collect();
+    matches.sort_by_key(|r| !r.task.to_lowercase().starts_with(&needle));
+    matches
+}
@@ -0,0 +1,312 @@
+//! Just enough HTTP/1.1 to talk to web services. Plain `http://` is spoken
+//! directly; `https://` requests go through the system's `curl`, since no
+//! TLS library is bundled.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sends `body` and fails unless the server answers with a 2xx status.
+pub fn post(url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<(), String> {
+    request("POST", url, headers, body).map(|_| ())
+}
+
+/// Why a request failed, and whether trying again later might help.
+pub struct HttpError {
+    pub message: String,
+    /// No connection, a timeout, or a 408, 429 or 5xx answer.
+    pub transient: bool,
+}
+
+impl From<HttpError> for String {
+    fn from(e: HttpError) -> Self {
+        e.message
+    }
+}
+
+/// Sends a request and returns the response body, failing unless the
+/// server answers with a 2xx status.
+pub fn request(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Vec<u8>, String> {
+    Ok(send(method, url, headers, body)?)
+}
+
+/// Like [`request`], telling failures that may pass from those that won't.
+pub fn send(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Vec<u8>, HttpError> {
+    if headers.iter().any(|(name, value)| name.contains(['\r', '\n']) || value.contains(['\r', '\n'])) {
+        return Err(HttpError {
+            message: format!("Request to {} not sent: a header contains a line break", url),
+            transient: false,
+        });
+    }
+    if url.starts_with("https://") {
+        return curl(method, url, headers, body);
+    }
+    let rest = url.strip_prefix("http://").ok_or_else(|| HttpError {
+        message: format!("Unsupported URL {}: only http:// and https:// are supported", url),
+        transient: false,
+    })?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+
+    let request_err = |e: std::io::Error| HttpError {
+        message: format!("Request to {} failed: {}", url, e),
+        transient: true,
+    };
+    let mut stream = TcpStream::connect(&address).map_err(request_err)?;
+    stream.set_read_timeout(Some(TIMEOUT)).map_err(request_err)?;
+    stream.set_write_timeout(Some(TIMEOUT)).map_err(request_err)?;
+
+    let mut head = format!(
+        "{} {} HTTP/1.1\r\nHost: {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        method,
+        path,
+        authority,
+        body.len()
+    );
+    for (name, value) in headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes()).map_err(request_err)?;
+    stream.write_all(body).map_err(request_err)?;
+
+    let mut reader = BufReader::new(stream);
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line).map_err(request_err)?;
+    let status = status_line.split_whitespace().nth(1).unwrap_or("").to_string();
+    let mut chunked = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(request_err)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            chunked |= name.trim().eq_ignore_ascii_case("transfer-encoding") && value.trim().eq_ignore_ascii_case("chunked");
+        }
+    }
+    let mut response = Vec::new();
+    if chunked {
+        read_chunks(&mut reader, &mut response).map_err(request_err)?;
+    } else {
+        // Connection: close, so the body runs to the end of the stream
+        let _ = reader.read_to_end(&mut response);
+    }
+
+    if !status.starts_with('2') {
+        return Err(status_error(url, status_line.trim(), &status, &response));
+    }
+    Ok(response)
+}
+
+fn read_chunks(reader: &mut impl BufRead, body: &mut Vec<u8>) -> std::io::Result<()> {
+    loop {
+        let mut size = String::new();
+        reader.read_line(&mut size)?;
+        let size = usize::from_str_radix(size.trim().split(';').next().unwrap_or(""), 16).unwrap_or(0);
+        if size == 0 {
+            return Ok(());
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = String::new();
+        reader.read_line(&mut crlf)?;
+    }
+}
+
+/// Runs the request through `curl`. Headers and the body's location are
+/// passed as a config on stdin, so tokens don't show up in the process list.
+fn curl(method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<Vec<u8>, HttpError> {
+    let body_path = temp_file("body", body).map_err(|message| HttpError { message, transient: false })?;
+
+    let mut config = format!(
+        "url = {}\nrequest = {}\ndata-binary = {}\nsilent\nshow-error\nmax-time = {}\nwrite-out = \"\\n%{{http_code}}\"\n",
+        curl_string(url),
+        curl_string(method),
+        curl_string(&format!("@{}", body_path.display())),
+        TIMEOUT.as_secs()
+    );
+    for (name, value) in headers {
+        config.push_str(&format!("header = {}\n", curl_string(&format!("{}: {}", name, value))));
+    }
+
+    let result = run_curl(&config);
+    let _ = fs::remove_file(&body_path);
+    let output = result.map_err(|e| HttpError {
+        message: format!("Request to {} failed: {}", url, e),
+        transient: false,
+    })?;
+    if !output.status.success() {
+        return Err(HttpError {
+            message: format!("Request to {} failed: {}", url, String::from_utf8_lossy(&output.stderr).trim()),
+            // Resolving, connecting, timeouts, TLS handshakes and dropped connections
+            transient: matches!(output.status.code(), Some(5 | 6 | 7 | 28 | 35 | 52 | 55 | 56)),
+        });
+    }
+
+    // The status code is written after the body, on a line of its own
+    let mut response = output.stdout;
+    let split = response.iter().rposition(|&b| b == b'\n').unwrap_or(0);
+    let status = String::from_utf8_lossy(&response[split..]).trim().to_string();
+    response.truncate(split);
+    if !status.starts_with('2') {
+        return Err(status_error(url, &status, &status, &response));
+    }
+    Ok(response)
+}
+
+fn status_error(url: &str, status_line: &str, status: &str, response: &[u8]) -> HttpError {
+    HttpError {
+        message: format!("{} answered {}: {}", url, status_line, String::from_utf8_lossy(response).trim()),
+        transient: status.starts_with('5') || status == "408" || status == "429",
+    }
+}
+
+/// `value` quoted for a curl config file. Line breaks are escaped, so a
+/// value can't end its line and add options of its own.
+pub fn curl_string(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Writes `contents` to a new file in the temp folder that only the user
+/// can read, for `curl` to send. The caller removes it.
+pub fn temp_file(extension: &str, contents: &[u8]) -> Result<PathBuf, String> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "activity-tracker-{}-{}.{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed),
+        extension
+    ));
+    let mut options = fs::OpenOptions::new();
+    // Never follow a file or link someone else left at that name
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(&path)
+        .and_then(|mut file| file.write_all(contents))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+/// Runs `curl` with `config` as its config file, given on stdin.
+pub fn run_curl(config: &str) -> std::io::Result<std::process::Output> {
+    let mut child = Command::new("curl")
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    child.wait_with_output()
+}
+
+/// Percent-encodes `text` for a URL path segment, query or form value.
+pub fn encode(text: &str) -> String {
+    let mut encoded = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Reverses [`encode`], also reading `+` as a space as in form values.
+pub fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (byte, _) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// `application/x-www-form-urlencoded` body from name/value pairs.
+pub fn form(fields: &[(&str, &str)]) -> String {
+    fields
+        .iter()
+        .map(|(name, value)| format!("{}={}", encode(name), encode(value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Value of an `Authorization` header for HTTP Basic authentication.
+pub fn basic_auth(username: &str, password: &str) -> String {
+    format!("Basic {}", base64(format!("{}:{}", username, password).as_bytes()))
+}
+
+/// Standard, padded base64.
+pub fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curl_string_escapes_line_breaks_and_quotes() {
+        assert_eq!(curl_string("a \"b\"\\c"), r#""a \"b\"\\c""#);
+        assert_eq!(curl_string("x\nurl = evil"), r#""x\nurl = evil""#);
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let text = "a b&c=d/é?";
+        assert_eq!(encode(text), "a%20b%26c%3Dd%2F%C3%A9%3F");
+        assert_eq!(decode(&encode(text)), text);
+        assert_eq!(decode("a+b%2"), "a b%2");
+    }
+}
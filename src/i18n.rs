@@ -0,0 +1,111 @@
+//! Translated text for the GUI and spoken announcements, kept in Fluent
+//! (`.ftl`) files under `locales/`.
+//!
+//! Only the plain subset of Fluent is understood: `key = value` messages,
+//! indented continuation lines, `#` comments and `{ $name }` variables.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::OnceLock;
+
+/// Languages shipped with the app, by code, with their message files.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+];
+
+const FALLBACK: &str = "en";
+
+static BUNDLE: OnceLock<Bundle> = OnceLock::new();
+
+struct Bundle {
+    messages: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+/// Selects the language used by `tr!` for the rest of the process: the
+/// configured one, or else the one named by the environment's locale.
+/// Unknown languages fall back to English.
+pub fn init(language: Option<&str>) {
+    let code = language.map(str::to_string).or_else(system_language).unwrap_or_default();
+    let code = if source(&code).is_some() { code.as_str() } else { FALLBACK };
+    tracing::info!(language = code, "using translations");
+    let _ = BUNDLE.set(Bundle {
+        messages: parse(source(code).unwrap_or_default()),
+        fallback: parse(source(FALLBACK).unwrap_or_default()),
+    });
+}
+
+/// Looks up a message and fills in its variables. Untranslated messages
+/// use the English text, and unknown ones show their key.
+pub fn translate(key: &str, args: &[(&str, String)]) -> String {
+    let bundle = BUNDLE.get_or_init(|| Bundle {
+        messages: HashMap::new(),
+        fallback: parse(source(FALLBACK).unwrap_or_default()),
+    });
+    let Some(message) = bundle.messages.get(key).or_else(|| bundle.fallback.get(key)) else {
+        return key.to_string();
+    };
+    let mut text = message.clone();
+    for (name, value) in args {
+        text = text.replace(&format!("{{ ${} }}", name), value);
+    }
+    text
+}
+
+/// Translates a message, e.g. `tr!("countdown", seconds = 3)`.
+macro_rules! tr {
+    ($key:expr) => {
+        $crate::i18n::translate($key, &[])
+    };
+    ($key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::translate($key, &[$((stringify!($name), $value.to_string())),+])
+    };
+}
+pub(crate) use tr;
+
+fn source(code: &str) -> Option<&'static str> {
+    LANGUAGES.iter().find(|(c, _)| *c == code).map(|(_, source)| *source)
+}
+
+/// Language code from the usual locale variables, e.g. "es" for `es_ES.UTF-8`.
+fn system_language() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .map(|value| {
+            value
+                .split(['_', '-', '.', '@'])
+                .next()
+                .unwrap_or_default()
+                .to_lowercase()
+        })
+}
+
+fn parse(source: &str) -> HashMap<String, String> {
+    let mut messages = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in source.lines() {
+        if line.trim_start().starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        if line.starts_with(' ') {
+            // Continuation of the previous message's value
+            if let Some(value) = current.as_ref().and_then(|key| messages.get_mut(key)) {
+                let value: &mut String = value;
+                if !value.is_empty() {
+                    value.push('\n');
+                }
+                value.push_str(line.trim());
+            }
+            continue;
+        }
+        if let Some((key, value)) = line.split_once('=') {
+            let key = key.trim().to_string();
+            messages.insert(key.clone(), value.trim().to_string());
+            current = Some(key);
+        }
+    }
+    messages
+}
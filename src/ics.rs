@@ -0,0 +1,117 @@
+//! iCalendar (RFC 5545) export: one event per session, so tracked work shows
+//! up in any calendar app. Event UIDs are derived from the session's start
+//! and task, so importing the file again updates events instead of
+//! duplicating them.
+
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+
+use crate::analysis::Summary;
+use crate::cli;
+use crate::storage::{self, SessionMeta};
+
+/// Content lines longer than this many bytes are folded.
+const MAX_LINE_BYTES: usize = 75;
+
+pub fn write_calendar(sessions: &[(SessionMeta, Summary)], out: &mut impl Write) -> io::Result<()> {
+    let stamp = utc(Utc::now().timestamp() as u64);
+    write_begin(out)?;
+    for (meta, summary) in sessions {
+        if summary.samples > 0 {
+            write_event(out, meta, summary, &stamp)?;
+        }
+    }
+    write_line(out, "END:VCALENDAR")
+}
+
+/// A calendar holding just this session's event, as stored on a CalDAV
+/// server.
+pub fn single_event(meta: &SessionMeta, summary: &Summary) -> Vec<u8> {
+    let mut out = Vec::new();
+    let stamp = utc(Utc::now().timestamp() as u64);
+    // Writing to a Vec can't fail
+    let _ = write_begin(&mut out)
+        .and_then(|_| write_event(&mut out, meta, summary, &stamp))
+        .and_then(|_| write_line(&mut out, "END:VCALENDAR"));
+    out
+}
+
+/// Stable identifier of the session's event.
+pub fn uid(meta: &SessionMeta, summary: &Summary) -> String {
+    format!("{}-{}@activity-tracker", summary.start, storage::sanitize(&meta.task))
+}
+
+fn write_begin(out: &mut impl Write) -> io::Result<()> {
+    write_line(out, "BEGIN:VCALENDAR")?;
+    write_line(out, "VERSION:2.0")?;
+    write_line(out, "PRODID:-//activity-tracker//EN")?;
+    write_line(out, "CALSCALE:GREGORIAN")
+}
+
+fn write_event(out: &mut impl Write, meta: &SessionMeta, summary: &Summary, stamp: &str) -> io::Result<()> {
+    write_line(out, "BEGIN:VEVENT")?;
+    write_line(out, &format!("UID:{}", uid(meta, summary)))?;
+    write_line(out, &format!("DTSTAMP:{}", stamp))?;
+    write_line(out, &format!("DTSTART:{}", utc(summary.start)))?;
+    write_line(out, &format!("DTEND:{}", utc(summary.end.max(summary.start + 1))))?;
+    write_line(out, &format!("SUMMARY:{}", escape(&meta.task)))?;
+    write_line(out, &format!("DESCRIPTION:{}", escape(&description(meta, summary))))?;
+    if !meta.tags.is_empty() {
+        let tags: Vec<String> = meta.tags.iter().map(|tag| escape(tag)).collect();
+        write_line(out, &format!("CATEGORIES:{}", tags.join(",")))?;
+    }
+    write_line(out, "END:VEVENT")
+}
+
+fn description(meta: &SessionMeta, summary: &Summary) -> String {
+    let mut lines = Vec::new();
+    if let Some(project) = &meta.project {
+        lines.push(format!("Project: {}", project));
+    }
+    lines.push(format!("Tracked: {}", cli::format_duration(summary.tracked_secs())));
+    if let Some(estimate) = meta.estimate_secs {
+        lines.push(format!("Estimate: {}", cli::format_duration(estimate)));
+    }
+    lines.push(format!("Key presses: {}", summary.key_presses));
+    lines.push(format!("Clicks: {}", summary.clicks));
+    lines.push(format!("Mouse distance: {:.0} px", summary.mouse_distance));
+    lines.push(format!("Idle: {:.1}%", summary.idle_ratio() * 100.0));
+    lines.join("\n")
+}
+
+fn utc(timestamp: u64) -> String {
+    DateTime::from_timestamp(timestamp as i64, 0)
+        .unwrap_or_default()
+        .format("%Y%m%dT%H%M%SZ")
+        .to_string()
+}
+
+/// Escapes a TEXT value.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Writes a content line with CRLF, folding it onto continuation lines that
+/// start with a space, without splitting a UTF-8 character.
+fn write_line(out: &mut impl Write, line: &str) -> io::Result<()> {
+    let mut start = 0;
+    let mut limit = MAX_LINE_BYTES;
+    while line.len() - start > limit {
+        let mut end = start + limit;
+        while !line.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.write_all(&line.as_bytes()[start..end])?;
+        out.write_all(b"\r\n ")?;
+        start = end;
+        // The leading space counts towards the continuation's length
+        limit = MAX_LINE_BYTES - 1;
+    }
+    out.write_all(&line.as_bytes()[start..])?;
+    out.write_all(b"\r\n")
+}
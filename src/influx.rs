@@ -0,0 +1,188 @@
+//! Built-in InfluxDB sink, configured under `influxdb` in the settings file:
+//!
+//! ```json
+//! "influxdb": {
+//!     "url": "http://localhost:8086/api/v2/write?org=me&bucket=activity",
+//!     "token": "...",
+//!     "measurement": "activity",
+//!     "tags": { "host": "laptop" }
+//! }
+//! ```
+//!
+//! When a session is saved its samples, or per-minute aggregates of them,
+//! are written in line protocol to `url`; the 1.x `/write?db=...` endpoint
+//! works as well. Each point is tagged with the task and project.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::outbox;
+use crate::recorder::{ActivityRecord, SAMPLE_INTERVAL};
+use crate::storage::SessionMeta;
+
+/// Lines sent per request.
+const BATCH_LINES: usize = 5000;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resolution {
+    Sample,
+    #[default]
+    Minute,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InfluxConfig {
+    /// Write endpoint including its query string.
+    pub url: String,
+    /// API token, sent as `Authorization: Token ...`.
+    pub token: Option<String>,
+    pub measurement: String,
+    /// Names of the tags holding the task and the project.
+    pub task_tag: String,
+    pub project_tag: String,
+    /// Extra tags added to every point.
+    pub tags: BTreeMap<String, String>,
+    pub resolution: Resolution,
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        InfluxConfig {
+            url: String::new(),
+            token: None,
+            measurement: "activity".to_string(),
+            task_tag: "task".to_string(),
+            project_tag: "project".to_string(),
+            tags: BTreeMap::new(),
+            resolution: Resolution::default(),
+        }
+    }
+}
+
+impl InfluxConfig {
+    pub fn write(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        if self.url.is_empty() {
+            return Err("InfluxDB sink has no url".to_string());
+        }
+        let series = self.series_key(meta);
+        let lines = match self.resolution {
+            Resolution::Sample => sample_lines(&series, records),
+            Resolution::Minute => minute_lines(&series, records),
+        };
+
+        let url = if self.url.contains("precision=") {
+            self.url.clone()
+        } else {
+            let separator = if self.url.contains('?') { '&' } else { '?' };
+            format!("{}{}precision=ms", self.url, separator)
+        };
+        let auth = self.token.as_ref().map(|token| format!("Token {}", token));
+        let mut headers = vec![("Content-Type", "text/plain; charset=utf-8")];
+        if let Some(auth) = &auth {
+            headers.push(("Authorization", auth));
+        }
+        // Later batches are still sent, or queued, when one fails
+        let mut result = Ok(());
+        for batch in lines.chunks(BATCH_LINES) {
+            let sent = outbox::deliver("InfluxDB sink", "POST", &url, &headers, batch.join("\n").as_bytes());
+            result = result.and(sent);
+        }
+        result
+    }
+
+    /// Measurement and tags, escaped, in the order line protocol wants them.
+    fn series_key(&self, meta: &SessionMeta) -> String {
+        let mut tags: BTreeMap<&str, &str> = self.tags.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        tags.insert(&self.task_tag, &meta.task);
+        if let Some(project) = &meta.project {
+            tags.insert(&self.project_tag, project);
+        }
+        let mut key = escape(&self.measurement, ", ");
+        for (name, value) in tags.into_iter().filter(|(_, v)| !v.is_empty()) {
+            key.push_str(&format!(",{}={}", escape(name, ",= "), escape(value, ",= ")));
+        }
+        key
+    }
+}
+
+/// One point per sample. Samples within the same second are spread over it
+/// at the capture interval, as points with equal timestamps would replace
+/// each other.
+fn sample_lines(series: &str, records: &[ActivityRecord]) -> Vec<String> {
+    let step_ms = SAMPLE_INTERVAL.as_millis() as u64;
+    let mut offset = 0;
+    let mut previous: Option<&ActivityRecord> = None;
+    records
+        .iter()
+        .map(|record| {
+            offset = match previous {
+                Some(p) if p.timestamp == record.timestamp => (offset + step_ms).min(999),
+                _ => 0,
+            };
+            let line = format!(
+                "{} mouse_x={}i,mouse_y={}i,keys={}i,clicks={}i {}",
+                series,
+                record.mouse_x,
+                record.mouse_y,
+                analysis::new_key_presses(previous, record),
+                analysis::new_clicks(previous, record),
+                record.timestamp * 1000 + offset
+            );
+            previous = Some(record);
+            line
+        })
+        .collect()
+}
+
+fn minute_lines(series: &str, records: &[ActivityRecord]) -> Vec<String> {
+    #[derive(Default)]
+    struct Minute {
+        samples: usize,
+        keys: usize,
+        clicks: usize,
+        distance: f64,
+    }
+    let mut minutes: BTreeMap<u64, Minute> = BTreeMap::new();
+    let mut previous: Option<&ActivityRecord> = None;
+    for record in records {
+        let minute = minutes.entry(record.timestamp / 60 * 60).or_default();
+        minute.samples += 1;
+        minute.keys += analysis::new_key_presses(previous, record);
+        minute.clicks += analysis::new_clicks(previous, record);
+        if let Some(p) = previous {
+            minute.distance += ((record.mouse_x - p.mouse_x) as f64).hypot((record.mouse_y - p.mouse_y) as f64);
+        }
+        previous = Some(record);
+    }
+
+    minutes
+        .into_iter()
+        .map(|(start, m)| {
+            format!(
+                "{} samples={}i,keys={}i,clicks={}i,mouse_distance={:.1} {}",
+                series,
+                m.samples,
+                m.keys,
+                m.clicks,
+                m.distance,
+                start * 1000
+            )
+        })
+        .collect()
+}
+
+/// Backslash-escapes `special` characters as line protocol requires.
+fn escape(text: &str, special: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if special.contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,171 @@
+//! Distributions of input timing: the time between key presses and between
+//! clicks, and how long keys and buttons are held. Times come from sample
+//! counts, so their resolution is one capture interval, and intervals
+//! spanning a gap in capture are left out.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::analysis;
+use crate::recorder::{ActivityRecord, SAMPLE_INTERVAL};
+
+#[derive(Serialize)]
+pub struct Histogram {
+    pub name: &'static str,
+    pub count: usize,
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    /// Count per bin; bin `i` covers `[i * bin_ms, (i + 1) * bin_ms)`.
+    pub bins: Vec<usize>,
+    /// Values at or above the last bin's end.
+    pub overflow: usize,
+}
+
+#[derive(Serialize)]
+pub struct Distributions {
+    pub bin_ms: u64,
+    pub histograms: Vec<Histogram>,
+}
+
+/// Durations in samples, collected per distribution.
+#[derive(Default)]
+struct Samples {
+    inter_key: Vec<u64>,
+    inter_click: Vec<u64>,
+    key_dwell: Vec<u64>,
+    button_dwell: Vec<u64>,
+}
+
+impl Samples {
+    fn add_session(&mut self, records: &[ActivityRecord]) {
+        let mut last_key: Option<u64> = None;
+        let mut last_click: Option<u64> = None;
+        let mut keys_down: HashMap<String, u64> = HashMap::new();
+        let mut buttons_down: HashMap<u8, u64> = HashMap::new();
+
+        let mut previous: Option<&ActivityRecord> = None;
+        for (i, record) in records.iter().enumerate() {
+            let i = i as u64;
+            if record.gap.is_some() {
+                // Nothing is known about what happened in between
+                last_key = None;
+                last_click = None;
+                keys_down.clear();
+                buttons_down.clear();
+                previous = None;
+            }
+            if analysis::new_key_presses(previous, record) > 0 {
+                if let Some(last) = last_key {
+                    self.inter_key.push(i - last);
+                }
+                last_key = Some(i);
+            }
+            if analysis::new_clicks(previous, record) > 0 {
+                if let Some(last) = last_click {
+                    self.inter_click.push(i - last);
+                }
+                last_click = Some(i);
+            }
+
+            let keys: Vec<String> = record.keys_pressed.iter().map(|k| format!("{:?}", k)).collect();
+            keys_down.retain(|key, down| {
+                let held = keys.contains(key);
+                if !held {
+                    self.key_dwell.push(i - *down);
+                }
+                held
+            });
+            for key in keys {
+                keys_down.entry(key).or_insert(i);
+            }
+            buttons_down.retain(|button, down| {
+                let held = record.mouse_buttons.contains(button);
+                if !held {
+                    self.button_dwell.push(i - *down);
+                }
+                held
+            });
+            for &button in record.mouse_buttons.iter().filter(|b| (1..=3).contains(*b)) {
+                buttons_down.entry(button).or_insert(i);
+            }
+            previous = Some(record);
+        }
+    }
+}
+
+impl Distributions {
+    /// Histograms with `bin_ms` wide bins up to `max_ms`.
+    pub fn from_sessions<'a>(sessions: impl IntoIterator<Item = &'a [ActivityRecord]>, bin_ms: u64, max_ms: u64) -> Self {
+        let mut samples = Samples::default();
+        for records in sessions {
+            samples.add_session(records);
+        }
+        let bin_ms = bin_ms.max(1);
+        let histograms = [
+            ("inter_key", samples.inter_key),
+            ("inter_click", samples.inter_click),
+            ("key_dwell", samples.key_dwell),
+            ("button_dwell", samples.button_dwell),
+        ]
+        .into_iter()
+        .map(|(name, durations)| Histogram::new(name, durations, bin_ms, max_ms))
+        .collect();
+        Distributions { bin_ms, histograms }
+    }
+
+    pub fn print(&self) {
+        for h in &self.histograms {
+            println!(
+                "{:<13} n = {:<7} mean {:>6.0} ms  median {:>6.0} ms",
+                h.name, h.count, h.mean_ms, h.median_ms
+            );
+        }
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "distribution,bin_start_ms,bin_end_ms,count")?;
+        for h in &self.histograms {
+            for (i, count) in h.bins.iter().enumerate() {
+                let start = i as u64 * self.bin_ms;
+                writeln!(out, "{},{},{},{}", h.name, start, start + self.bin_ms, count)?;
+            }
+            writeln!(out, "{},{},,{}", h.name, h.bins.len() as u64 * self.bin_ms, h.overflow)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+}
+
+impl Histogram {
+    fn new(name: &'static str, mut samples: Vec<u64>, bin_ms: u64, max_ms: u64) -> Self {
+        let sample_ms = SAMPLE_INTERVAL.as_millis() as u64;
+        samples.sort_unstable();
+        let count = samples.len();
+        let to_ms = |samples: u64| samples * sample_ms;
+        let mean_ms = match count {
+            0 => 0.0,
+            n => samples.iter().map(|&s| to_ms(s)).sum::<u64>() as f64 / n as f64,
+        };
+        let median_ms = match count {
+            0 => 0.0,
+            n if n % 2 == 1 => to_ms(samples[n / 2]) as f64,
+            n => (to_ms(samples[n / 2 - 1]) + to_ms(samples[n / 2])) as f64 / 2.0,
+        };
+
+        let mut bins = vec![0; max_ms.div_ceil(bin_ms) as usize];
+        let mut overflow = 0;
+        for ms in samples.into_iter().map(to_ms) {
+            match bins.get_mut((ms / bin_ms) as usize) {
+                Some(bin) => *bin += 1,
+                None => overflow += 1,
+            }
+        }
+        Histogram { name, count, mean_ms, median_ms, bins, overflow }
+    }
+}
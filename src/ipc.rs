@@ -0,0 +1,135 @@
+//! Local control endpoint: a Unix socket on Linux/macOS and a named pipe on
+//! Windows. Clients send newline-delimited commands (`start <task>`, `stop`,
+//! `status`, `mark [label]`) and get one `ok ...` / `error ...` line back per
+//! command, e.g. `echo status | nc -U "$XDG_RUNTIME_DIR/activity-tracker.sock"`.
+//! `tab <json>` passes on the browser's active tab, see `native_messaging.rs`.
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::Arc;
+use std::thread;
+
+use interprocess::local_socket::{prelude::*, ListenerOptions, Name, Stream};
+
+use crate::control::Command;
+use crate::recorder::Recorder;
+
+#[cfg(unix)]
+pub fn endpoint_display() -> String {
+    socket_path().display().to_string()
+}
+
+#[cfg(windows)]
+pub fn endpoint_display() -> String {
+    format!(r"\\.\pipe\{}", PIPE_NAME)
+}
+
+/// In the user's runtime directory, or their data directory where there is
+/// none (macOS), so other users can't take the name.
+#[cfg(unix)]
+fn socket_path() -> std::path::PathBuf {
+    dirs::runtime_dir()
+        .or_else(|| dirs::data_local_dir().map(|dir| dir.join("activity-tracker")))
+        .unwrap_or_else(|| std::path::PathBuf::from("."))
+        .join("activity-tracker.sock")
+}
+
+#[cfg(windows)]
+const PIPE_NAME: &str = "activity-tracker";
+
+#[cfg(unix)]
+fn endpoint_name() -> io::Result<Name<'static>> {
+    use interprocess::local_socket::GenericFilePath;
+    socket_path().to_fs_name::<GenericFilePath>()
+}
+
+#[cfg(windows)]
+fn endpoint_name() -> io::Result<Name<'static>> {
+    use interprocess::local_socket::GenericNamespaced;
+    PIPE_NAME.to_ns_name::<GenericNamespaced>()
+}
+
+/// Starts listening on the control endpoint in a background thread.
+/// `on_change` is called after every command so the UI can refresh.
+pub fn spawn_server(
+    recorder: Recorder,
+    on_change: impl Fn() + Send + Sync + 'static,
+) -> io::Result<()> {
+    // Another instance answering keeps its endpoint
+    if Stream::connect(endpoint_name()?).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AddrInUse,
+            format!("another instance is listening on {}", endpoint_display()),
+        ));
+    }
+    #[cfg(unix)]
+    {
+        let path = socket_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Nothing answered, so a socket file here was left by a run that crashed
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+    let listener = ListenerOptions::new().name(endpoint_name()?).create_sync()?;
+    let on_change = Arc::new(on_change);
+
+    thread::spawn(move || {
+        for conn in listener.incoming() {
+            let conn = match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::warn!("control socket: failed to accept a connection: {}", e);
+                    continue;
+                }
+            };
+            let recorder = recorder.clone();
+            let on_change = Arc::clone(&on_change);
+            thread::spawn(move || {
+                if let Err(e) = handle_client(conn, &recorder, &*on_change) {
+                    tracing::debug!("control socket: client error: {}", e);
+                }
+            });
+        }
+    });
+
+    Ok(())
+}
+
+fn handle_client(conn: Stream, recorder: &Recorder, on_change: &dyn Fn()) -> io::Result<()> {
+    let mut conn = BufReader::new(conn);
+    let mut line = String::new();
+
+    while conn.read_line(&mut line)? > 0 {
+        let reply = match Command::parse(&line).and_then(|cmd| cmd.execute(recorder)) {
+            Ok(msg) => format!("ok {}\n", msg),
+            Err(e) => format!("error {}\n", e),
+        };
+        conn.get_mut().write_all(reply.as_bytes())?;
+        on_change();
+        line.clear();
+    }
+
+    Ok(())
+}
+
+/// Sends one command to the running instance and returns its reply.
+pub fn send(command: &str) -> Result<String, String> {
+    let conn = Stream::connect(endpoint_name().map_err(|e| e.to_string())?)
+        .map_err(|e| format!("activity-tracker is not running ({}): {}", endpoint_display(), e))?;
+    let mut conn = BufReader::new(conn);
+    conn.get_mut()
+        .write_all(format!("{}\n", command).as_bytes())
+        .map_err(|e| format!("Failed to send to {}: {}", endpoint_display(), e))?;
+    let mut reply = String::new();
+    conn.read_line(&mut reply)
+        .map_err(|e| format!("Failed to read from {}: {}", endpoint_display(), e))?;
+    let reply = reply.trim_end();
+    match reply.split_once(' ').unwrap_or((reply, "")) {
+        ("ok", message) => Ok(message.to_string()),
+        ("error", error) => Err(error.to_string()),
+        _ => Err(format!("unexpected reply from {}: {:?}", endpoint_display(), reply)),
+    }
+}
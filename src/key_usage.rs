@@ -0,0 +1,77 @@
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::analysis;
+use crate::keys::{self, KeyCategory};
+use crate::recorder::ActivityRecord;
+
+/// Presses per key plus how much of the keyboard they cover.
+#[derive(Default, Serialize)]
+pub struct KeyUsage {
+    pub total_presses: usize,
+    /// Share of the standard keyboard's keys pressed at least once.
+    pub coverage: f64,
+    pub keys: Vec<KeyCount>,
+}
+
+#[derive(Serialize)]
+pub struct KeyCount {
+    pub key: String,
+    pub category: KeyCategory,
+    pub count: usize,
+    pub share: f64,
+}
+
+impl KeyUsage {
+    pub fn from_sessions<'a>(sessions: impl IntoIterator<Item = &'a [ActivityRecord]>) -> Self {
+        let mut counts = std::collections::HashMap::new();
+        for records in sessions {
+            for (key, count) in analysis::key_frequency(records) {
+                *counts.entry(key).or_insert(0) += count;
+            }
+        }
+
+        let total_presses: usize = counts.values().sum();
+        let standard = keys::ALL.iter().filter(|k| keys::is_standard(**k));
+        let covered = standard.clone().filter(|k| counts.contains_key(*k)).count();
+
+        let mut keys: Vec<KeyCount> = counts
+            .into_iter()
+            .map(|(key, count)| KeyCount {
+                key: format!("{:?}", key),
+                category: keys::category(key),
+                count,
+                share: count as f64 / total_presses as f64,
+            })
+            .collect();
+        keys.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+
+        KeyUsage {
+            total_presses,
+            coverage: covered as f64 / standard.count() as f64,
+            keys,
+        }
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "key,category,count,share")?;
+        for key in &self.keys {
+            writeln!(out, "{},{},{},{:.4}", key.key, key.category.as_str(), key.count, key.share)?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    pub fn print(&self) {
+        println!("Key presses:     {}", self.total_presses);
+        println!("Coverage:        {:.1}% of the keyboard", self.coverage * 100.0);
+        for key in &self.keys {
+            println!("{:<16} {:>8}  {:>5.1}%", key.key, key.count, key.share * 100.0);
+        }
+    }
+}
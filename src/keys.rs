@@ -0,0 +1,81 @@
+use device_query::Keycode;
+use serde::{Deserialize, Serialize};
+
+/// Coarse grouping of keys, used by filters and reports.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KeyCategory {
+    Letters,
+    Digits,
+    Function,
+    Modifiers,
+    Navigation,
+    Editing,
+    Symbols,
+}
+
+impl KeyCategory {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            KeyCategory::Letters => "letters",
+            KeyCategory::Digits => "digits",
+            KeyCategory::Function => "function",
+            KeyCategory::Modifiers => "modifiers",
+            KeyCategory::Navigation => "navigation",
+            KeyCategory::Editing => "editing",
+            KeyCategory::Symbols => "symbols",
+        }
+    }
+}
+
+/// Every key the capture backend can report.
+pub const ALL: &[Keycode] = {
+    use Keycode::*;
+    &[
+    Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, A, B, C, D, E, F, G, H, I, J, K,
+    L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11,
+    F12, F13, F14, F15, F16, F17, F18, F19, F20, Escape, Space, LControl, RControl, LShift,
+    RShift, LAlt, RAlt, Command, LOption, ROption, LMeta, RMeta, Enter, Up, Down, Left, Right,
+    Backspace, CapsLock, Tab, Home, End, PageUp, PageDown, Insert, Delete, Numpad0, Numpad1,
+    Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9, NumpadSubtract,
+    NumpadAdd, NumpadDivide, NumpadMultiply, NumpadEquals, NumpadEnter, NumpadDecimal, Grave,
+    Minus, Equal, LeftBracket, RightBracket, BackSlash, Semicolon, Apostrophe, Comma, Dot,
+    Slash,
+    ]
+};
+
+/// Whether the key is on a typical full-size keyboard: F13-F20 rarely
+/// exist, and macOS' Command/Option are reported instead of Meta/Alt
+/// there, never as well.
+pub fn is_standard(key: Keycode) -> bool {
+    use Keycode::*;
+
+    !matches!(
+        key,
+        F13 | F14 | F15 | F16 | F17 | F18 | F19 | F20 | Command | LOption | ROption
+    )
+}
+
+pub fn category(key: Keycode) -> KeyCategory {
+    use Keycode::*;
+
+    match key {
+        A | B | C | D | E | F | G | H | I | J | K | L | M | N | O | P | Q | R | S | T | U | V
+        | W | X | Y | Z => KeyCategory::Letters,
+        Key0 | Key1 | Key2 | Key3 | Key4 | Key5 | Key6 | Key7 | Key8 | Key9 | Numpad0 | Numpad1
+        | Numpad2 | Numpad3 | Numpad4 | Numpad5 | Numpad6 | Numpad7 | Numpad8 | Numpad9 => {
+            KeyCategory::Digits
+        }
+        F1 | F2 | F3 | F4 | F5 | F6 | F7 | F8 | F9 | F10 | F11 | F12 | F13 | F14 | F15 | F16
+        | F17 | F18 | F19 | F20 => KeyCategory::Function,
+        LControl | RControl | LShift | RShift | LAlt | RAlt | Command | LOption | ROption
+        | LMeta | RMeta | CapsLock => KeyCategory::Modifiers,
+        Up | Down | Left | Right | Home | End | PageUp | PageDown => KeyCategory::Navigation,
+        Escape | Space | Enter | NumpadEnter | Backspace | Tab | Insert | Delete => {
+            KeyCategory::Editing
+        }
+        NumpadSubtract | NumpadAdd | NumpadDivide | NumpadMultiply | NumpadEquals
+        | NumpadDecimal | Grave | Minus | Equal | LeftBracket | RightBracket | BackSlash
+        | Semicolon | Apostrophe | Comma | Dot | Slash => KeyCategory::Symbols,
+    }
+}
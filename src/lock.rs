@@ -0,0 +1,55 @@
+//! Screen-lock detection. Linux asks logind for the session's `LockedHint`;
+//! Windows and macOS hand focus to a dedicated lock-screen process, so the
+//! foreground window gives it away.
+
+#[cfg(target_os = "linux")]
+use std::time::{Duration, Instant};
+
+use active_win_pos_rs::ActiveWindow;
+
+/// Whether [`LockMonitor::is_locked`] relies on the foreground window.
+pub const USES_FOREGROUND_WINDOW: bool = cfg!(not(target_os = "linux"));
+
+/// How often logind is asked; spawning `loginctl` at the sample rate would
+/// cost more than the capture itself.
+#[cfg(target_os = "linux")]
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Default)]
+pub struct LockMonitor {
+    #[cfg(target_os = "linux")]
+    last_poll: Option<Instant>,
+    #[cfg(target_os = "linux")]
+    locked: bool,
+}
+
+impl LockMonitor {
+    #[cfg(target_os = "linux")]
+    pub fn is_locked(&mut self, _window: Option<&ActiveWindow>) -> bool {
+        use std::process::Command;
+
+        if self.last_poll.is_some_and(|t| t.elapsed() < POLL_INTERVAL) {
+            return self.locked;
+        }
+        self.last_poll = Some(Instant::now());
+
+        let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+        // No logind (containers, non-systemd distros) means we cannot tell
+        self.locked = Command::new("loginctl")
+            .args(["show-session", &session, "--property=LockedHint", "--value"])
+            .output()
+            .is_ok_and(|out| out.status.success() && out.stdout.trim_ascii() == b"yes");
+        self.locked
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn is_locked(&mut self, window: Option<&ActiveWindow>) -> bool {
+        const LOCK_SCREEN_APPS: &[&str] = if cfg!(windows) {
+            &["LockApp", "LockApp.exe", "LogonUI", "LogonUI.exe"]
+        } else {
+            &["loginwindow"]
+        };
+
+        window.is_some_and(|w| LOCK_SCREEN_APPS.contains(&w.app_name.as_str()))
+    }
+}
@@ -0,0 +1,222 @@
+//! Diagnostic log for troubleshooting capture problems, written through
+//! `tracing` to `activity-tracker.log` in the log directory. The file is
+//! rotated once it grows past `MAX_BYTES`, keeping `KEEP` old files next to
+//! it as `.log.1`, `.log.2`, ...
+
+use std::fmt::{self, Write as _};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use chrono::Local;
+use serde::Deserialize;
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+const MAX_BYTES: u64 = 1024 * 1024;
+const KEEP: usize = 3;
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Off => LevelFilter::OFF,
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+/// The current log file, e.g. `~/.local/share/activity-tracker/logs/activity-tracker.log`.
+pub fn path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("activity-tracker").join("logs").join("activity-tracker.log"))
+}
+
+/// Starts logging at `level`. Failing to open the log file leaves logging
+/// off rather than keeping the app from starting.
+pub fn init(level: LogLevel) {
+    let filter = LevelFilter::from(level);
+    if filter == LevelFilter::OFF {
+        return;
+    }
+    let Some(path) = path() else {
+        return;
+    };
+    let file = match LogFile::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Logging disabled: {}", e);
+            return;
+        }
+    };
+    let logger = FileLogger { filter, file: Mutex::new(file), next_span: AtomicU64::new(1) };
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+/// Opens the log file with the platform's default viewer.
+pub fn open() -> Result<(), String> {
+    let path = path().ok_or("No log directory")?;
+    if !path.exists() {
+        return Err(format!("No log yet at {}", path.display()));
+    }
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(&path)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl LogFile {
+    fn open(path: PathBuf) -> Result<Self, String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        let size = file.metadata().map_or(0, |m| m.len());
+        Ok(LogFile { path, file, size })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_BYTES {
+            self.rotate();
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+
+    /// Shifts `.log.N` to `.log.N+1`, dropping the oldest, and starts a new
+    /// file. Errors keep appending to the current one.
+    fn rotate(&mut self) {
+        let numbered = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+        for n in (1..KEEP).rev() {
+            let _ = fs::rename(numbered(n), numbered(n + 1));
+        }
+        if fs::rename(&self.path, numbered(1)).is_err() {
+            return;
+        }
+        if let Ok(reopened) = LogFile::open(self.path.clone()) {
+            *self = reopened;
+        }
+    }
+}
+
+struct FileLogger {
+    filter: LevelFilter,
+    file: Mutex<LogFile>,
+    next_span: AtomicU64,
+}
+
+impl Subscriber for FileLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.filter >= *metadata.level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.filter)
+    }
+
+    fn new_span(&self, _span: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        let line = format!(
+            "{} {:<5} {}: {}{}\n",
+            Local::now().format("%Y-%m-%dT%H:%M:%S%.3f%z"),
+            level_name(metadata.level()),
+            metadata.target(),
+            fields.message,
+            fields.rest
+        );
+        self.file.lock().unwrap_or_else(|e| e.into_inner()).write_line(&line);
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+fn level_name(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "ERROR",
+        Level::WARN => "WARN",
+        Level::INFO => "INFO",
+        Level::DEBUG => "DEBUG",
+        Level::TRACE => "TRACE",
+    }
+}
+
+/// An event's message followed by its other fields as ` key=value`.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    rest: String,
+}
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Matrix room announcements, configured under `matrix` in the settings
+//! file:
+//!
+//! ```json
+//! "matrix": {
+//!     "homeserver": "https://matrix.example.org",
+//!     "access_token": "syt_...",
+//!     "room_id": "!abcdef:example.org",
+//!     "on_start": true
+//! }
+//! ```
+//!
+//! Notices are sent to the room as the account the access token belongs
+//! to, which must have joined it: one when a session starts, unless
+//! `on_start` is off, and one when it is saved, with its duration and
+//! headline stats. Each message has its own transaction ID, so a retried
+//! delivery is not posted twice.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::analysis;
+use crate::cli;
+use crate::http;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MatrixConfig {
+    pub homeserver: String,
+    pub access_token: String,
+    /// Room ID, not an alias.
+    pub room_id: String,
+    /// Announce sessions when they start, not only when they end.
+    pub on_start: bool,
+}
+
+impl Default for MatrixConfig {
+    fn default() -> Self {
+        MatrixConfig {
+            homeserver: String::new(),
+            access_token: String::new(),
+            room_id: String::new(),
+            on_start: true,
+        }
+    }
+}
+
+impl MatrixConfig {
+    pub fn session_started(&self, meta: &SessionMeta) -> Result<(), String> {
+        if !self.on_start {
+            return Ok(());
+        }
+        let (text, html) = task(meta);
+        self.send(&format!("Recording started: {}", text), &format!("🔴 Recording started: {}", html))
+    }
+
+    pub fn session_ended(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let stats = format!(
+            "{} tracked · {} key presses · {} clicks · {:.0}% idle",
+            cli::format_duration(summary.tracked_secs()),
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        );
+        let (text, html) = task(meta);
+        self.send(
+            &format!("Recording finished: {}\n{}", text, stats),
+            &format!("✅ Recording finished: {}<br>{}", html, stats),
+        )
+    }
+
+    fn send(&self, text: &str, html: &str) -> Result<(), String> {
+        let transaction = format!(
+            "activity-tracker-{}",
+            SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos()
+        );
+        let url = format!(
+            "{}/_matrix/client/v3/rooms/{}/send/m.room.message/{}",
+            self.homeserver.trim_end_matches('/'),
+            http::encode(&self.room_id),
+            transaction
+        );
+        let body = json!({
+            "msgtype": "m.notice",
+            "body": text,
+            "format": "org.matrix.custom.html",
+            "formatted_body": html,
+        });
+        let authorization = format!("Bearer {}", self.access_token);
+        let headers = [("Authorization", authorization.as_str()), ("Content-Type", "application/json")];
+        outbox::deliver("Matrix", "PUT", &url, &headers, body.to_string().as_bytes())
+    }
+}
+
+/// The task and its project, as plain text and as HTML with the task in bold.
+fn task(meta: &SessionMeta) -> (String, String) {
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    match &meta.project {
+        Some(project) => (
+            format!("{} ({})", meta.task, project),
+            format!("<b>{}</b> ({})", escape(&meta.task), escape(project)),
+        ),
+        None => (meta.task.clone(), format!("<b>{}</b>", escape(&meta.task))),
+    }
+}
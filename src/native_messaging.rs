@@ -0,0 +1,209 @@
+//! Native-messaging host for the companion browser extension, which reports
+//! the active tab so sessions can tell which site the browser time went to.
+//!
+//! `activity-tracker install-native-host <browser> <extension id>` writes the
+//! host manifest where the browser looks for it, along with a launcher that
+//! runs `activity-tracker native-host`. The browser starts that when the
+//! extension connects and talks to it over stdin and stdout, each message
+//! being JSON preceded by its length as a native-endian `u32`. The extension
+//! sends
+//!
+//! ```json
+//! { "type": "tab", "url": "https://example.org/", "title": "Example" }
+//! ```
+//!
+//! whenever the active tab changes, and gets `{ "ok": true }` back, or
+//! `{ "ok": false, "error": "..." }`, e.g. while nothing is being recorded.
+//! Tabs are passed to the running instance over its control endpoint (see
+//! `ipc.rs`).
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::context::BrowserTab;
+use crate::ipc;
+
+/// Name the extension connects to.
+const HOST_NAME: &str = "activity_tracker";
+/// Longest message accepted; tab changes are far smaller.
+const MAX_MESSAGE: u32 = 1 << 20;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Browser {
+    Firefox,
+    Chrome,
+    Chromium,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Message {
+    Tab(BrowserTab),
+}
+
+#[derive(Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Serves the extension until the browser closes stdin.
+pub fn run() -> Result<(), String> {
+    let mut stdin = io::stdin().lock();
+    let mut stdout = io::stdout().lock();
+    while let Some(message) = read_message(&mut stdin)? {
+        let result = serde_json::from_slice::<Message>(&message)
+            .map_err(|e| format!("invalid message: {}", e))
+            .and_then(|message| match message {
+                Message::Tab(tab) => {
+                    let tab = serde_json::to_string(&tab).map_err(|e| e.to_string())?;
+                    ipc::send(&format!("tab {}", tab))
+                }
+            });
+        let response = match result {
+            Ok(_) => Response { ok: true, error: None },
+            Err(error) => Response { ok: false, error: Some(error) },
+        };
+        let response = serde_json::to_vec(&response).map_err(|e| e.to_string())?;
+        // The browser is gone once stdout is closed; stop quietly
+        let written = stdout
+            .write_all(&(response.len() as u32).to_ne_bytes())
+            .and_then(|_| stdout.write_all(&response))
+            .and_then(|_| stdout.flush());
+        if written.is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The next message, or `None` once stdin is closed.
+fn read_message(input: &mut impl Read) -> Result<Option<Vec<u8>>, String> {
+    let mut len = [0u8; 4];
+    match input.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(format!("Failed to read stdin: {}", e)),
+    }
+    let len = u32::from_ne_bytes(len);
+    if len > MAX_MESSAGE {
+        return Err(format!("message of {} bytes is too long", len));
+    }
+    let mut message = vec![0; len as usize];
+    input
+        .read_exact(&mut message)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(Some(message))
+}
+
+/// Registers the host with `browser` for the extension `extension_id`,
+/// returning where the manifest was written.
+pub fn install(browser: Browser, extension_id: &str) -> Result<PathBuf, String> {
+    let dir = dirs::data_local_dir()
+        .map(|dir| dir.join("activity-tracker").join("native-host"))
+        .ok_or("Could not find the local data directory")?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let launcher = write_launcher(&dir)?;
+
+    let mut manifest = json!({
+        "name": HOST_NAME,
+        "description": "activity-tracker browser tab context",
+        "path": launcher,
+        "type": "stdio",
+    });
+    match browser {
+        Browser::Firefox => manifest["allowed_extensions"] = json!([extension_id]),
+        Browser::Chrome | Browser::Chromium => {
+            manifest["allowed_origins"] = json!([format!("chrome-extension://{}/", extension_id)])
+        }
+    }
+    let path = manifest_dir(browser, &dir)?.join(format!("{}.json", HOST_NAME));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let manifest = serde_json::to_string_pretty(&manifest).map_err(|e| e.to_string())?;
+    fs::write(&path, manifest).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    register(browser, &path)?;
+    Ok(path)
+}
+
+/// Browsers start the host with arguments of their own, so it is launched
+/// through a script that puts the subcommand first.
+fn write_launcher(dir: &Path) -> Result<PathBuf, String> {
+    let exe = std::env::current_exe().map_err(|e| format!("Failed to find this executable: {}", e))?;
+    #[cfg(unix)]
+    let (path, script) = (
+        dir.join("activity-tracker-native-host"),
+        format!("#!/bin/sh\nexec '{}' native-host \"$@\"\n", exe.display().to_string().replace('\'', r"'\''")),
+    );
+    #[cfg(windows)]
+    let (path, script) = (
+        dir.join("activity-tracker-native-host.bat"),
+        format!("@echo off\r\n\"{}\" native-host %*\r\n", exe.display()),
+    );
+    fs::write(&path, script).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| format!("Failed to make {} executable: {}", path.display(), e))?;
+    }
+    Ok(path)
+}
+
+/// Where `browser` looks for host manifests. Windows looks them up in the
+/// registry instead, so there they stay next to the launcher.
+// Each platform only needs one of the arguments
+#[allow(unused_variables)]
+fn manifest_dir(browser: Browser, launcher_dir: &Path) -> Result<PathBuf, String> {
+    #[cfg(target_os = "macos")]
+    let dir = dirs::home_dir().map(|home| {
+        let support = home.join("Library").join("Application Support");
+        match browser {
+            Browser::Firefox => support.join("Mozilla"),
+            Browser::Chrome => support.join("Google").join("Chrome"),
+            Browser::Chromium => support.join("Chromium"),
+        }
+        .join("NativeMessagingHosts")
+    });
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let dir = dirs::home_dir().map(|home| match browser {
+        Browser::Firefox => home.join(".mozilla").join("native-messaging-hosts"),
+        Browser::Chrome => home.join(".config").join("google-chrome").join("NativeMessagingHosts"),
+        Browser::Chromium => home.join(".config").join("chromium").join("NativeMessagingHosts"),
+    });
+    #[cfg(windows)]
+    let dir = Some(launcher_dir.to_path_buf());
+    dir.ok_or_else(|| "Could not find the home directory".to_string())
+}
+
+#[cfg(windows)]
+fn register(browser: Browser, manifest: &Path) -> Result<(), String> {
+    let vendor = match browser {
+        Browser::Firefox => r"Mozilla",
+        Browser::Chrome => r"Google\Chrome",
+        Browser::Chromium => r"Chromium",
+    };
+    let key = format!(r"HKCU\Software\{}\NativeMessagingHosts\{}", vendor, HOST_NAME);
+    let status = std::process::Command::new("reg")
+        .args(["add", &key, "/ve", "/t", "REG_SZ", "/d"])
+        .arg(manifest)
+        .arg("/f")
+        .status()
+        .map_err(|e| format!("Failed to run reg: {}", e))?;
+    if !status.success() {
+        return Err(format!("reg could not add {}", key));
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+fn register(_browser: Browser, _manifest: &Path) -> Result<(), String> {
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Key n-gram frequencies for layout research. Only the aggregated counts
+//! leave this module: the press sequence is walked once and dropped, runs
+//! are broken at pauses and gaps so unrelated typing is never joined, and
+//! n-grams rarer than a minimum count are withheld because a one-off
+//! sequence can spell out a password.
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use device_query::Keycode;
+use serde::Serialize;
+
+use crate::recorder::ActivityRecord;
+
+/// Presses further apart than this belong to separate runs.
+const MAX_PAUSE_SECS: u64 = 2;
+
+#[derive(Default)]
+pub struct NgramCounter {
+    n: usize,
+    counts: HashMap<Vec<Keycode>, usize>,
+}
+
+#[derive(Serialize)]
+pub struct NgramCount {
+    pub ngram: String,
+    pub count: usize,
+}
+
+impl NgramCounter {
+    pub fn new(n: usize) -> Self {
+        NgramCounter { n: n.max(1), counts: HashMap::new() }
+    }
+
+    pub fn add_session(&mut self, records: &[ActivityRecord]) {
+        let mut window: Vec<Keycode> = Vec::with_capacity(self.n);
+        let mut last_press: Option<u64> = None;
+        let mut held: &[Keycode] = &[];
+
+        for record in records {
+            if record.gap.is_some() {
+                window.clear();
+            }
+            // Keys going down in the same sample have no known order
+            let mut pressed: Vec<Keycode> = record
+                .keys_pressed
+                .iter()
+                .copied()
+                .filter(|k| !held.contains(k))
+                .collect();
+            pressed.sort_by_key(|k| format!("{:?}", k));
+            held = &record.keys_pressed;
+
+            if pressed.is_empty() {
+                continue;
+            }
+            if last_press.is_some_and(|t| record.timestamp.saturating_sub(t) > MAX_PAUSE_SECS) {
+                window.clear();
+            }
+            last_press = Some(record.timestamp);
+
+            for key in pressed {
+                if window.len() == self.n {
+                    window.remove(0);
+                }
+                window.push(key);
+                if window.len() == self.n {
+                    *self.counts.entry(window.clone()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    /// Counts of at least `min_count`, most frequent first.
+    pub fn table(&self, min_count: usize) -> Vec<NgramCount> {
+        let mut table: Vec<NgramCount> = self
+            .counts
+            .iter()
+            .filter(|(_, &count)| count >= min_count)
+            .map(|(keys, &count)| NgramCount {
+                ngram: keys.iter().map(|k| format!("{:?}", k)).collect::<Vec<_>>().join(" "),
+                count,
+            })
+            .collect();
+        table.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.ngram.cmp(&b.ngram)));
+        table
+    }
+}
+
+pub fn write_csv(table: &[NgramCount], out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "ngram,count")?;
+    for row in table {
+        writeln!(out, "\"{}\",{}", row.ngram, row.count)?;
+    }
+    Ok(())
+}
+
+pub fn write_json(table: &[NgramCount], out: &mut impl Write) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, table)?;
+    writeln!(out)
+}
@@ -0,0 +1,107 @@
+//! Notion database sink, configured under `notion` in the settings file:
+//!
+//! ```json
+//! "notion": {
+//!     "token": "secret_...",
+//!     "database_id": "0123456789abcdef0123456789abcdef",
+//!     "properties": { "project": "Client", "tags": null }
+//! }
+//! ```
+//!
+//! Each saved session becomes a row (a page) in the database. `properties`
+//! names the database column for each field; a column set to `null` is left
+//! out. The database must be shared with the integration the token belongs
+//! to.
+
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::analysis;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+const PAGES_URL: &str = "https://api.notion.com/v1/pages";
+const NOTION_VERSION: &str = "2022-06-28";
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotionConfig {
+    /// Internal integration token.
+    pub token: String,
+    pub database_id: String,
+    pub properties: NotionProperties,
+}
+
+/// Database column for each session field.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotionProperties {
+    /// Title column holding the task name.
+    pub task: String,
+    /// Select column.
+    pub project: Option<String>,
+    /// Multi-select column.
+    pub tags: Option<String>,
+    /// Number column holding the tracked time in minutes.
+    pub duration: Option<String>,
+    /// Date column spanning the session.
+    pub date: Option<String>,
+}
+
+impl Default for NotionProperties {
+    fn default() -> Self {
+        NotionProperties {
+            task: "Name".to_string(),
+            project: Some("Project".to_string()),
+            tags: Some("Tags".to_string()),
+            duration: Some("Duration".to_string()),
+            date: Some("Date".to_string()),
+        }
+    }
+}
+
+impl NotionConfig {
+    pub fn add_row(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        let columns = &self.properties;
+        let mut properties = Map::new();
+        properties.insert(columns.task.clone(), json!({ "title": [{ "text": { "content": meta.task } }] }));
+        if let (Some(column), Some(project)) = (&columns.project, &meta.project) {
+            // Commas aren't allowed in select options
+            properties.insert(column.clone(), json!({ "select": { "name": project.replace(',', " ") } }));
+        }
+        if let Some(column) = &columns.tags {
+            let tags: Vec<Value> = meta.tags.iter().map(|tag| json!({ "name": tag.replace(',', " ") })).collect();
+            properties.insert(column.clone(), json!({ "multi_select": tags }));
+        }
+        if let Some(column) = &columns.duration {
+            let minutes = (summary.tracked_secs() as f64 / 60.0 * 10.0).round() / 10.0;
+            properties.insert(column.clone(), json!({ "number": minutes }));
+        }
+        if let Some(column) = &columns.date {
+            let time = |timestamp: u64| {
+                chrono::DateTime::from_timestamp(timestamp as i64, 0).unwrap_or_default().to_rfc3339()
+            };
+            properties.insert(
+                column.clone(),
+                json!({ "date": { "start": time(summary.start), "end": time(summary.end) } }),
+            );
+        }
+
+        let body = json!({
+            "parent": { "database_id": self.database_id },
+            "properties": properties,
+        });
+        let authorization = format!("Bearer {}", self.token);
+        let headers = [
+            ("Authorization", authorization.as_str()),
+            ("Notion-Version", NOTION_VERSION),
+            ("Content-Type", "application/json"),
+        ];
+        outbox::deliver("Notion", "POST", PAGES_URL, &headers, body.to_string().as_bytes())
+    }
+}
@@ -0,0 +1,174 @@
+//! Sessions as OpenTelemetry traces, configured under `otlp` in the settings
+//! file:
+//!
+//! ```json
+//! "otlp": { "endpoint": "http://localhost:4318", "headers": { "x-api-key": "..." } }
+//! ```
+//!
+//! When a session is saved it is sent over OTLP/HTTP with JSON encoding as
+//! one trace: a root span for the session, a child span per task segment
+//! (the stretches between markers) and one per idle period or gap.
+
+use std::collections::BTreeMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use serde::Deserialize;
+use serde_json::{Value, json};
+
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+/// No input for at least this long becomes an idle span.
+const IDLE_SPAN_SECS: u64 = 60;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// Collector base URL; traces go to `<endpoint>/v1/traces`.
+    pub endpoint: String,
+    /// Extra request headers, e.g. for authentication.
+    pub headers: BTreeMap<String, String>,
+    pub service_name: String,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        OtlpConfig {
+            endpoint: "http://localhost:4318".to_string(),
+            headers: BTreeMap::new(),
+            service_name: "activity-tracker".to_string(),
+        }
+    }
+}
+
+struct Span {
+    id: String,
+    parent: Option<String>,
+    name: String,
+    start: u64,
+    end: u64,
+    attributes: Vec<(&'static str, String)>,
+}
+
+impl OtlpConfig {
+    pub fn export(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            return Ok(());
+        };
+        let trace_id = random_hex(16);
+        let root = Span {
+            id: random_hex(8),
+            parent: None,
+            name: meta.task.clone(),
+            start: first.timestamp,
+            end: last.timestamp,
+            attributes: [
+                meta.project.clone().map(|p| ("activity.project", p)),
+                meta.estimate_secs.map(|e| ("activity.estimate_secs", e.to_string())),
+                Some(("activity.samples", records.len().to_string())),
+            ]
+            .into_iter()
+            .flatten()
+            .collect(),
+        };
+        let mut spans = child_spans(&root, records);
+        spans.insert(0, root);
+
+        let body = json!({
+            "resourceSpans": [{
+                "resource": { "attributes": [attribute("service.name", &self.service_name)] },
+                "scopeSpans": [{
+                    "scope": { "name": "activity-tracker", "version": env!("CARGO_PKG_VERSION") },
+                    "spans": spans.iter().map(|span| span_json(&trace_id, span)).collect::<Vec<_>>(),
+                }],
+            }],
+        });
+
+        let url = format!("{}/v1/traces", self.endpoint.trim_end_matches('/'));
+        let mut headers: Vec<(&str, &str)> = vec![("Content-Type", "application/json")];
+        headers.extend(self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str())));
+        outbox::deliver("OpenTelemetry export", "POST", &url, &headers, body.to_string().as_bytes())
+    }
+}
+
+/// Task segments between markers, idle periods and gaps under `root`.
+fn child_spans(root: &Span, records: &[ActivityRecord]) -> Vec<Span> {
+    let child = |name: String, start: u64, end: u64, kind: &str| Span {
+        id: random_hex(8),
+        parent: Some(root.id.clone()),
+        name,
+        start,
+        end,
+        attributes: vec![("activity.span", kind.to_string())],
+    };
+    let mut spans = Vec::new();
+
+    let mut segment = (root.name.clone(), root.start);
+    for record in records {
+        if let Some(marker) = &record.marker {
+            if record.timestamp > segment.1 {
+                spans.push(child(segment.0, segment.1, record.timestamp, "segment"));
+            }
+            segment = (marker.clone(), record.timestamp);
+        }
+    }
+    if spans.is_empty() || root.end > segment.1 {
+        spans.push(child(segment.0, segment.1, root.end, "segment"));
+    }
+
+    let mut idle_since: Option<u64> = None;
+    let end_idle = |since: Option<u64>, end: u64, spans: &mut Vec<Span>| {
+        if let Some(since) = since.filter(|&since| end.saturating_sub(since) >= IDLE_SPAN_SECS) {
+            spans.push(child("idle".to_string(), since, end, "idle"));
+        }
+    };
+    let mut previous: Option<&ActivityRecord> = None;
+    for record in records {
+        if let Some(gap) = record.gap {
+            end_idle(idle_since.take(), gap.start, &mut spans);
+            spans.push(child(gap.reason.as_str().to_string(), gap.start, record.timestamp, "gap"));
+        }
+        let moved = previous.is_some_and(|p| p.mouse_x != record.mouse_x || p.mouse_y != record.mouse_y);
+        if moved || !record.keys_pressed.is_empty() || !record.mouse_buttons.is_empty() {
+            end_idle(idle_since.take(), record.timestamp, &mut spans);
+        } else if idle_since.is_none() {
+            idle_since = Some(record.timestamp);
+        }
+        previous = Some(record);
+    }
+    end_idle(idle_since, root.end, &mut spans);
+
+    spans
+}
+
+fn span_json(trace_id: &str, span: &Span) -> Value {
+    let nanos = |secs: u64| (secs as u128 * 1_000_000_000).to_string();
+    json!({
+        "traceId": trace_id,
+        "spanId": span.id,
+        "parentSpanId": span.parent.clone().unwrap_or_default(),
+        "name": span.name,
+        "kind": 1,
+        "startTimeUnixNano": nanos(span.start),
+        "endTimeUnixNano": nanos(span.end),
+        "attributes": span.attributes.iter().map(|(k, v)| attribute(k, v)).collect::<Vec<_>>(),
+    })
+}
+
+fn attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+/// Random ids from the standard library's randomly seeded hasher.
+fn random_hex(bytes: usize) -> String {
+    let mut hex = String::with_capacity(bytes * 2);
+    while hex.len() < bytes * 2 {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(hex.len());
+        hex.push_str(&format!("{:016x}", hasher.finish()));
+    }
+    hex.truncate(bytes * 2);
+    hex
+}
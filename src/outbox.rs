@@ -0,0 +1,231 @@
+//! Outbound deliveries that survive network trouble. Integrations hand
+//! their requests to [`deliver`]; one that fails for a reason that may pass
+//! (no connection, a timeout, a 408, 429 or 5xx answer) is written to the
+//! outbox folder and retried in the background with exponential backoff,
+//! across restarts too. After `MAX_ATTEMPTS` it is moved to `failed/` for
+//! inspection. `activity-tracker outbox` lists what is waiting.
+//!
+//! Each delivery is a file of its own; whoever renames it to `.sending`
+//! first gets to send it, so several running instances don't double up.
+//! Requests carry tokens and webhook URLs, so the folder and its files are
+//! only readable by the user.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::http;
+
+/// How often the background worker looks for due deliveries.
+const POLL: Duration = Duration::from_secs(30);
+/// Wait before the first retry, doubled after every failed one up to
+/// `MAX_BACKOFF_SECS`.
+const FIRST_BACKOFF_SECS: u64 = 30;
+const MAX_BACKOFF_SECS: u64 = 3600;
+/// About a day of retrying.
+const MAX_ATTEMPTS: u32 = 30;
+/// A `.sending` file this old was left behind by an instance that died
+/// mid-delivery.
+const STALE_SENDING: Duration = Duration::from_secs(600);
+
+/// A queued request. Bodies are text: JSON, forms, line protocol or iCalendar.
+#[derive(Serialize, Deserialize)]
+pub struct Delivery {
+    /// Integration the request belongs to, e.g. "Slack".
+    pub integration: String,
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// Unix time it was first tried.
+    pub created: u64,
+    pub attempts: u32,
+    pub next_attempt: u64,
+    pub last_error: String,
+}
+
+/// Sends the request now, queueing it for later if that fails for a
+/// reason that may pass. Errors name the integration either way. Blocks
+/// for the first attempt, so call it off the GUI thread and the recorder
+/// lock.
+pub fn deliver(integration: &str, method: &str, url: &str, headers: &[(&str, &str)], body: &[u8]) -> Result<(), String> {
+    let e = match http::send(method, url, headers, body) {
+        Ok(_) => return Ok(()),
+        Err(e) if !e.transient => return Err(format!("{}: {}", integration, e.message)),
+        Err(e) => e,
+    };
+    let now = now();
+    let delivery = Delivery {
+        integration: integration.to_string(),
+        method: method.to_string(),
+        url: url.to_string(),
+        headers: headers.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect(),
+        body: String::from_utf8_lossy(body).into_owned(),
+        created: now,
+        attempts: 1,
+        next_attempt: now + backoff(1),
+        last_error: e.message.clone(),
+    };
+    let name = format!("{}-{}.json", SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos(), std::process::id());
+    let queued = dir().ok_or_else(|| "No data directory".to_string()).and_then(|dir| {
+        create_private_dir(&dir)?;
+        delivery.write(&dir.join(name))
+    });
+    match queued {
+        Ok(()) => Err(format!("{}: {}; will retry", integration, e.message)),
+        Err(queue_error) => Err(format!("{}: {} (could not queue a retry: {})", integration, e.message, queue_error)),
+    }
+}
+
+/// Retries queued deliveries in a background thread for as long as the
+/// app runs.
+pub fn spawn_worker() {
+    thread::spawn(|| loop {
+        let (delivered, _) = retry(false);
+        if delivered > 0 {
+            tracing::info!(delivered, "outbox: delivered queued requests");
+        }
+        thread::sleep(POLL);
+    });
+}
+
+/// Tries the deliveries that are due, or all of them with `force`, and
+/// returns how many went through and how many are still queued.
+pub fn retry(force: bool) -> (usize, usize) {
+    reclaim_stale();
+    let (mut delivered, mut queued) = (0, 0);
+    for path in files("json") {
+        let sending = path.with_extension("sending");
+        // Another instance got there first
+        if fs::rename(&path, &sending).is_err() {
+            continue;
+        }
+        // Renaming keeps the old time, which would make the claim look stale
+        let _ = fs::File::options().write(true).open(&sending).and_then(|file| file.set_modified(SystemTime::now()));
+        let mut delivery = match Delivery::read(&sending) {
+            Ok(delivery) => delivery,
+            Err(e) => {
+                tracing::warn!("outbox: {}", e);
+                let _ = fs::rename(&sending, &path);
+                continue;
+            }
+        };
+        if !force && delivery.next_attempt > now() {
+            let _ = fs::rename(&sending, &path);
+            queued += 1;
+            continue;
+        }
+
+        let headers: Vec<(&str, &str)> = delivery.headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+        let result = http::send(&delivery.method, &delivery.url, &headers, delivery.body.as_bytes());
+        delivery.attempts += 1;
+        match result {
+            Ok(_) => {
+                delivered += 1;
+                let _ = fs::remove_file(&sending);
+            }
+            Err(e) if e.transient && delivery.attempts < MAX_ATTEMPTS => {
+                queued += 1;
+                delivery.next_attempt = now() + backoff(delivery.attempts);
+                delivery.last_error = e.message;
+                if let Err(e) = delivery.write(&path) {
+                    tracing::warn!("outbox: {}", e);
+                }
+                let _ = fs::remove_file(&sending);
+            }
+            Err(e) => {
+                tracing::warn!(integration = %delivery.integration, "outbox: giving up after {} attempts: {}", delivery.attempts, e.message);
+                delivery.last_error = e.message;
+                let failed = path.parent().map(|dir| dir.join("failed")).unwrap_or_default();
+                let moved = create_private_dir(&failed).and_then(|_| delivery.write(&failed.join(path.file_name().unwrap_or_default())));
+                if let Err(e) = moved {
+                    tracing::warn!("outbox: {}", e);
+                }
+                let _ = fs::remove_file(&sending);
+            }
+        }
+    }
+    (delivered, queued)
+}
+
+/// Deliveries waiting to be retried, oldest first.
+pub fn pending() -> Vec<Delivery> {
+    let mut pending: Vec<Delivery> = files("json").iter().chain(&files("sending")).filter_map(|path| Delivery::read(path).ok()).collect();
+    pending.sort_by_key(|d| d.created);
+    pending
+}
+
+impl Delivery {
+    fn read(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("Invalid delivery in {}: {}", path.display(), e))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        options
+            .open(path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<(), String> {
+    let mut builder = fs::DirBuilder::new();
+    builder.recursive(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))
+}
+
+fn dir() -> Option<PathBuf> {
+    Some(dirs::data_local_dir()?.join("activity-tracker").join("outbox"))
+}
+
+/// Files in the outbox folder with `extension`, in the order they were queued.
+fn files(extension: &str) -> Vec<PathBuf> {
+    let Some(Ok(entries)) = dir().map(fs::read_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == extension))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Hands deliveries left `.sending` by an instance that died back to the queue.
+fn reclaim_stale() {
+    for path in files("sending") {
+        let stale = fs::metadata(&path)
+            .and_then(|meta| meta.modified())
+            .is_ok_and(|modified| modified.elapsed().unwrap_or_default() > STALE_SENDING);
+        if stale {
+            let _ = fs::rename(&path, path.with_extension("json"));
+        }
+    }
+}
+
+fn backoff(attempts: u32) -> u64 {
+    FIRST_BACKOFF_SECS.saturating_mul(1 << attempts.saturating_sub(1).min(16)).min(MAX_BACKOFF_SECS)
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
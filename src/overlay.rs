@@ -0,0 +1,161 @@
+//! Cursor overlays: the recorded pointer drawn frame by frame on a
+//! transparent canvas the size of the screen, for compositing over a screen
+//! recording.
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::recorder::ActivityRecord;
+
+/// Samples of trail drawn behind the cursor.
+const TRAIL_SAMPLES: usize = 20;
+const CURSOR_RADIUS: i32 = 8;
+const TRAIL_RADIUS: i32 = 2;
+const CURSOR_COLOR: [u8; 3] = [255, 64, 32];
+const CLICK_COLOR: [u8; 3] = [255, 220, 0];
+
+/// An RGBA image to draw on.
+pub struct Canvas {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Canvas { width, height, pixels: vec![0; width * height * 4] }
+    }
+
+    pub fn clear(&mut self) {
+        self.pixels.fill(0);
+    }
+
+    /// Blends `color` over the pixel at `opacity` from 0 to 1.
+    fn blend(&mut self, x: i32, y: i32, color: [u8; 3], opacity: f64) {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return;
+        }
+        let i = (y as usize * self.width + x as usize) * 4;
+        let src_a = opacity.clamp(0.0, 1.0);
+        let dst_a = self.pixels[i + 3] as f64 / 255.0;
+        let out_a = src_a + dst_a * (1.0 - src_a);
+        if out_a == 0.0 {
+            return;
+        }
+        for (c, &src) in color.iter().enumerate() {
+            let dst = self.pixels[i + c] as f64;
+            self.pixels[i + c] = ((src as f64 * src_a + dst * dst_a * (1.0 - src_a)) / out_a) as u8;
+        }
+        self.pixels[i + 3] = (out_a * 255.0) as u8;
+    }
+
+    pub fn disc(&mut self, cx: i32, cy: i32, radius: i32, color: [u8; 3], opacity: f64) {
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                if x * x + y * y <= radius * radius {
+                    self.blend(cx + x, cy + y, color, opacity);
+                }
+            }
+        }
+    }
+
+    pub fn ring(&mut self, cx: i32, cy: i32, radius: i32, width: i32, color: [u8; 3], opacity: f64) {
+        let inner = (radius - width).max(0);
+        for y in -radius..=radius {
+            for x in -radius..=radius {
+                let d = x * x + y * y;
+                if d <= radius * radius && d > inner * inner {
+                    self.blend(cx + x, cy + y, color, opacity);
+                }
+            }
+        }
+    }
+
+    /// A line of discs from one point to the other.
+    pub fn line(&mut self, from: (i32, i32), to: (i32, i32), radius: i32, color: [u8; 3], opacity: f64) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).max(1);
+        for step in 0..=steps {
+            let x = from.0 + (to.0 - from.0) * step / steps;
+            let y = from.1 + (to.1 - from.1) * step / steps;
+            self.disc(x, y, radius, color, opacity);
+        }
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Fast);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+}
+
+/// The screen size the session covered: up to its rightmost and lowest
+/// pointer positions.
+pub fn screen_size(records: &[ActivityRecord]) -> (usize, usize) {
+    let width = records.iter().map(|r| r.mouse_x).max().unwrap_or(0).max(0) as usize + 1;
+    let height = records.iter().map(|r| r.mouse_y).max().unwrap_or(0).max(0) as usize + 1;
+    (width, height)
+}
+
+/// Draws the cursor at `records[index]` with a fading trail of the samples
+/// before it and a ring while a button is held.
+pub fn draw_cursor(canvas: &mut Canvas, records: &[ActivityRecord], index: usize, scale: f64) {
+    let point = |r: &ActivityRecord| ((r.mouse_x as f64 * scale) as i32, (r.mouse_y as f64 * scale) as i32);
+
+    let first = index.saturating_sub(TRAIL_SAMPLES);
+    // A trail does not reach back across a gap
+    let first = (first + 1..=index).rev().find(|&i| records[i].gap.is_some()).unwrap_or(first);
+    for i in first + 1..=index {
+        let opacity = 0.6 * (i - first) as f64 / (index - first + 1) as f64;
+        canvas.line(point(&records[i - 1]), point(&records[i]), TRAIL_RADIUS, CURSOR_COLOR, opacity);
+    }
+
+    let (x, y) = point(&records[index]);
+    let radius = ((CURSOR_RADIUS as f64 * scale) as i32).max(2);
+    canvas.disc(x, y, radius, CURSOR_COLOR, 0.9);
+    if records[index].mouse_buttons.iter().any(|b| (1..=3).contains(b)) {
+        canvas.ring(x, y, radius * 2, 3, CLICK_COLOR, 1.0);
+    }
+}
+
+/// Writes one numbered PNG per sample, taken `interval` apart, into `dir`,
+/// with empty frames where nothing was captured, so the sequence plays back
+/// in step with a screen recording started together with the session.
+/// Returns the number of frames.
+pub fn write_frames(records: &[ActivityRecord], interval: Duration, size: Option<(u32, u32)>, dir: &Path) -> Result<usize, String> {
+    if records.is_empty() {
+        return Err("Session has no samples".to_string());
+    }
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    let (width, height) = size.map_or_else(|| screen_size(records), |(w, h)| (w as usize, h as usize));
+    let mut canvas = Canvas::new(width, height);
+    let blank = Canvas::new(width, height);
+    let per_sec = (1.0 / interval.as_secs_f64()).round() as u64;
+
+    let mut frame = 0;
+    let mut write = |canvas: &Canvas| {
+        frame += 1;
+        canvas.write_png(&dir.join(format!("frame_{:06}.png", frame)))
+    };
+
+    for (i, record) in records.iter().enumerate() {
+        if let Some(previous) = i.checked_sub(1).map(|j| &records[j]) {
+            for _ in 0..record.timestamp.saturating_sub(previous.timestamp + 1) * per_sec {
+                write(&blank)?;
+            }
+        }
+        canvas.clear();
+        draw_cursor(&mut canvas, records, i, 1.0);
+        write(&canvas)?;
+    }
+
+    Ok(frame)
+}
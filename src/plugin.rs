@@ -0,0 +1,161 @@
+//! Out-of-process plugins, configured under `plugins` in the settings file:
+//!
+//! ```json
+//! { "name": "archive", "kind": "sink", "command": ["jsonl-sink", "/tmp/all.jsonl"] }
+//! ```
+//!
+//! When a session is saved each plugin is spawned with `command` and fed
+//! JSON lines on stdin: `{"event":"start","task":...}`, one
+//! `{"event":"record","record":{...}}` per sample, then `{"event":"end"}`
+//! before stdin is closed. A processor answers every record event with one
+//! line holding the (possibly changed) record, or `null` to drop it; its
+//! output feeds the next processor and finally the saved file. Sinks only
+//! consume, and receive the fully processed session. See
+//! `examples/jsonl_sink.rs`.
+
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::thread;
+
+use serde::{Deserialize, Serialize};
+
+use crate::export::JsonRecord;
+use crate::recorder::ActivityRecord;
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginKind {
+    Sink,
+    Processor,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    pub name: String,
+    pub kind: PluginKind,
+    /// Program followed by its arguments.
+    pub command: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "lowercase")]
+enum Event<'a> {
+    Start { task: &'a str },
+    Record { record: &'a JsonRecord },
+    End,
+}
+
+impl PluginConfig {
+    fn spawn(&self) -> Result<Child, String> {
+        let Some((program, args)) = self.command.split_first() else {
+            return Err(format!("Plugin \"{}\" has an empty command.", self.name));
+        };
+        Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(if self.kind == PluginKind::Processor { Stdio::piped() } else { Stdio::null() })
+            .spawn()
+            .map_err(|e| format!("Failed to start plugin \"{}\": {}", self.name, e))
+    }
+
+    fn wait(&self, mut child: Child) -> Result<(), String> {
+        let status = child
+            .wait()
+            .map_err(|e| format!("Plugin \"{}\" failed: {}", self.name, e))?;
+        if !status.success() {
+            return Err(format!("Plugin \"{}\" exited with {}", self.name, status));
+        }
+        Ok(())
+    }
+}
+
+fn send_session(stdin: ChildStdin, task: &str, records: &[JsonRecord]) -> std::io::Result<()> {
+    let mut stdin = BufWriter::new(stdin);
+    let mut send = |event: &Event| -> std::io::Result<()> {
+        serde_json::to_writer(&mut stdin, event)?;
+        stdin.write_all(b"\n")
+    };
+
+    send(&Event::Start { task })?;
+    for record in records {
+        send(&Event::Record { record })?;
+    }
+    send(&Event::End)?;
+    stdin.flush()
+}
+
+/// Passes the records through every configured processor, in order.
+pub fn run_processors(
+    plugins: &[PluginConfig],
+    task: &str,
+    records: &[ActivityRecord],
+) -> Result<Vec<ActivityRecord>, String> {
+    let mut records: Vec<JsonRecord> = records.iter().map(JsonRecord::from).collect();
+
+    for plugin in plugins.iter().filter(|p| p.kind == PluginKind::Processor) {
+        records = process(plugin, task, &records)?;
+    }
+
+    records.into_iter().map(JsonRecord::into_record).collect()
+}
+
+/// Runs one processor over the records. The plugin is waited for whatever
+/// happens, so none is left behind.
+fn process(plugin: &PluginConfig, task: &str, records: &[JsonRecord]) -> Result<Vec<JsonRecord>, String> {
+    let mut child = plugin.spawn()?;
+    let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+        let _ = child.kill();
+        let _ = plugin.wait(child);
+        return Err(format!("Plugin \"{}\" failed: its input and output are not piped", plugin.name));
+    };
+
+    let (output, sent) = thread::scope(|scope| {
+        // Write from a separate thread so a processor that answers as it
+        // reads cannot deadlock on a full pipe
+        let writer = scope.spawn(move || send_session(stdin, task, records));
+
+        let mut output = Vec::with_capacity(records.len());
+        let read: Result<(), String> = BufReader::new(stdout).lines().try_for_each(|line| {
+            let line = line.map_err(|e| format!("Plugin \"{}\" failed: {}", plugin.name, e))?;
+            let record: Option<JsonRecord> = serde_json::from_str(&line)
+                .map_err(|e| format!("Plugin \"{}\" sent an invalid record: {}", plugin.name, e))?;
+            output.extend(record);
+            Ok(())
+        });
+        // A processor no longer listened to could block the writer for good
+        if read.is_err() {
+            let _ = child.kill();
+        }
+        (read.map(|_| output), writer.join().unwrap_or(Ok(())))
+    });
+
+    let waited = plugin.wait(child);
+    let output = output?;
+    waited?;
+    sent.map_err(|e| format!("Plugin \"{}\" failed: {}", plugin.name, e))?;
+    Ok(output)
+}
+
+/// Hands the finished session to every configured sink. Returns one error
+/// per sink that failed; the others still run.
+pub fn run_sinks(plugins: &[PluginConfig], task: &str, records: &[ActivityRecord]) -> Vec<String> {
+    let records: Vec<JsonRecord> = records.iter().map(JsonRecord::from).collect();
+
+    plugins
+        .iter()
+        .filter(|p| p.kind == PluginKind::Sink)
+        .filter_map(|plugin| {
+            let result = plugin.spawn().and_then(|mut child| {
+                let sent = child
+                    .stdin
+                    .take()
+                    .ok_or_else(|| std::io::Error::other("its input is not piped"))
+                    .and_then(|stdin| send_session(stdin, task, &records));
+                plugin.wait(child)?;
+                sent.map_err(|e| format!("Plugin \"{}\" failed: {}", plugin.name, e))
+            });
+            result.err()
+        })
+        .collect()
+}
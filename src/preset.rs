@@ -0,0 +1,100 @@
+//! Session presets, configured under `presets` in the settings file:
+//!
+//! ```json
+//! "presets": [{
+//!     "name": "usability-test",
+//!     "task": "Usability test {date} #{seq}",
+//!     "project": "acme",
+//!     "tags": ["ux", "lab"],
+//!     "sample_every": 2,
+//!     "privacy": true
+//! }]
+//! ```
+//!
+//! Starting from a preset fills in the task, project and tags and applies
+//! its settings on top of the usual ones for that session only. `task` may
+//! use `{date}`, `{time}` and `{seq}`, the number of sessions started from
+//! the preset today plus one.
+
+use chrono::Local;
+use serde::Deserialize;
+
+use crate::config::{Config, Pattern};
+use crate::filter::FilterRule;
+use crate::history;
+use crate::keys::KeyCategory;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    pub name: String,
+    pub task: String,
+    #[serde(default)]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub estimate_secs: Option<u64>,
+    /// Overrides `sample_every`.
+    #[serde(default)]
+    pub sample_every: Option<u32>,
+    /// Overrides `record_app`.
+    #[serde(default)]
+    pub record_app: Option<bool>,
+    /// Added to the configured filters and excluded apps.
+    #[serde(default)]
+    pub filters: Vec<FilterRule>,
+    #[serde(default)]
+    pub excluded_apps: Vec<Pattern>,
+    /// Store neither the focused app or process nor any sample in which text is typed
+    /// (letters, digits or symbols held).
+    #[serde(default)]
+    pub privacy: bool,
+}
+
+impl Preset {
+    /// The metadata for a new session from this preset.
+    pub fn meta(&self) -> SessionMeta {
+        let now = Local::now();
+        let today = now.format("%Y-%m-%d").to_string();
+        let seq = history::load()
+            .iter()
+            .filter(|r| r.preset.as_deref() == Some(&self.name) && r.date.as_deref() == Some(&today))
+            .count()
+            + 1;
+        SessionMeta {
+            task: self
+                .task
+                .replace("{date}", &today)
+                .replace("{time}", &now.format("%H:%M").to_string())
+                .replace("{seq}", &seq.to_string()),
+            project: self.project.clone(),
+            estimate_secs: self.estimate_secs,
+            tags: self.tags.clone(),
+            preset: Some(self.name.clone()),
+            notes: None,
+            sample_interval_ms: None,
+        }
+    }
+
+    pub fn apply(self, config: &mut Config) {
+        if let Some(every) = self.sample_every {
+            config.sample_every = every;
+        }
+        if let Some(record_app) = self.record_app {
+            config.record_app = record_app;
+        }
+        config.filters.extend(self.filters);
+        config.excluded_apps.extend(self.excluded_apps);
+        if self.privacy {
+            config.record_app = false;
+            config.record_process = false;
+            config.filters.push(FilterRule::keys(vec![
+                KeyCategory::Letters,
+                KeyCategory::Digits,
+                KeyCategory::Symbols,
+            ]));
+        }
+    }
+}
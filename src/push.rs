@@ -0,0 +1,132 @@
+//! Push notifications to a phone through ntfy or Pushover, configured under
+//! `push` in the settings file:
+//!
+//! ```json
+//! "push": { "service": "ntfy", "url": "https://ntfy.sh/my-lab-recordings", "min_minutes": 30 }
+//! ```
+//!
+//! or
+//!
+//! ```json
+//! "push": { "service": "pushover", "token": "a...", "user": "u..." }
+//! ```
+//!
+//! A notification is sent when a session of at least `min_minutes` has been
+//! saved, and whenever saving it or anything run after the save failed, so
+//! unattended recordings that go wrong are noticed. For a protected ntfy
+//! topic, `token` is its access token; for Pushover it is the application
+//! token and `user` the user key.
+
+use std::path::Path;
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::analysis;
+use crate::cli;
+use crate::http;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PushService {
+    #[default]
+    Ntfy,
+    Pushover,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PushConfig {
+    pub service: PushService,
+    /// ntfy topic URL, on ntfy.sh or a self-hosted server.
+    pub url: String,
+    pub token: Option<String>,
+    pub user: Option<String>,
+    /// Shortest session, in tracked minutes, worth a notification when it
+    /// saved without problems.
+    pub min_minutes: u64,
+}
+
+impl PushConfig {
+    /// Reports how saving the session went: `saved` is where it was
+    /// written or why it could not be, `warnings` what failed after that.
+    pub fn notify(
+        &self,
+        meta: &SessionMeta,
+        records: &[ActivityRecord],
+        saved: Result<&Path, String>,
+        warnings: &[String],
+    ) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        let failed = saved.is_err() || !warnings.is_empty();
+        if !failed && (summary.samples == 0 || summary.tracked_secs() < self.min_minutes * 60) {
+            return Ok(());
+        }
+
+        let title = match &saved {
+            Ok(_) if warnings.is_empty() => format!("Finished: {}", meta.task),
+            Ok(_) => format!("Finished with problems: {}", meta.task),
+            Err(_) => format!("Not saved: {}", meta.task),
+        };
+        let mut message = format!(
+            "{} tracked, {} key presses, {} clicks, {:.0}% idle",
+            cli::format_duration(summary.tracked_secs()),
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        );
+        match saved {
+            Ok(path) => message.push_str(&format!("\nSaved to {}", path.display())),
+            Err(e) => message.push_str(&format!("\n{}", e)),
+        }
+        for warning in warnings {
+            message.push_str(&format!("\n{}", warning));
+        }
+
+        match self.service {
+            PushService::Ntfy => self.ntfy(&title, &message, failed),
+            PushService::Pushover => self.pushover(&title, &message, failed),
+        }
+    }
+
+    fn ntfy(&self, title: &str, message: &str, failed: bool) -> Result<(), String> {
+        // Publishing as JSON to the server root keeps non-ASCII titles intact
+        let (server, topic) = self
+            .url
+            .trim_end_matches('/')
+            .rsplit_once('/')
+            .ok_or_else(|| format!("ntfy: {} is not a topic URL", self.url))?;
+        let body = json!({
+            "topic": topic,
+            "title": title,
+            "message": message,
+            "tags": [if failed { "warning" } else { "white_check_mark" }],
+            "priority": if failed { 4 } else { 3 },
+        });
+        let authorization = self.token.as_ref().map(|token| format!("Bearer {}", token));
+        let mut headers = vec![("Content-Type", "application/json")];
+        if let Some(authorization) = &authorization {
+            headers.push(("Authorization", authorization));
+        }
+        outbox::deliver("ntfy", "POST", &format!("{}/", server), &headers, body.to_string().as_bytes())
+    }
+
+    fn pushover(&self, title: &str, message: &str, failed: bool) -> Result<(), String> {
+        let token = self.token.as_deref().ok_or("Pushover: no application token")?;
+        let user = self.user.as_deref().ok_or("Pushover: no user key")?;
+        let body = http::form(&[
+            ("token", token),
+            ("user", user),
+            ("title", title),
+            ("message", message),
+            ("priority", if failed { "1" } else { "0" }),
+        ]);
+        let headers = [("Content-Type", "application/x-www-form-urlencoded")];
+        outbox::deliver("Pushover", "POST", PUSHOVER_URL, &headers, body.as_bytes())
+    }
+}
@@ -0,0 +1,388 @@
+//! Minimal QR code encoder for short texts such as the dashboard link:
+//! byte mode, error correction level M (15% recoverable), versions 1-9,
+//! i.e. up to 180 bytes. Follows ISO/IEC 18004.
+
+/// Largest version supported; it is the last whose length field is 8 bits.
+const MAX_VERSION: usize = 9;
+
+/// Error correction codewords per block and number of blocks, at level M,
+/// indexed by version.
+const ECC_PER_BLOCK: [usize; MAX_VERSION + 1] = [0, 10, 16, 26, 18, 24, 16, 18, 22, 22];
+const BLOCKS: [usize; MAX_VERSION + 1] = [0, 1, 1, 1, 2, 2, 4, 4, 4, 5];
+
+/// Level M's two format bits.
+const LEVEL_M: u32 = 0b00;
+
+pub struct QrCode {
+    size: usize,
+    /// Row-major, `true` for dark modules.
+    modules: Vec<bool>,
+    /// Modules belonging to finder, timing, alignment, format and version
+    /// patterns, which masking leaves alone.
+    function: Vec<bool>,
+}
+
+impl QrCode {
+    /// Encodes `text` in the smallest version it fits in.
+    pub fn encode(text: &str) -> Result<QrCode, String> {
+        let data = text.as_bytes();
+        let version = (1..=MAX_VERSION)
+            .find(|&v| 4 + 8 + data.len() * 8 <= data_codewords(v) * 8)
+            .ok_or_else(|| format!("Too long for a QR code: {} bytes", data.len()))?;
+
+        let mut bits = Bits::default();
+        bits.push(0b0100, 4); // byte mode
+        bits.push(data.len() as u32, 8);
+        for &byte in data {
+            bits.push(byte as u32, 8);
+        }
+        let capacity = data_codewords(version) * 8;
+        bits.push(0, (capacity - bits.0.len()).min(4)); // terminator
+        bits.push(0, (8 - bits.0.len() % 8) % 8);
+        let mut codewords = bits.bytes();
+        for pad in [0xEC, 0x11].into_iter().cycle() {
+            if codewords.len() == capacity / 8 {
+                break;
+            }
+            codewords.push(pad);
+        }
+
+        let mut qr = QrCode {
+            size: version * 4 + 17,
+            modules: vec![false; (version * 4 + 17).pow(2)],
+            function: vec![false; (version * 4 + 17).pow(2)],
+        };
+        qr.draw_function_patterns(version);
+        qr.draw_codewords(&interleave(version, &codewords));
+
+        // Keep the mask that leaves the fewest patterns scanners trip over
+        let mask = (0..8)
+            .min_by_key(|&mask| {
+                qr.apply_mask(mask);
+                qr.draw_format(mask);
+                let penalty = qr.penalty();
+                qr.apply_mask(mask);
+                penalty
+            })
+            .expect("eight masks");
+        qr.apply_mask(mask);
+        qr.draw_format(mask);
+        Ok(qr)
+    }
+
+    /// Modules per side, without a quiet zone.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Whether the module at column `x`, row `y` is dark.
+    pub fn dark(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+
+    /// The code drawn with half-block characters, two rows per line, with
+    /// a quiet zone and explicit colours so it scans on dark terminals too.
+    pub fn to_terminal(&self) -> String {
+        const QUIET: usize = 2;
+        let dark = |x: usize, y: usize| {
+            (QUIET..self.size + QUIET).contains(&x) && (QUIET..self.size + QUIET).contains(&y) && self.dark(x - QUIET, y - QUIET)
+        };
+        let side = self.size + 2 * QUIET;
+        let mut out = String::new();
+        for y in (0..side).step_by(2) {
+            out.push_str("\x1b[30;107m");
+            for x in 0..side {
+                out.push(match (dark(x, y), dark(x, y + 1)) {
+                    (true, true) => '█',
+                    (true, false) => '▀',
+                    (false, true) => '▄',
+                    (false, false) => ' ',
+                });
+            }
+            out.push_str("\x1b[0m\n");
+        }
+        out
+    }
+
+    fn set_function(&mut self, x: usize, y: usize, dark: bool) {
+        self.modules[y * self.size + x] = dark;
+        self.function[y * self.size + x] = true;
+    }
+
+    fn draw_function_patterns(&mut self, version: usize) {
+        let size = self.size;
+        for i in 0..size {
+            self.set_function(6, i, i % 2 == 0);
+            self.set_function(i, 6, i % 2 == 0);
+        }
+        for (cx, cy) in [(3, 3), (size - 4, 3), (3, size - 4)] {
+            for dy in -4..=4_i32 {
+                for dx in -4..=4_i32 {
+                    let (x, y) = (cx as i32 + dx, cy as i32 + dy);
+                    if (0..size as i32).contains(&x) && (0..size as i32).contains(&y) {
+                        let ring = dx.abs().max(dy.abs());
+                        self.set_function(x as usize, y as usize, ring != 2 && ring != 4);
+                    }
+                }
+            }
+        }
+        let centres = alignment_centres(version);
+        let last = centres.len().saturating_sub(1);
+        for (i, &cx) in centres.iter().enumerate() {
+            for (j, &cy) in centres.iter().enumerate() {
+                // The corners are taken by finder patterns
+                if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                    continue;
+                }
+                for dy in -2..=2_i32 {
+                    for dx in -2..=2_i32 {
+                        let (x, y) = ((cx as i32 + dx) as usize, (cy as i32 + dy) as usize);
+                        self.set_function(x, y, dx.abs().max(dy.abs()) != 1);
+                    }
+                }
+            }
+        }
+        // Reserve the format areas; the real bits go in once the mask is known
+        self.draw_format(0);
+        if version >= 7 {
+            let mut remainder = version as u32;
+            for _ in 0..12 {
+                remainder = (remainder << 1) ^ ((remainder >> 11) * 0x1F25);
+            }
+            let bits = (version as u32) << 12 | remainder;
+            for i in 0..18 {
+                let dark = bits >> i & 1 == 1;
+                let (a, b) = (size - 11 + i % 3, i / 3);
+                self.set_function(a, b, dark);
+                self.set_function(b, a, dark);
+            }
+        }
+    }
+
+    fn draw_format(&mut self, mask: u32) {
+        let data = LEVEL_M << 3 | mask;
+        let mut remainder = data;
+        for _ in 0..10 {
+            remainder = (remainder << 1) ^ ((remainder >> 9) * 0x537);
+        }
+        let bits = (data << 10 | remainder) ^ 0x5412;
+        let bit = |i: usize| bits >> i & 1 == 1;
+        let size = self.size;
+
+        for i in 0..=5 {
+            self.set_function(8, i, bit(i));
+        }
+        self.set_function(8, 7, bit(6));
+        self.set_function(8, 8, bit(7));
+        self.set_function(7, 8, bit(8));
+        for i in 9..15 {
+            self.set_function(14 - i, 8, bit(i));
+        }
+        for i in 0..8 {
+            self.set_function(size - 1 - i, 8, bit(i));
+        }
+        for i in 8..15 {
+            self.set_function(8, size - 15 + i, bit(i));
+        }
+        self.set_function(8, size - 8, true);
+    }
+
+    /// Places the codewords in the zigzag of two-module columns, from the
+    /// bottom right.
+    fn draw_codewords(&mut self, codewords: &[u8]) {
+        let size = self.size;
+        let mut i = 0;
+        let mut right = size - 1;
+        loop {
+            if right == 6 {
+                right = 5;
+            }
+            let upward = (right + 1) & 2 == 0;
+            for vertical in 0..size {
+                let y = if upward { size - 1 - vertical } else { vertical };
+                for x in [right, right - 1] {
+                    if !self.function[y * size + x] && i < codewords.len() * 8 {
+                        self.modules[y * size + x] = codewords[i / 8] >> (7 - i % 8) & 1 == 1;
+                        i += 1;
+                    }
+                }
+            }
+            if right < 2 {
+                break;
+            }
+            right -= 2;
+        }
+    }
+
+    /// Flips the data modules selected by `mask`; applying it twice undoes it.
+    fn apply_mask(&mut self, mask: u32) {
+        for y in 0..self.size {
+            for x in 0..self.size {
+                let flip = match mask {
+                    0 => (x + y) % 2 == 0,
+                    1 => y % 2 == 0,
+                    2 => x % 3 == 0,
+                    3 => (x + y) % 3 == 0,
+                    4 => (x / 3 + y / 2) % 2 == 0,
+                    5 => x * y % 2 + x * y % 3 == 0,
+                    6 => (x * y % 2 + x * y % 3) % 2 == 0,
+                    _ => ((x + y) % 2 + x * y % 3) % 2 == 0,
+                };
+                let i = y * self.size + x;
+                if flip && !self.function[i] {
+                    self.modules[i] = !self.modules[i];
+                }
+            }
+        }
+    }
+
+    /// The standard's penalty score for the current modules.
+    fn penalty(&self) -> usize {
+        let size = self.size;
+        let mut penalty = 0;
+        let lines = (0..size).flat_map(|y| [(0..size).map(|x| self.dark(x, y)).collect::<Vec<_>>(), (0..size).map(|x| self.dark(y, x)).collect()]);
+        for line in lines {
+            // Runs of five or more modules of one colour
+            for run in line.chunk_by(|a, b| a == b).map(<[bool]>::len).filter(|&len| len >= 5) {
+                penalty += run - 2;
+            }
+            // Patterns that look like a finder: 1:1:3:1:1 with four light modules to one side
+            const FINDER: [bool; 11] = [true, false, true, true, true, false, true, false, false, false, false];
+            for window in line.windows(11) {
+                if window == FINDER || window.iter().rev().eq(FINDER.iter()) {
+                    penalty += 40;
+                }
+            }
+        }
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                let colour = self.dark(x, y);
+                if self.dark(x + 1, y) == colour && self.dark(x, y + 1) == colour && self.dark(x + 1, y + 1) == colour {
+                    penalty += 3;
+                }
+            }
+        }
+        let total = size * size;
+        let dark = self.modules.iter().filter(|&&m| m).count();
+        let deviation = (dark * 20).abs_diff(total * 10);
+        penalty + deviation.div_ceil(total).saturating_sub(1) * 10
+    }
+}
+
+#[derive(Default)]
+struct Bits(Vec<bool>);
+
+impl Bits {
+    fn push(&mut self, value: u32, count: usize) {
+        for i in (0..count).rev() {
+            self.0.push(value >> i & 1 == 1);
+        }
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.0.chunks(8).map(|byte| byte.iter().fold(0, |acc, &bit| acc << 1 | bit as u8)).collect()
+    }
+}
+
+/// Modules left for data and error correction once the function patterns
+/// are drawn, in whole codewords.
+fn raw_codewords(version: usize) -> usize {
+    let mut modules = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let alignments = version / 7 + 2;
+        modules -= (25 * alignments - 10) * alignments - 55;
+        if version >= 7 {
+            modules -= 36;
+        }
+    }
+    modules / 8
+}
+
+fn data_codewords(version: usize) -> usize {
+    raw_codewords(version) - ECC_PER_BLOCK[version] * BLOCKS[version]
+}
+
+fn alignment_centres(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let step = (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2;
+    let mut centres = vec![6];
+    let mut centre = version * 4 + 17 - 7;
+    for _ in 0..count - 1 {
+        centres.insert(1, centre);
+        centre -= step;
+    }
+    centres
+}
+
+/// Splits the data into blocks, appends each block's error correction and
+/// interleaves them in transmission order.
+fn interleave(version: usize, data: &[u8]) -> Vec<u8> {
+    let blocks = BLOCKS[version];
+    let ecc_len = ECC_PER_BLOCK[version];
+    let raw = raw_codewords(version);
+    // The last `raw % blocks` blocks hold one more data codeword
+    let short_blocks = blocks - raw % blocks;
+    let short_len = raw / blocks - ecc_len;
+    let divisor = reed_solomon_divisor(ecc_len);
+
+    let mut rest = data;
+    let mut data_blocks = Vec::new();
+    let mut ecc_blocks = Vec::new();
+    for i in 0..blocks {
+        let len = short_len + usize::from(i >= short_blocks);
+        let (block, tail) = rest.split_at(len);
+        rest = tail;
+        ecc_blocks.push(reed_solomon_remainder(block, &divisor));
+        data_blocks.push(block);
+    }
+
+    let mut result = Vec::with_capacity(raw);
+    for i in 0..=short_len {
+        result.extend(data_blocks.iter().filter_map(|block| block.get(i)));
+    }
+    for i in 0..ecc_len {
+        result.extend(ecc_blocks.iter().map(|block| block[i]));
+    }
+    result
+}
+
+fn reed_solomon_divisor(degree: usize) -> Vec<u8> {
+    let mut result = vec![0; degree];
+    result[degree - 1] = 1;
+    let mut root = 1;
+    for _ in 0..degree {
+        for j in 0..degree {
+            result[j] = gf_multiply(result[j], root);
+            if j + 1 < degree {
+                result[j] ^= result[j + 1];
+            }
+        }
+        root = gf_multiply(root, 0x02);
+    }
+    result
+}
+
+fn reed_solomon_remainder(data: &[u8], divisor: &[u8]) -> Vec<u8> {
+    let mut result = vec![0; divisor.len()];
+    for &byte in data {
+        let factor = byte ^ result.remove(0);
+        result.push(0);
+        for (r, &d) in result.iter_mut().zip(divisor) {
+            *r ^= gf_multiply(d, factor);
+        }
+    }
+    result
+}
+
+/// Product in GF(2^8) modulo the QR polynomial x^8 + x^4 + x^3 + x^2 + 1.
+fn gf_multiply(x: u8, y: u8) -> u8 {
+    let mut z: u32 = 0;
+    for i in (0..8).rev() {
+        z = (z << 1) ^ ((z >> 7) * 0x11D);
+        z ^= (y as u32 >> i & 1) * x as u32;
+    }
+    z as u8
+}
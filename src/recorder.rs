@@ -0,0 +1,921 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use active_win_pos_rs::ActiveWindow;
+use device_query::Keycode;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::{DeviceInput, Devices, Input};
+use crate::config::Config;
+use crate::context::{self, BrowserTab, EditorFile, ProcessInfo, SystemContext};
+use crate::diagnostics::{Diagnostics, Health};
+use crate::dnd::DoNotDisturb;
+use crate::export::JsonRecord;
+use crate::lock::LockMonitor;
+use crate::scripting::Scripts;
+use crate::i18n::tr;
+use crate::sound::{self, Cue};
+use crate::storage::{SessionMeta, StorageError};
+use crate::{analysis, editor, ergonomics, fatigue, filter, history, plugin, speech, storage, suggest, tagging, window};
+
+/// Seconds between starting a task and the first recorded sample.
+pub const COUNTDOWN_SECS: u64 = 5;
+
+/// Task name for sessions started without one. Once recording has run for
+/// `SUGGEST_AFTER`, a name is suggested from the windows in focus so far.
+pub const UNTITLED_TASK: &str = "Untitled";
+const SUGGEST_AFTER: Duration = Duration::from_secs(180);
+
+/// Time between two captured samples.
+pub const SAMPLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How often the last hour's load is checked against the strain limits, and
+/// the least time between two reminders.
+const STRAIN_CHECK: Duration = Duration::from_secs(60);
+const STRAIN_REMINDER: Duration = Duration::from_secs(3600);
+
+/// A wall-clock jump between two capture ticks longer than this means the
+/// machine was suspended in between.
+pub const SLEEP_GAP: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct ActivityRecord {
+    pub timestamp: u64,
+    pub mouse_x: i32,
+    pub mouse_y: i32,
+    pub keys_pressed: Vec<Keycode>,
+    /// Mouse buttons held, numbered from 1 (left); on Linux 4 and 5 are
+    /// the scroll wheel.
+    pub mouse_buttons: Vec<u8>,
+    /// Name of the focused application, when `record_app` is on.
+    pub app: Option<String>,
+    pub marker: Option<String>,
+    /// Set on the first sample after a stretch where nothing was captured;
+    /// the gap runs from `gap.start` to this sample's timestamp.
+    pub gap: Option<Gap>,
+    pub devices: DeviceInput,
+    pub context: SystemContext,
+    /// Pointer positions polled since the previous sample, oldest first,
+    /// as milliseconds since the epoch and coordinates. The sample's own
+    /// position is `mouse_x`/`mouse_y`.
+    pub path: Vec<(u64, i32, i32)>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Gap {
+    pub reason: GapReason,
+    pub start: u64,
+}
+
+/// Why a session has no samples for a while, as written to exports.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GapReason {
+    Sleep,
+    Lock,
+    Pause,
+    /// An app listed in `excluded_apps` had focus.
+    Excluded,
+    /// Recording was stopped and started again; see `merge`.
+    Restart,
+}
+
+impl GapReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GapReason::Sleep => "sleep",
+            GapReason::Lock => "lock",
+            GapReason::Pause => "pause",
+            GapReason::Excluded => "excluded",
+            GapReason::Restart => "restart",
+        }
+    }
+}
+
+impl FromStr for GapReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sleep" => Ok(GapReason::Sleep),
+            "lock" => Ok(GapReason::Lock),
+            "pause" => Ok(GapReason::Pause),
+            "excluded" => Ok(GapReason::Excluded),
+            "restart" => Ok(GapReason::Restart),
+            other => Err(format!("unknown gap reason {}", other)),
+        }
+    }
+}
+
+/// Where the recorder currently is in its lifecycle.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    Idle,
+    Countdown(u64),
+    Recording,
+    Paused(PauseReason),
+}
+
+/// Why capture is suspended in the middle of a session.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    Sleep,
+    ScreenLock,
+    PrivateWindow,
+    ExcludedApp,
+}
+
+impl PauseReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            PauseReason::Sleep => "system sleep",
+            PauseReason::ScreenLock => "screen locked",
+            PauseReason::PrivateWindow => "private window",
+            PauseReason::ExcludedApp => "excluded app",
+        }
+    }
+
+    pub fn gap_reason(self) -> GapReason {
+        match self {
+            PauseReason::Sleep => GapReason::Sleep,
+            PauseReason::ScreenLock => GapReason::Lock,
+            PauseReason::PrivateWindow => GapReason::Pause,
+            PauseReason::ExcludedApp => GapReason::Excluded,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct SaveError {
+    pub message: String,
+    /// No output folder could be found; the user has to pick one.
+    pub needs_folder: bool,
+}
+
+/// A stopped session as it was saved, for the sinks and services told
+/// about it afterwards.
+struct SavedSession {
+    meta: SessionMeta,
+    records: Vec<ActivityRecord>,
+    saved: Result<PathBuf, String>,
+    config: Arc<Config>,
+    scripts: Arc<Scripts>,
+    /// Raised while saving, for the push notification.
+    warnings: Vec<String>,
+}
+
+impl SavedSession {
+    /// Hands the session to every configured sink and service, returning
+    /// their errors.
+    fn announce(self) -> Vec<String> {
+        let SavedSession { meta, records, saved, config, scripts, mut warnings } = self;
+        let records = &records;
+        let saving = warnings.len();
+        if let Ok(path) = &saved {
+            warnings.extend(plugin::run_sinks(&config.plugins, &meta.task, records));
+            let results = [
+                config.influxdb.as_ref().map(|influx| influx.write(&meta, records)),
+                config.otlp.as_ref().map(|otlp| otlp.export(&meta, records)),
+                config.google_calendar.as_ref().map(|google| google.create_event(&meta, records)),
+                config.caldav.as_ref().map(|caldav| caldav.upload(&meta, records)),
+                config.daily_note.as_ref().map(|daily_note| daily_note.append(&meta, records)),
+                config.notion.as_ref().map(|notion| notion.add_row(&meta, records)),
+                config.airtable.as_ref().map(|airtable| airtable.add_record(&meta, records)),
+                config.email.as_ref().map(|email| email.send_report(&meta, records, path)),
+                config.slack.as_ref().map(|slack| slack.session_ended(&meta, records)),
+                config.discord.as_ref().map(|discord| discord.session_ended(&meta, records)),
+                config.matrix.as_ref().map(|matrix| matrix.session_ended(&meta, records)),
+            ];
+            warnings.extend(results.into_iter().flatten().filter_map(Result::err));
+            if let Err(e) = scripts.on_stop(&meta.task, path) {
+                warnings.push(format!("Script error: {}", e));
+            }
+        }
+        if let Some(push) = &config.push {
+            if let Err(e) = push.notify(&meta, records, saved.as_deref().map_err(String::clone), &warnings) {
+                warnings.push(e);
+            }
+        }
+        let errors = warnings.split_off(saving);
+        for e in &errors {
+            tracing::warn!("{}", e);
+        }
+        errors
+    }
+}
+
+#[derive(Default)]
+struct State {
+    meta: SessionMeta,
+    start_time: Option<Instant>,
+    records: Vec<ActivityRecord>,
+    pending_marker: Option<String>,
+    /// The browser's active tab, when it changed since the last sample.
+    pending_tab: Option<BrowserTab>,
+    /// The file an editor last reported, and when; kept across sessions.
+    editor: Option<(EditorFile, Instant)>,
+    pending_gap: Option<Gap>,
+    paused: Option<PauseReason>,
+    stop_flag: Option<Arc<AtomicBool>>,
+    status: String,
+    config: Arc<Config>,
+    scripts: Arc<Scripts>,
+    health: Health,
+    /// Set while a stopped session's records are with the processors.
+    processing: bool,
+    /// Set while a stopped session failed to save and can be retried.
+    save_error: Option<SaveError>,
+    /// Switched on for this session; restored when capture stops.
+    dnd: Option<DoNotDisturb>,
+    /// Samples each `(app, title)` had focus for, while an untitled
+    /// session's name is being worked out.
+    focus: HashMap<(String, String), u32>,
+    /// Where the last session was saved.
+    last_saved: Option<PathBuf>,
+    /// Threads still handing saved sessions to integrations.
+    announcing: Vec<thread::JoinHandle<()>>,
+}
+
+/// Shared handle to the recording session, used by the GUI and every
+/// external controller (IPC, signals, ...).
+#[derive(Clone, Default)]
+pub struct Recorder {
+    state: Arc<Mutex<State>>,
+}
+
+impl Recorder {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // A panic in the capture thread must not take the UI down with it
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn phase(&self) -> Phase {
+        let state = self.lock();
+        match state.start_time {
+            None => Phase::Idle,
+            Some(start_time) => {
+                let elapsed = start_time.elapsed().as_secs();
+                if elapsed < COUNTDOWN_SECS {
+                    Phase::Countdown(COUNTDOWN_SECS - elapsed)
+                } else if let Some(reason) = state.paused {
+                    Phase::Paused(reason)
+                } else {
+                    Phase::Recording
+                }
+            }
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.phase() != Phase::Idle
+    }
+
+    pub fn task_name(&self) -> String {
+        self.lock().meta.task.clone()
+    }
+
+    pub fn meta(&self) -> SessionMeta {
+        self.lock().meta.clone()
+    }
+
+    /// Time spent recording so far, excluding the countdown.
+    pub fn elapsed(&self) -> Duration {
+        self.lock().start_time.map_or(Duration::ZERO, |start_time| {
+            start_time.elapsed().saturating_sub(Duration::from_secs(COUNTDOWN_SECS))
+        })
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.lock().records.len()
+    }
+
+    /// Samples with input in each `bucket` of the last `window`, oldest
+    /// first; see [`analysis::activity_since`].
+    pub fn recent_activity(&self, window: Duration, bucket: Duration) -> Vec<usize> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let bucket_secs = bucket.as_secs().max(1);
+        let buckets = (window.as_secs() / bucket_secs) as usize;
+        // The current, partial bucket is the last one
+        let since = (now + 1).saturating_sub(buckets as u64 * bucket_secs);
+        analysis::activity_since(&self.lock().records, since, bucket_secs, buckets)
+    }
+
+    /// Headline statistics of what has been recorded so far.
+    pub fn summary(&self) -> analysis::Summary {
+        analysis::summarize(&self.lock().records)
+    }
+
+    /// The last `count` samples, oldest first.
+    pub fn recent_samples(&self, count: usize) -> Vec<JsonRecord> {
+        let state = self.lock();
+        let skip = state.records.len().saturating_sub(count);
+        state.records[skip..].iter().map(JsonRecord::from).collect()
+    }
+
+    /// A name for an untitled session, once enough of it has been recorded.
+    pub fn suggested_task(&self) -> Option<String> {
+        let state = self.lock();
+        let recording = state.start_time?.elapsed().saturating_sub(Duration::from_secs(COUNTDOWN_SECS));
+        if state.meta.task != UNTITLED_TASK || recording < SUGGEST_AFTER {
+            return None;
+        }
+        suggest::task_name(&state.focus)
+    }
+
+    /// Stops suggesting a name for this session.
+    pub fn dismiss_suggestion(&self) {
+        self.lock().focus.clear();
+    }
+
+    /// Renames the running session, e.g. to accept a suggested name.
+    pub fn rename(&self, task: &str) -> Result<(), String> {
+        storage::validate_task_name(task)?;
+        let mut state = self.lock();
+        if state.start_time.is_none() {
+            return Err("Not recording.".to_string());
+        }
+        state.meta.task = task.to_string();
+        state.focus.clear();
+        tracing::info!(task, "session renamed");
+        if let Err(e) = history::remember(&state.meta) {
+            tracing::warn!("could not remember task name: {}", e);
+        }
+        Ok(())
+    }
+
+    /// Approximate size of the session file if it were saved now.
+    pub fn estimated_size(&self) -> u64 {
+        storage::estimate_csv_size(&self.lock().records)
+    }
+
+    pub fn diagnostics(&self) -> Diagnostics {
+        let state = self.lock();
+        state.health.snapshot(state.records.len())
+    }
+
+    pub fn status(&self) -> String {
+        self.lock().status.clone()
+    }
+
+    pub fn set_status(&self, status: impl Into<String>) {
+        self.lock().status = status.into();
+    }
+
+    /// Begins the countdown and spawns the capture thread.
+    pub fn start(&self, mut meta: SessionMeta) -> Result<(), String> {
+        storage::validate_task_name(&meta.task)?;
+        let mut config = Config::load()?;
+        if let Some(preset) = &meta.preset {
+            config.apply_preset(preset)?;
+        }
+        meta.sample_interval_ms = Some(config.sample_interval().as_millis() as u64);
+        let config = Arc::new(config);
+        let scripts = Arc::new(Scripts::load(&config.scripts)?);
+
+        let mut state = self.lock();
+        if state.start_time.is_some() {
+            return Err(format!("Already recording task \"{}\".", state.meta.task));
+        }
+        if state.processing {
+            return Err("Still saving the last session.".to_string());
+        }
+
+        let stop_flag = Arc::new(AtomicBool::new(false));
+        state.start_time = Some(Instant::now());
+        state.records = Vec::new();
+        state.pending_marker = None;
+        state.pending_tab = None;
+        state.pending_gap = None;
+        state.paused = None;
+        state.save_error = None;
+        state.focus.clear();
+        state.health.reset();
+        state.stop_flag = Some(Arc::clone(&stop_flag));
+        state.config = Arc::clone(&config);
+        state.scripts = Arc::clone(&scripts);
+        state.status = format!("Preparing to record ({} second countdown)...", COUNTDOWN_SECS);
+        if let Err(e) = scripts.on_start(&meta.task) {
+            tracing::warn!("script error in on_start: {}", e);
+            state.status.push_str(&format!("\nScript error: {}", e));
+        }
+        if config.do_not_disturb {
+            match DoNotDisturb::enable() {
+                Ok(dnd) => state.dnd = Some(dnd),
+                Err(e) => {
+                    tracing::warn!("could not enable do not disturb: {}", e);
+                    state.status.push_str(&format!("\nDo Not Disturb: {}", e));
+                }
+            }
+        }
+        tracing::info!(task = %meta.task, "session started");
+        if config.sounds {
+            sound::play(Cue::Start);
+        }
+        if meta.task != UNTITLED_TASK {
+            if let Err(e) = history::remember(&meta) {
+                tracing::warn!("could not remember task name: {}", e);
+            }
+        }
+        self.announce_start(&config, &meta);
+        state.meta = meta;
+        drop(state);
+
+        let recorder = self.clone();
+        thread::spawn(move || recorder.capture_loop(&stop_flag, &config, &scripts));
+
+        Ok(())
+    }
+
+    /// Tells chat integrations that a session started, on a thread of its
+    /// own so a slow network does not hold up the countdown.
+    fn announce_start(&self, config: &Arc<Config>, meta: &SessionMeta) {
+        if config.slack.is_none() && config.discord.is_none() && config.matrix.is_none() {
+            return;
+        }
+        let (recorder, config, meta) = (self.clone(), Arc::clone(config), meta.clone());
+        thread::spawn(move || {
+            let results = [
+                config.slack.as_ref().map(|slack| slack.session_started(&meta)),
+                config.discord.as_ref().map(|discord| discord.session_started(&meta)),
+                config.matrix.as_ref().map(|matrix| matrix.session_started(&meta)),
+            ];
+            for e in results.into_iter().flatten().filter_map(Result::err) {
+                tracing::warn!("{}", e);
+                recorder.lock().status.push_str(&format!("\n{}", e));
+            }
+        });
+    }
+
+    /// Stops capture, saves the session and returns the written file.
+    pub fn stop(&self) -> Result<PathBuf, String> {
+        let state = self.lock();
+        let Some(start_time) = state.start_time else {
+            return Err("Not recording.".to_string());
+        };
+        if start_time.elapsed().as_secs() < COUNTDOWN_SECS {
+            return Err("Please wait for timer to complete.".to_string());
+        }
+        let (result, saved) = self.finish(state);
+        self.announce_saved(saved);
+        result
+    }
+
+    /// Abandons a session that is still counting down; nothing is saved.
+    pub fn cancel(&self) -> Result<(), String> {
+        let mut state = self.lock();
+        let Some(start_time) = state.start_time else {
+            return Err("Not recording.".to_string());
+        };
+        if start_time.elapsed().as_secs() >= COUNTDOWN_SECS {
+            return Err("Recording already started; end the task instead.".to_string());
+        }
+        Self::halt_capture(&mut state);
+        state.records = Vec::new();
+        state.status = "Countdown cancelled.".to_string();
+        tracing::info!(task = %state.meta.task, "session cancelled");
+        Ok(())
+    }
+
+    /// Ends whatever session is in progress, saving any captured data, even
+    /// mid-countdown. Used when the app is being shut down.
+    pub fn finalize(&self) -> Option<Result<PathBuf, String>> {
+        let mut state = self.lock();
+        // A session being stopped is saved once its processors are done
+        while state.processing {
+            drop(state);
+            thread::sleep(Duration::from_millis(50));
+            state = self.lock();
+        }
+        state.start_time?;
+        if state.records.is_empty() {
+            Self::halt_capture(&mut state);
+            return None;
+        }
+        let (result, saved) = self.finish(state);
+        // The app is about to exit, which would cut a background thread short
+        saved.announce();
+        Some(result)
+    }
+
+    fn halt_capture(state: &mut State) {
+        if let Some(stop_flag) = state.stop_flag.take() {
+            stop_flag.store(true, Ordering::SeqCst);
+        }
+        state.start_time = None;
+        if let Some(dnd) = state.dnd.take() {
+            if let Err(e) = dnd.restore() {
+                tracing::warn!("could not restore notifications: {}", e);
+            }
+        }
+    }
+
+    /// Stops capture and saves the session. Processors run with the lock
+    /// released, since they may take a while.
+    fn finish(&self, mut state: MutexGuard<'_, State>) -> (Result<PathBuf, String>, SavedSession) {
+        Self::halt_capture(&mut state);
+        if state.config.sounds {
+            sound::play(Cue::Stop);
+        }
+        if state.config.speak {
+            speech::say(tr!("speech-stopped", task = state.meta.task));
+        }
+
+        let records = std::mem::take(&mut state.records);
+        let config = Arc::clone(&state.config);
+        let task = state.meta.task.clone();
+        state.processing = true;
+        drop(state);
+
+        let mut warnings = Vec::new();
+        // A broken processor must not cost the user their session
+        let records = match plugin::run_processors(&config.plugins, &task, &records) {
+            Ok(processed) => processed,
+            Err(e) => {
+                warnings.push(format!("{}; saved unprocessed data.", e));
+                records
+            }
+        };
+
+        let mut state = self.lock();
+        state.processing = false;
+        state.records = records;
+        for tag in tagging::tags(&state.config.tag_rules, &state.config.categories, &state.records) {
+            if !state.meta.tags.contains(&tag) {
+                state.meta.tags.push(tag);
+            }
+        }
+        Self::save(&mut state, None, warnings)
+    }
+
+    /// Writes the stopped session's records; on failure they are kept for
+    /// [`Self::retry_save`]. Everything that follows a save is returned, to
+    /// be run once the lock is released.
+    fn save(state: &mut State, dir: Option<&Path>, mut warnings: Vec<String>) -> (Result<PathBuf, String>, SavedSession) {
+        let write_start = Instant::now();
+        let result = storage::save_csv(dir, &state.meta, &state.config.filename_template, &state.records);
+        state.health.set_write_latency(write_start.elapsed());
+        match &result {
+            Ok(path) => {
+                if let Err(e) = storage::save_meta(path, &state.meta) {
+                    warnings.push(e.to_string());
+                }
+                tracing::info!(samples = state.records.len(), "session saved to {}", path.display());
+                state.last_saved = Some(path.clone());
+            }
+            Err(e) => tracing::error!("saving session failed: {}", e),
+        }
+        for warning in &warnings {
+            tracing::warn!("{}", warning);
+        }
+        state.save_error = match &result {
+            Err(e) if e.is_retryable() => Some(SaveError {
+                message: e.to_string(),
+                needs_folder: matches!(e, StorageError::NoOutputDir),
+            }),
+            _ => None,
+        };
+        state.status = match &result {
+            Ok(path) => {
+                let mut status_msg = format!("Activity data saved to {}", path.display());
+                if cfg!(target_os = "macos") {
+                    status_msg.push_str("\nNote: On macOS, you may need to look in ~/Downloads");
+                }
+                status_msg
+            }
+            Err(e) => e.to_string(),
+        };
+        for warning in &warnings {
+            state.status.push('\n');
+            state.status.push_str(warning);
+        }
+
+        let result = result.map_err(String::from);
+        let saved = SavedSession {
+            meta: state.meta.clone(),
+            records: state.records.clone(),
+            saved: result.clone(),
+            config: Arc::clone(&state.config),
+            scripts: Arc::clone(&state.scripts),
+            warnings,
+        };
+        (result, saved)
+    }
+
+    /// Runs what follows a save on a thread of its own, so slow networks
+    /// hold up neither the caller nor anything waiting on the lock.
+    fn announce_saved(&self, saved: SavedSession) {
+        let recorder = self.clone();
+        let handle = thread::spawn(move || {
+            for e in saved.announce() {
+                recorder.lock().status.push_str(&format!("\n{}", e));
+            }
+        });
+        let mut state = self.lock();
+        state.announcing.retain(|handle| !handle.is_finished());
+        state.announcing.push(handle);
+    }
+
+    /// Waits until saved sessions have been handed to every integration,
+    /// before the app exits.
+    pub fn wait_for_integrations(&self) {
+        let announcing = std::mem::take(&mut self.lock().announcing);
+        for handle in announcing {
+            let _ = handle.join();
+        }
+    }
+
+    /// The file the last session was saved to.
+    pub fn last_saved(&self) -> Option<PathBuf> {
+        self.lock().last_saved.clone()
+    }
+
+    /// Why the last session could not be saved, while its data is still
+    /// held for another attempt.
+    pub fn save_error(&self) -> Option<SaveError> {
+        self.lock().save_error.clone()
+    }
+
+    /// Tries saving the unsaved session again, into `dir` if given.
+    pub fn retry_save(&self, dir: Option<&Path>) -> Result<PathBuf, String> {
+        let mut state = self.lock();
+        if state.save_error.is_none() || state.start_time.is_some() {
+            return Err("No unsaved session.".to_string());
+        }
+        let (result, saved) = Self::save(&mut state, dir, Vec::new());
+        drop(state);
+        self.announce_saved(saved);
+        result
+    }
+
+    /// Gives up on the unsaved session.
+    pub fn discard_unsaved(&self) {
+        let mut state = self.lock();
+        if state.save_error.take().is_some() {
+            state.records.clear();
+            state.status = "Unsaved session discarded.".to_string();
+        }
+    }
+
+    /// Attaches a label to the next captured sample.
+    pub fn mark(&self, label: &str) -> Result<(), String> {
+        let mut state = self.lock();
+        if state.start_time.is_none() {
+            return Err("Not recording.".to_string());
+        }
+        state.pending_marker = Some(label.to_string());
+        Ok(())
+    }
+
+    /// Notes the browser's newly active tab for the next captured sample.
+    pub fn set_tab(&self, tab: BrowserTab) -> Result<(), String> {
+        let mut state = self.lock();
+        if state.start_time.is_none() {
+            return Err("Not recording.".to_string());
+        }
+        state.pending_tab = Some(tab);
+        Ok(())
+    }
+
+    /// Notes the file being worked on in an editor, or `None` when none is.
+    pub fn set_editor(&self, file: Option<EditorFile>) {
+        self.lock().editor = file.map(|file| (file, Instant::now()));
+    }
+
+    fn capture_loop(&self, stop_flag: &AtomicBool, config: &Config, scripts: &Scripts) {
+        // Wait for the countdown before starting to record, but stay
+        // responsive to a stop request
+        let countdown_end = Instant::now() + Duration::from_secs(COUNTDOWN_SECS);
+        while Instant::now() < countdown_end {
+            if stop_flag.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+        if config.sounds {
+            sound::play(Cue::Recording);
+        }
+        if config.speak {
+            speech::say(tr!("speech-started", task = self.task_name()));
+        }
+
+        let devices = match Devices::spawn(config) {
+            Ok(devices) => devices,
+            Err(e) => {
+                tracing::error!("{}", e);
+                // Back to idle, as after a cancelled countdown
+                let mut state = self.lock();
+                if !stop_flag.load(Ordering::SeqCst) {
+                    Self::halt_capture(&mut state);
+                    state.records = Vec::new();
+                }
+                state.status = e;
+                return;
+            }
+        };
+        let mut system = context::Sampler::new(config);
+        let untitled = self.task_name() == UNTITLED_TASK;
+        let needs_window = config.needs_window() || untitled;
+        let recording_start = Instant::now();
+        let mut paused_since: Option<SystemTime> = None;
+        let mut lock_monitor = LockMonitor::default();
+        let mut last_tick = SystemTime::now();
+        let fatigue_every = Duration::from_secs(config.fatigue.window_secs());
+        let mut next_fatigue_check = Instant::now() + fatigue_every;
+        let mut next_strain_check = Instant::now() + STRAIN_CHECK;
+        let mut tick: u64 = 0;
+
+        while !stop_flag.load(Ordering::SeqCst) {
+            // Monotonic clocks stop during suspend on most platforms, so
+            // compare wall-clock time between ticks instead
+            let now = SystemTime::now();
+            let tick_start = Instant::now();
+            let slept = now.duration_since(last_tick).ok().filter(|gap| *gap >= SLEEP_GAP);
+            self.lock().health.tick(tick_start, slept.is_some());
+            if let Some(gap) = slept {
+                tracing::info!("resumed after {}s without a capture tick; assuming sleep", gap.as_secs());
+                self.add_gap(PauseReason::Sleep, last_tick);
+            }
+            last_tick = now;
+
+            let window = if needs_window { window::foreground() } else { None };
+            // A locked screen would otherwise record a frozen cursor
+            let locked = config.pause_on_lock && lock_monitor.is_locked(window.as_ref());
+            let pause = if locked {
+                Some(PauseReason::ScreenLock)
+            } else {
+                window.as_ref().and_then(|w| config.pause_reason(w))
+            };
+            self.set_paused(pause, &mut paused_since);
+            if let Some(window) = window.as_ref().filter(|_| untitled && recording_start.elapsed() < SUGGEST_AFTER) {
+                *self.lock().focus.entry((window.app_name.clone(), window.title.clone())).or_default() += 1;
+            }
+
+            tick += 1;
+            let due = tick.is_multiple_of(u64::from(config.sample_every.max(1)));
+            if pause.is_some() {
+                // Nothing seen while paused may reach the next sample
+                devices.take();
+            } else if due {
+                // Input from the ticks skipped since the last sample is
+                // merged into this one
+                let input = devices.take();
+                let context = system.sample();
+                self.capture_sample(input, context, window.as_ref(), stop_flag, config, scripts);
+            }
+
+            if config.fatigue.alerts && Instant::now() >= next_fatigue_check {
+                next_fatigue_check = Instant::now() + fatigue_every;
+                self.check_fatigue(config);
+            }
+            if config.ergonomics.alerts && Instant::now() >= next_strain_check {
+                next_strain_check = Instant::now() + STRAIN_CHECK;
+                if self.check_strain(config) {
+                    next_strain_check = Instant::now() + STRAIN_REMINDER;
+                }
+            }
+
+            self.lock().health.set_tick_work(tick_start.elapsed());
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    }
+
+    /// Returns whether a reminder was shown.
+    fn check_strain(&self, config: &Config) -> bool {
+        let mut state = self.lock();
+        let Some(reminder) = ergonomics::recent_strain(&state.records, &config.ergonomics) else {
+            return false;
+        };
+        tracing::debug!("{}", reminder);
+        state.status = reminder;
+        true
+    }
+
+    fn check_fatigue(&self, config: &Config) {
+        let mut state = self.lock();
+        let windows = fatigue::windows(&state.records, config.fatigue.window_secs());
+        let warnings = fatigue::warnings(&windows, &config.fatigue);
+        if !warnings.is_empty() {
+            tracing::debug!("fatigue: {}", warnings.join("; "));
+            state.status = format!("Fatigue: {}. Consider taking a break.", warnings.join("; "));
+        }
+    }
+
+    /// Tracks pause transitions; on resume the gap is attached to the next
+    /// stored sample.
+    fn set_paused(&self, pause: Option<PauseReason>, paused_since: &mut Option<SystemTime>) {
+        let previous = {
+            let mut state = self.lock();
+            std::mem::replace(&mut state.paused, pause)
+        };
+
+        match (previous, pause) {
+            (None, Some(reason)) => {
+                tracing::info!("capture paused: {}", reason.label());
+                *paused_since = Some(SystemTime::now());
+            }
+            (Some(reason), None) => {
+                tracing::info!("capture resumed after {}", reason.label());
+                if let Some(start) = paused_since.take() {
+                    self.add_gap(reason, start);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Records a gap starting at `start`. A gap already pending, e.g. a lock
+    /// that was followed by sleep, keeps its earlier start and reason.
+    fn add_gap(&self, reason: PauseReason, start: SystemTime) {
+        let start = start.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        self.lock().pending_gap.get_or_insert(Gap { reason: reason.gap_reason(), start });
+    }
+
+    fn capture_sample(
+        &self,
+        input: Input,
+        mut context: SystemContext,
+        window: Option<&ActiveWindow>,
+        stop_flag: &AtomicBool,
+        config: &Config,
+        scripts: &Scripts,
+    ) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let (marker, tab, editor) = {
+            let mut state = self.lock();
+            let editor = state
+                .editor
+                .as_ref()
+                .filter(|(_, at)| at.elapsed() < editor::STALE_AFTER)
+                .map(|(file, _)| file.clone());
+            (state.pending_marker.take(), state.pending_tab.take(), editor)
+        };
+        context.tab = tab.filter(|_| config.record_app);
+        context.editor = editor.filter(|_| config.record_app);
+        context.process = window.filter(|_| config.record_process).map(|w| ProcessInfo {
+            pid: w.process_id,
+            exe: w.process_path.display().to_string(),
+        });
+        let record = ActivityRecord {
+            timestamp,
+            mouse_x: input.mouse.0,
+            mouse_y: input.mouse.1,
+            keys_pressed: input.keys,
+            mouse_buttons: input.buttons,
+            app: window.filter(|_| config.record_app).map(|w| w.app_name.clone()),
+            marker: marker.clone(),
+            gap: None,
+            devices: input.devices,
+            context,
+            path: input.path,
+        };
+
+        match self.screen(record, window, config, scripts) {
+            Some(mut record) => {
+                let mut state = self.lock();
+                // The session may have been stopped while we were polling
+                if !stop_flag.load(Ordering::SeqCst) {
+                    record.gap = state.pending_gap.take();
+                    state.records.push(record);
+                    state.health.sampled(Instant::now());
+                }
+            }
+            // The marker goes on the next sample that is kept instead
+            None => {
+                if let Some(marker) = marker {
+                    self.lock().pending_marker.get_or_insert(marker);
+                }
+            }
+        }
+    }
+
+    /// Applies filters and script hooks to a fresh sample; `None` if it must
+    /// not be stored.
+    fn screen(
+        &self,
+        record: ActivityRecord,
+        window: Option<&ActiveWindow>,
+        config: &Config,
+        scripts: &Scripts,
+    ) -> Option<ActivityRecord> {
+        if filter::is_filtered(&config.filters, &record, window) {
+            return None;
+        }
+
+        // A failing script drops the sample rather than leaking what it
+        // meant to hide
+        scripts.on_record(record).unwrap_or_else(|e| {
+            tracing::warn!("script error in on_record: {}", e);
+            self.set_status(format!("Script error: {}", e));
+            None
+        })
+    }
+}
@@ -0,0 +1,134 @@
+//! Animated GIF replays of the cursor trace, small enough to paste into a
+//! chat or an issue.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::overlay::{self, Canvas};
+use crate::recorder::{ActivityRecord, SAMPLE_INTERVAL};
+
+/// Levels per channel of the fixed 216 colour palette every frame uses.
+const LEVELS: u8 = 6;
+
+pub struct ReplayOptions<'a> {
+    /// Width of the animation; the height follows the screen's aspect ratio.
+    pub width: u32,
+    /// Playback speed relative to real time.
+    pub speed: u32,
+    /// A screenshot to draw the trace over instead of a blank background.
+    pub background: Option<&'a Path>,
+}
+
+pub fn write_gif(records: &[ActivityRecord], options: &ReplayOptions, path: &Path) -> Result<(), String> {
+    if records.is_empty() {
+        return Err("Session has no samples".to_string());
+    }
+    let (screen_w, screen_h) = overlay::screen_size(records);
+    let scale = options.width as f64 / screen_w as f64;
+    let width = options.width as usize;
+    let height = ((screen_h as f64 * scale).round() as usize).max(1);
+    if width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(format!("Animation size {}x{} is too large for a GIF", width, height));
+    }
+
+    let background = match options.background {
+        Some(image) => load_background(image, width, height)?,
+        None => {
+            let mut canvas = Canvas::new(width, height);
+            canvas.pixels.fill(255);
+            canvas
+        }
+    };
+
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+
+    write_header(&mut out, width as u16, height as u16).map_err(write_err)?;
+    let delay = (SAMPLE_INTERVAL.as_millis() / 10) as u16;
+    let mut canvas = Canvas::new(width, height);
+    for index in (0..records.len()).step_by(options.speed.max(1) as usize) {
+        canvas.pixels.copy_from_slice(&background.pixels);
+        overlay::draw_cursor(&mut canvas, records, index, scale);
+        write_frame(&mut out, &canvas, delay).map_err(write_err)?;
+    }
+    out.write_all(&[0x3b]).and_then(|_| out.flush()).map_err(write_err)
+}
+
+/// Reads a PNG screenshot and stretches it to the animation's size.
+fn load_background(path: &Path, width: usize, height: usize) -> Result<Canvas, String> {
+    let read_err = |e: png::DecodingError| format!("Failed to read {}: {}", path.display(), e);
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut decoder = png::Decoder::new(file);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(read_err)?;
+    let mut buf = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buf).map_err(read_err)?;
+    let channels = info.color_type.samples();
+    let (src_w, src_h) = (info.width as usize, info.height as usize);
+
+    let mut canvas = Canvas::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let src = ((y * src_h / height) * src_w + x * src_w / width) * channels;
+            let rgb = match channels {
+                1 | 2 => [buf[src]; 3],
+                _ => [buf[src], buf[src + 1], buf[src + 2]],
+            };
+            let dst = (y * width + x) * 4;
+            canvas.pixels[dst..dst + 3].copy_from_slice(&rgb);
+            canvas.pixels[dst + 3] = 255;
+        }
+    }
+    Ok(canvas)
+}
+
+fn write_header(out: &mut impl Write, width: u16, height: u16) -> std::io::Result<()> {
+    out.write_all(b"GIF89a")?;
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    // Global colour table of 256 entries, the last 40 unused
+    out.write_all(&[0xf7, 0, 0])?;
+    let step = |level: u8| level * (255 / (LEVELS - 1));
+    let mut palette = Vec::with_capacity(256 * 3);
+    for r in 0..LEVELS {
+        for g in 0..LEVELS {
+            for b in 0..LEVELS {
+                palette.extend([step(r), step(g), step(b)]);
+            }
+        }
+    }
+    palette.resize(256 * 3, 0);
+    out.write_all(&palette)?;
+    // Loop forever
+    out.write_all(&[0x21, 0xff, 0x0b])?;
+    out.write_all(b"NETSCAPE2.0")?;
+    out.write_all(&[0x03, 0x01, 0x00, 0x00, 0x00])
+}
+
+fn write_frame(out: &mut impl Write, canvas: &Canvas, delay: u16) -> std::io::Result<()> {
+    let [d0, d1] = delay.to_le_bytes();
+    out.write_all(&[0x21, 0xf9, 0x04, 0x00, d0, d1, 0x00, 0x00])?;
+
+    let [w0, w1] = (canvas.width as u16).to_le_bytes();
+    let [h0, h1] = (canvas.height as u16).to_le_bytes();
+    out.write_all(&[0x2c, 0, 0, 0, 0, w0, w1, h0, h1, 0x00])?;
+
+    let level = |c: u8| ((c as u16 * (LEVELS as u16 - 1) + 127) / 255) as u8;
+    let indices: Vec<u8> = canvas
+        .pixels
+        .chunks_exact(4)
+        .map(|p| (level(p[0]) * LEVELS + level(p[1])) * LEVELS + level(p[2]))
+        .collect();
+    let data = weezl::encode::Encoder::new(weezl::BitOrder::Lsb, 8)
+        .encode(&indices)
+        .map_err(std::io::Error::other)?;
+
+    out.write_all(&[8])?;
+    for block in data.chunks(255) {
+        out.write_all(&[block.len() as u8])?;
+        out.write_all(block)?;
+    }
+    out.write_all(&[0])
+}
@@ -0,0 +1,158 @@
+//! Downsampling a session to one row per interval, for sharing and plotting
+//! without the full sample rate. Each row averages the pointer position and
+//! speed, keeps the peak speed, and counts input over the interval.
+//! Intervals in which nothing was captured are left out.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::recorder::ActivityRecord;
+use crate::{analysis, storage};
+
+#[derive(Serialize)]
+pub struct Bucket {
+    /// Unix time the interval starts at.
+    pub start: u64,
+    pub samples: usize,
+    pub mouse_x: f64,
+    pub mouse_y: f64,
+    pub mouse_distance: f64,
+    /// Pointer speed in px/s, averaged over the interval and at its peak.
+    pub mean_speed: f64,
+    pub max_speed: f64,
+    pub key_presses: usize,
+    pub clicks: usize,
+    /// Share of samples without any input.
+    pub idle_share: f64,
+    /// Application focused at the end of the interval.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub markers: Vec<String>,
+}
+
+/// Groups the records, taken `interval` apart, into intervals of
+/// `every_secs`, aligned to multiples of it since the epoch.
+pub fn resample(records: &[ActivityRecord], every_secs: u64, interval: Duration) -> Vec<Bucket> {
+    let every_secs = every_secs.max(1);
+    let motion = analysis::motion_series(records, interval);
+    let mut buckets: Vec<Bucket> = Vec::new();
+    // Speeds seen in the current bucket, for its mean
+    let mut speeds = 0;
+
+    let mut previous: Option<&ActivityRecord> = None;
+    for (record, motion) in records.iter().zip(&motion) {
+        let start = record.timestamp - record.timestamp % every_secs;
+        if buckets.last().is_none_or(|b| b.start != start) {
+            finish(buckets.last_mut(), speeds);
+            speeds = 0;
+            buckets.push(Bucket {
+                start,
+                samples: 0,
+                mouse_x: 0.0,
+                mouse_y: 0.0,
+                mouse_distance: 0.0,
+                mean_speed: 0.0,
+                max_speed: 0.0,
+                key_presses: 0,
+                clicks: 0,
+                idle_share: 0.0,
+                app: None,
+                markers: Vec::new(),
+            });
+        }
+        let bucket = buckets.last_mut().expect("pushed above");
+
+        let step = match previous.filter(|_| record.gap.is_none()) {
+            Some(p) => ((record.mouse_x - p.mouse_x) as f64).hypot((record.mouse_y - p.mouse_y) as f64),
+            None => 0.0,
+        };
+        let key_presses = analysis::new_key_presses(previous, record);
+        bucket.samples += 1;
+        bucket.mouse_x += record.mouse_x as f64;
+        bucket.mouse_y += record.mouse_y as f64;
+        bucket.mouse_distance += step;
+        if let Some(speed) = motion.velocity {
+            bucket.mean_speed += speed;
+            bucket.max_speed = bucket.max_speed.max(speed);
+            speeds += 1;
+        }
+        bucket.key_presses += key_presses;
+        bucket.clicks += analysis::new_clicks(previous, record);
+        if step == 0.0 && record.keys_pressed.is_empty() && record.mouse_buttons.is_empty() {
+            bucket.idle_share += 1.0;
+        }
+        if record.app.is_some() {
+            bucket.app = record.app.clone();
+        }
+        if let Some(marker) = &record.marker {
+            bucket.markers.push(marker.clone());
+        }
+        previous = Some(record);
+    }
+    finish(buckets.last_mut(), speeds);
+
+    buckets
+}
+
+/// Turns the bucket's running sums into averages.
+fn finish(bucket: Option<&mut Bucket>, speeds: usize) {
+    let Some(bucket) = bucket else {
+        return;
+    };
+    let samples = bucket.samples.max(1) as f64;
+    bucket.mouse_x /= samples;
+    bucket.mouse_y /= samples;
+    bucket.idle_share /= samples;
+    bucket.mean_speed /= speeds.max(1) as f64;
+}
+
+pub fn write_csv(buckets: &[Bucket], out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(
+        out,
+        "start,samples,mouse_x,mouse_y,mouse_distance,mean_speed,max_speed,key_presses,clicks,idle_share,app,markers"
+    )?;
+    for b in buckets {
+        writeln!(
+            out,
+            "{},{},{:.1},{:.1},{:.1},{:.1},{:.1},{},{},{:.3},\"{}\",\"{}\"",
+            b.start,
+            b.samples,
+            b.mouse_x,
+            b.mouse_y,
+            b.mouse_distance,
+            b.mean_speed,
+            b.max_speed,
+            b.key_presses,
+            b.clicks,
+            b.idle_share,
+            b.app.as_deref().unwrap_or("").replace('"', "\"\""),
+            b.markers.join("; ").replace('"', "\"\"")
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_json(buckets: &[Bucket], out: &mut impl Write) -> std::io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, buckets)?;
+    writeln!(out)
+}
+
+/// Writes a resampled CSV of the session file next to it, named after the
+/// interval, and returns its path.
+pub fn write_beside(session: &Path, every_secs: u64) -> Result<PathBuf, String> {
+    let interval = storage::load_meta(session)?.sample_interval();
+    let buckets = resample(&storage::load_csv(session)?, every_secs, interval);
+    let stem = session.file_stem().unwrap_or_default().to_string_lossy();
+    let path = session.with_file_name(format!("{}-{}s.csv", stem, every_secs.max(1)));
+    let file = File::create(&path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut out = BufWriter::new(file);
+    write_csv(&buckets, &mut out)
+        .and_then(|_| out.flush())
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
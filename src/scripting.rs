@@ -0,0 +1,197 @@
+//! User scripts in Rhai, listed under `scripts` in the settings file. A
+//! script may define any of these hooks:
+//!
+//! - `on_start(task)` when recording begins
+//! - `on_record(record)` for every sample; return the record (changed or
+//!   not) to keep it, or `()` to drop it before it is stored
+//! - `on_stop(task, path)` after the session has been saved
+//!
+//! Records are maps with `timestamp`, `mouse_x`, `mouse_y`, `keys` (array
+//! of key names), `buttons` (array of mouse button numbers), `app` and
+//! `marker` (strings or `()`). Scripts can call
+//! `exec(program, [args...])` to run a command, e.g. `curl` for a webhook;
+//! it returns the exit code.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use device_query::Keycode;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+
+use crate::capture::DeviceInput;
+use crate::context::SystemContext;
+use crate::recorder::ActivityRecord;
+
+#[derive(Default)]
+pub struct Scripts {
+    engine: Engine,
+    scripts: Vec<(PathBuf, AST)>,
+}
+
+impl Scripts {
+    /// Compiles every script up front so syntax errors surface before the
+    /// session starts.
+    pub fn load(paths: &[PathBuf]) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.register_fn("exec", exec);
+
+        let scripts = paths
+            .iter()
+            .map(|path| {
+                engine
+                    .compile_file(path.clone())
+                    .map(|ast| (path.clone(), ast))
+                    .map_err(|e| format!("Failed to load script {}: {}", path.display(), e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Scripts { engine, scripts })
+    }
+
+    /// Calls `name` in every script that defines it, stopping at the first
+    /// error.
+    fn call(&self, name: &str, arity: usize, args: impl FuncArgs + Clone) -> Result<Vec<Dynamic>, String> {
+        self.scripts
+            .iter()
+            .filter(|(_, ast)| defines(ast, name, arity))
+            .map(|(path, ast)| {
+                self.engine
+                    .call_fn_with_options(call_options(), &mut Scope::new(), ast, name, args.clone())
+                    .map_err(|e| format!("{} in {}: {}", name, path.display(), e))
+            })
+            .collect()
+    }
+
+    pub fn on_start(&self, task: &str) -> Result<(), String> {
+        self.call("on_start", 1, (task.to_string(),)).map(drop)
+    }
+
+    /// Runs the record through each script's `on_record` in turn; `None`
+    /// once any of them drops it.
+    pub fn on_record(&self, record: ActivityRecord) -> Result<Option<ActivityRecord>, String> {
+        let mut value = Dynamic::from_map(to_map(&record));
+
+        for (path, ast) in &self.scripts {
+            if !defines(ast, "on_record", 1) {
+                continue;
+            }
+            value = self
+                .engine
+                .call_fn_with_options(call_options(), &mut Scope::new(), ast, "on_record", (value,))
+                .map_err(|e| format!("on_record in {}: {}", path.display(), e))?;
+            if value.is_unit() {
+                return Ok(None);
+            }
+        }
+
+        match value.try_cast::<Map>() {
+            Some(map) => {
+                let mut changed = from_map(map).map_err(|e| format!("on_record returned {}", e))?;
+                changed.devices = record.devices;
+                changed.context = record.context;
+                // A path leading somewhere the script moved the pointer away
+                // from would give the real position away
+                if (changed.mouse_x, changed.mouse_y) == (record.mouse_x, record.mouse_y) {
+                    changed.path = record.path;
+                }
+                Ok(Some(changed))
+            }
+            None => Err("on_record must return a record or ()".to_string()),
+        }
+    }
+
+    pub fn on_stop(&self, task: &str, path: &Path) -> Result<(), String> {
+        let args = (task.to_string(), path.display().to_string());
+        self.call("on_stop", 2, args).map(drop)
+    }
+}
+
+/// Only the hook functions are called; a script's top-level statements never
+/// run.
+fn call_options() -> CallFnOptions<'static> {
+    CallFnOptions::new().eval_ast(false)
+}
+
+fn defines(ast: &AST, name: &str, arity: usize) -> bool {
+    ast.iter_functions().any(|f| f.name == name && f.params.len() == arity)
+}
+
+fn exec(program: &str, args: Array) -> Result<i64, Box<EvalAltResult>> {
+    let args: Vec<String> = args.into_iter().map(|a| a.to_string()).collect();
+    let status = Command::new(program)
+        .args(&args)
+        .status()
+        .map_err(|e| format!("exec {}: {}", program, e))?;
+    Ok(status.code().unwrap_or(-1) as i64)
+}
+
+fn to_map(record: &ActivityRecord) -> Map {
+    let keys: Array = record
+        .keys_pressed
+        .iter()
+        .map(|k| Dynamic::from(format!("{:?}", k)))
+        .collect();
+
+    let mut map = Map::new();
+    map.insert("timestamp".into(), Dynamic::from(record.timestamp as i64));
+    map.insert("mouse_x".into(), Dynamic::from(record.mouse_x as i64));
+    map.insert("mouse_y".into(), Dynamic::from(record.mouse_y as i64));
+    map.insert("keys".into(), Dynamic::from_array(keys));
+    let buttons: Array = record.mouse_buttons.iter().map(|&b| Dynamic::from(b as i64)).collect();
+    map.insert("buttons".into(), Dynamic::from_array(buttons));
+    map.insert("app".into(), record.app.clone().map_or(Dynamic::UNIT, Dynamic::from));
+    map.insert(
+        "marker".into(),
+        record.marker.clone().map_or(Dynamic::UNIT, Dynamic::from),
+    );
+    map
+}
+
+fn from_map(mut map: Map) -> Result<ActivityRecord, String> {
+    let int = |name: &str| {
+        map.get(name)
+            .and_then(|v| v.as_int().ok())
+            .ok_or_else(|| format!("a record without an integer {}", name))
+    };
+    let timestamp = int("timestamp")?;
+    let mouse_x = int("mouse_x")?;
+    let mouse_y = int("mouse_y")?;
+
+    let keys_pressed = map
+        .remove("keys")
+        .and_then(|v| v.try_cast::<Array>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|k| {
+            let name = k.to_string();
+            name.parse::<Keycode>().map_err(|_| format!("unknown key {}", name))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mouse_buttons = map
+        .remove("buttons")
+        .and_then(|v| v.try_cast::<Array>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|b| b.as_int().ok().and_then(|b| u8::try_from(b).ok()).ok_or("an invalid mouse button"))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut text = |name: &str| map.remove(name).filter(|v| !v.is_unit()).map(|v| v.to_string());
+    let app = text("app");
+    let marker = text("marker");
+
+    Ok(ActivityRecord {
+        timestamp: timestamp.try_into().map_err(|_| "an invalid timestamp")?,
+        mouse_x: mouse_x.try_into().map_err(|_| "an invalid mouse_x")?,
+        mouse_y: mouse_y.try_into().map_err(|_| "an invalid mouse_y")?,
+        keys_pressed,
+        mouse_buttons,
+        app,
+        marker,
+        // Gaps are attached after the hooks have run
+        gap: None,
+        // Other devices' input, the system context and the pointer's path
+        // aren't passed to scripts and are carried over
+        devices: DeviceInput::default(),
+        context: SystemContext::default(),
+        path: Vec::new(),
+    })
+}
@@ -0,0 +1,133 @@
+//! Keyboard shortcut usage: every non-modifier key pressed while Ctrl, Alt
+//! or Meta/Command is held counts as a shortcut, attributed to the
+//! application that had focus. Also spots mouse habits that a shortcut
+//! would replace.
+
+use std::collections::{BTreeMap, HashMap};
+
+use device_query::Keycode;
+
+use crate::analysis;
+use crate::keys::{self, KeyCategory};
+use crate::recorder::ActivityRecord;
+
+/// Longest wait between a right click and the left click that picks a
+/// context menu entry.
+const CONTEXT_MENU_SECS: u64 = 3;
+/// Fewest occurrences of a habit before it is worth a suggestion.
+const MIN_HABIT: usize = 10;
+
+#[derive(Default)]
+pub struct ShortcutReport {
+    /// Count per `(shortcut, application)`.
+    pub usage: BTreeMap<(String, String), usize>,
+    context_menu_picks: usize,
+    multi_clicks: usize,
+}
+
+impl ShortcutReport {
+    pub fn add_session(&mut self, records: &[ActivityRecord]) {
+        let mut previous: Option<&ActivityRecord> = None;
+        let mut last_right_click: Option<u64> = None;
+        // (timestamp, clicks in a row) of the current left-click streak
+        let mut streak: Option<(u64, usize)> = None;
+
+        for record in records {
+            let held: &[Keycode] = previous.map_or(&[], |p| &p.keys_pressed);
+            let chord = modifiers(&record.keys_pressed);
+            if !chord.is_empty() {
+                for key in record.keys_pressed.iter().filter(|k| !held.contains(k)) {
+                    if keys::category(*key) != KeyCategory::Modifiers {
+                        let name = format!("{}+{:?}", chord, key);
+                        let app = record.app.clone().unwrap_or_else(|| "(unknown)".to_string());
+                        *self.usage.entry((name, app)).or_default() += 1;
+                    }
+                }
+            }
+
+            if analysis::new_clicks(previous, record) > 0 {
+                let held_buttons: &[u8] = previous.map_or(&[], |p| &p.mouse_buttons);
+                let pressed = |button: u8| record.mouse_buttons.contains(&button) && !held_buttons.contains(&button);
+
+                if pressed(3) {
+                    last_right_click = Some(record.timestamp);
+                }
+                if pressed(1) {
+                    if last_right_click
+                        .take()
+                        .is_some_and(|t| record.timestamp - t <= CONTEXT_MENU_SECS)
+                    {
+                        self.context_menu_picks += 1;
+                    }
+                    streak = match streak {
+                        // Within the same second counts as one burst
+                        Some((t, n)) if record.timestamp <= t + 1 => {
+                            if n + 1 == 3 {
+                                self.multi_clicks += 1;
+                            }
+                            Some((record.timestamp, n + 1))
+                        }
+                        _ => Some((record.timestamp, 1)),
+                    };
+                }
+            }
+            previous = Some(record);
+        }
+    }
+
+    /// Uses per shortcut, summed over applications, most used first.
+    pub fn totals(&self) -> Vec<(String, usize)> {
+        let mut totals: HashMap<&str, usize> = HashMap::new();
+        for ((shortcut, _), count) in &self.usage {
+            *totals.entry(shortcut).or_default() += count;
+        }
+        let mut totals: Vec<_> = totals.into_iter().map(|(s, c)| (s.to_string(), c)).collect();
+        totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        totals
+    }
+
+    fn uses(&self, shortcut_keys: &[&str]) -> usize {
+        self.usage
+            .iter()
+            .filter(|((shortcut, _), _)| shortcut_keys.iter().any(|k| shortcut.ends_with(&format!("+{}", k))))
+            .map(|(_, count)| count)
+            .sum()
+    }
+
+    pub fn suggestions(&self) -> Vec<String> {
+        let mut suggestions = Vec::new();
+        if self.context_menu_picks >= MIN_HABIT && self.uses(&["C", "V", "X"]) < self.context_menu_picks {
+            suggestions.push(format!(
+                "You picked {} context menu entries with the mouse. Ctrl+C, Ctrl+V and Ctrl+X (Cmd on macOS) copy, paste and cut without the menu.",
+                self.context_menu_picks
+            ));
+        }
+        if self.multi_clicks >= MIN_HABIT && self.uses(&["Home", "End"]) < self.multi_clicks {
+            suggestions.push(format!(
+                "You triple-clicked {} times, usually to select a line. Home then Shift+End selects it from the keyboard.",
+                self.multi_clicks
+            ));
+        }
+        suggestions
+    }
+}
+
+/// Canonical name of the held shortcut modifiers, e.g. `Ctrl+Shift`; empty
+/// when none that make a shortcut are held (Shift alone just types).
+fn modifiers(held: &[Keycode]) -> String {
+    let has = |keys: &[Keycode]| keys.iter().any(|k| held.contains(k));
+    let ctrl = has(&[Keycode::LControl, Keycode::RControl]);
+    let alt = has(&[Keycode::LAlt, Keycode::RAlt, Keycode::LOption, Keycode::ROption]);
+    let meta = has(&[Keycode::LMeta, Keycode::RMeta, Keycode::Command]);
+    if !(ctrl || alt || meta) {
+        return String::new();
+    }
+
+    let shift = has(&[Keycode::LShift, Keycode::RShift]);
+    [(ctrl, "Ctrl"), (alt, "Alt"), (shift, "Shift"), (meta, "Meta")]
+        .iter()
+        .filter(|(held, _)| *held)
+        .map(|(_, name)| *name)
+        .collect::<Vec<_>>()
+        .join("+")
+}
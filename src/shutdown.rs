@@ -0,0 +1,23 @@
+use std::process;
+
+use crate::recorder::Recorder;
+
+/// Saves the in-progress session when the process receives SIGINT, SIGTERM
+/// or SIGHUP (sent on logout), or a console close event on Windows.
+pub fn install_signal_handler(recorder: Recorder) -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(move || {
+        finalize(&recorder);
+        process::exit(0);
+    })
+}
+
+/// Finalizes the session and reports the outcome on stderr, since the UI
+/// may already be gone.
+pub fn finalize(recorder: &Recorder) {
+    match recorder.finalize() {
+        Some(Ok(path)) => eprintln!("Session saved to {}", path.display()),
+        Some(Err(e)) => eprintln!("Failed to save session: {}", e),
+        None => {}
+    }
+    recorder.wait_for_integrations();
+}
@@ -0,0 +1,75 @@
+//! Slack announcements through an incoming webhook, configured under
+//! `slack` in the settings file:
+//!
+//! ```json
+//! "slack": { "webhook_url": "https://hooks.slack.com/services/T.../B.../...", "on_start": true }
+//! ```
+//!
+//! A message is posted to the webhook's channel when a session starts,
+//! unless `on_start` is off, and when it is saved, with its duration and
+//! headline stats.
+
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::analysis;
+use crate::cli;
+use crate::outbox;
+use crate::recorder::ActivityRecord;
+use crate::storage::SessionMeta;
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SlackConfig {
+    pub webhook_url: String,
+    /// Announce sessions when they start, not only when they end.
+    pub on_start: bool,
+}
+
+impl Default for SlackConfig {
+    fn default() -> Self {
+        SlackConfig {
+            webhook_url: String::new(),
+            on_start: true,
+        }
+    }
+}
+
+impl SlackConfig {
+    pub fn session_started(&self, meta: &SessionMeta) -> Result<(), String> {
+        if !self.on_start {
+            return Ok(());
+        }
+        self.post(&format!(":red_circle: Recording started: {}", task(meta)))
+    }
+
+    pub fn session_ended(&self, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+        let summary = analysis::summarize(records);
+        if summary.samples == 0 {
+            return Ok(());
+        }
+        self.post(&format!(
+            ":white_check_mark: Recording finished: {}\n{} tracked · {} key presses · {} clicks · {:.0}% idle",
+            task(meta),
+            cli::format_duration(summary.tracked_secs()),
+            summary.key_presses,
+            summary.clicks,
+            summary.idle_ratio() * 100.0
+        ))
+    }
+
+    fn post(&self, text: &str) -> Result<(), String> {
+        let body = json!({ "text": text });
+        let headers = [("Content-Type", "application/json")];
+        outbox::deliver("Slack", "POST", &self.webhook_url, &headers, body.to_string().as_bytes())
+    }
+}
+
+/// The task in bold, and its project, escaped for Slack's markup.
+fn task(meta: &SessionMeta) -> String {
+    let escape = |text: &str| text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    match &meta.project {
+        Some(project) => format!("*{}* ({})", escape(&meta.task), escape(project)),
+        None => format!("*{}*", escape(&meta.task)),
+    }
+}
@@ -0,0 +1,133 @@
+//! Audible cues for the session lifecycle. Tones are synthesized into small
+//! WAV files and played with the platform's own command-line player, so no
+//! audio library is needed.
+
+use std::f32::consts::TAU;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+const SAMPLE_RATE: u32 = 22_050;
+
+/// Ramp at each end of a note, to avoid clicks.
+const FADE_SECS: f32 = 0.01;
+
+#[derive(Clone, Copy)]
+pub enum Cue {
+    /// A task was created and its countdown began.
+    Start,
+    /// The countdown is over and samples are being recorded.
+    Recording,
+    /// The session ended.
+    Stop,
+}
+
+impl Cue {
+    fn name(self) -> &'static str {
+        match self {
+            Cue::Start => "start",
+            Cue::Recording => "recording",
+            Cue::Stop => "stop",
+        }
+    }
+
+    /// Frequency in Hz and length in seconds of each note.
+    fn notes(self) -> &'static [(f32, f32)] {
+        match self {
+            Cue::Start => &[(660.0, 0.12), (880.0, 0.18)],
+            Cue::Recording => &[(1000.0, 0.15)],
+            Cue::Stop => &[(880.0, 0.12), (660.0, 0.12), (523.0, 0.2)],
+        }
+    }
+}
+
+/// Plays `cue` in the background. Failures are only logged; a missing
+/// player must never get in the way of recording.
+pub fn play(cue: Cue) {
+    thread::spawn(move || {
+        if let Err(e) = write_cue(cue).and_then(|path| run_player(&path)) {
+            tracing::warn!("could not play {} sound: {}", cue.name(), e);
+        }
+    });
+}
+
+fn write_cue(cue: Cue) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join("activity-tracker-sounds");
+    let path = dir.join(format!("{}.wav", cue.name()));
+    if path.exists() {
+        return Ok(path);
+    }
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let mut wav = Vec::new();
+    write_wav(&mut wav, &samples(cue)).map_err(|e| e.to_string())?;
+    fs::write(&path, wav).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn samples(cue: Cue) -> Vec<i16> {
+    let mut samples = Vec::new();
+    for &(freq, secs) in cue.notes() {
+        let count = (secs * SAMPLE_RATE as f32) as usize;
+        for i in 0..count {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let envelope = (t / FADE_SECS).min((secs - t) / FADE_SECS).clamp(0.0, 1.0);
+            samples.push(((TAU * freq * t).sin() * envelope * 0.4 * i16::MAX as f32) as i16);
+        }
+    }
+    samples
+}
+
+/// 16-bit mono PCM.
+fn write_wav(out: &mut impl Write, samples: &[i16]) -> io::Result<()> {
+    let data_len = samples.len() as u32 * 2;
+    out.write_all(b"RIFF")?;
+    out.write_all(&(36 + data_len).to_le_bytes())?;
+    out.write_all(b"WAVEfmt ")?;
+    out.write_all(&16u32.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&1u16.to_le_bytes())?;
+    out.write_all(&SAMPLE_RATE.to_le_bytes())?;
+    out.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+    out.write_all(&2u16.to_le_bytes())?;
+    out.write_all(&16u16.to_le_bytes())?;
+    out.write_all(b"data")?;
+    out.write_all(&data_len.to_le_bytes())?;
+    for sample in samples {
+        out.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+fn run_player(path: &Path) -> Result<(), String> {
+    let players: Vec<Command> = if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command"]).arg(format!(
+            "(New-Object Media.SoundPlayer '{}').PlaySync()",
+            path.display().to_string().replace('\'', "''")
+        ));
+        vec![command]
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("afplay");
+        command.arg(path);
+        vec![command]
+    } else {
+        let mut paplay = Command::new("paplay");
+        paplay.arg(path);
+        let mut aplay = Command::new("aplay");
+        aplay.arg("-q").arg(path);
+        vec![paplay, aplay]
+    };
+
+    let mut last_error = "no audio player".to_string();
+    for mut command in players {
+        let program = command.get_program().to_string_lossy().into_owned();
+        match command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = format!("{} exited with {}", program, status),
+            Err(e) => last_error = format!("{}: {}", program, e),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,47 @@
+//! Spoken announcements of the session lifecycle, through the platform's
+//! speech synthesizer: `say` on macOS, System.Speech on Windows and
+//! speech-dispatcher or eSpeak elsewhere.
+
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Speaks `text` in the background. Failures are only logged.
+pub fn say(text: String) {
+    thread::spawn(move || {
+        if let Err(e) = speak(&text) {
+            tracing::warn!("could not speak \"{}\": {}", text, e);
+        }
+    });
+}
+
+fn speak(text: &str) -> Result<(), String> {
+    let speakers: Vec<Command> = if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command"]).arg(format!(
+            "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+            text.replace('\'', "''")
+        ));
+        vec![command]
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("say");
+        command.arg(text);
+        vec![command]
+    } else {
+        let mut spd_say = Command::new("spd-say");
+        spd_say.args(["--wait", "--"]).arg(text);
+        let mut espeak = Command::new("espeak");
+        espeak.arg("--").arg(text);
+        vec![spd_say, espeak]
+    };
+
+    let mut last_error = "no speech synthesizer".to_string();
+    for mut command in speakers {
+        let program = command.get_program().to_string_lossy().into_owned();
+        match command.stdout(Stdio::null()).stderr(Stdio::null()).status() {
+            Ok(status) if status.success() => return Ok(()),
+            Ok(status) => last_error = format!("{} exited with {}", program, status),
+            Err(e) => last_error = format!("{}: {}", program, e),
+        }
+    }
+    Err(last_error)
+}
@@ -0,0 +1,207 @@
+//! Sessions as SQLite databases, through the `sqlite3` command-line shell
+//! since no SQLite library is bundled. A database holds one session: its
+//! samples in `samples`, with keys and buttons joined by `+` and other
+//! devices' input, the system context and the pointer's path as JSON as in
+//! the CSV, and its metadata as JSON in `session`. Reading needs `sqlite3`
+//! 3.33 or newer, for its `-json` output.
+
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use serde::Deserialize;
+
+use crate::capture::DeviceInput;
+use crate::context::SystemContext;
+use crate::recorder::{ActivityRecord, Gap};
+use crate::storage::SessionMeta;
+
+const SCHEMA: &str = "\
+CREATE TABLE session (meta TEXT NOT NULL);
+CREATE TABLE samples (
+    timestamp INTEGER NOT NULL,
+    mouse_x INTEGER NOT NULL,
+    mouse_y INTEGER NOT NULL,
+    keys_pressed TEXT NOT NULL,
+    mouse_buttons TEXT NOT NULL,
+    app TEXT,
+    marker TEXT,
+    gap_reason TEXT,
+    gap_start INTEGER,
+    devices TEXT,
+    context TEXT,
+    path TEXT
+);
+";
+
+#[derive(Deserialize)]
+struct Row {
+    timestamp: u64,
+    mouse_x: i32,
+    mouse_y: i32,
+    keys_pressed: String,
+    mouse_buttons: String,
+    app: Option<String>,
+    marker: Option<String>,
+    gap_reason: Option<String>,
+    gap_start: Option<u64>,
+    // Missing from databases written before the column existed
+    #[serde(default)]
+    devices: Option<String>,
+    #[serde(default)]
+    context: Option<String>,
+    #[serde(default)]
+    path: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MetaRow {
+    meta: String,
+}
+
+/// Creates a database at `path` holding the session. The file must not
+/// exist yet.
+pub fn write(path: &Path, meta: &SessionMeta, records: &[ActivityRecord]) -> Result<(), String> {
+    let meta_json = serde_json::to_string(meta).map_err(|e| e.to_string())?;
+    let mut sql = format!("BEGIN;\n{}INSERT INTO session VALUES ({});\n", SCHEMA, text(&meta_json));
+    for r in records {
+        let keys: Vec<String> = r.keys_pressed.iter().map(|k| format!("{:?}", k)).collect();
+        let buttons: Vec<String> = r.mouse_buttons.iter().map(|b| b.to_string()).collect();
+        let devices = if r.devices.is_empty() {
+            "NULL".to_string()
+        } else {
+            text(&serde_json::to_string(&r.devices).map_err(|e| e.to_string())?)
+        };
+        let context = if r.context.is_empty() {
+            "NULL".to_string()
+        } else {
+            text(&serde_json::to_string(&r.context).map_err(|e| e.to_string())?)
+        };
+        let path = if r.path.is_empty() {
+            "NULL".to_string()
+        } else {
+            text(&serde_json::to_string(&r.path).map_err(|e| e.to_string())?)
+        };
+        sql.push_str(&format!(
+            "INSERT INTO samples VALUES ({}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {}, {});\n",
+            r.timestamp,
+            r.mouse_x,
+            r.mouse_y,
+            text(&keys.join("+")),
+            text(&buttons.join("+")),
+            r.app.as_deref().map_or("NULL".to_string(), text),
+            r.marker.as_deref().map_or("NULL".to_string(), text),
+            r.gap.map_or("NULL".to_string(), |gap| text(gap.reason.as_str())),
+            r.gap.map_or("NULL".to_string(), |gap| gap.start.to_string()),
+            devices,
+            context,
+            path,
+        ));
+    }
+    sql.push_str("COMMIT;\n");
+    sqlite3(path, &[], &sql).map(|_| ())
+}
+
+/// Reads a session written by [`write`].
+pub fn read(path: &Path) -> Result<(SessionMeta, Vec<ActivityRecord>), String> {
+    let meta_rows: Vec<MetaRow> = query(path, "SELECT meta FROM session LIMIT 1")?;
+    let meta = match meta_rows.first() {
+        Some(row) => serde_json::from_str(&row.meta).map_err(|e| format!("Invalid metadata in {}: {}", path.display(), e))?,
+        None => SessionMeta::default(),
+    };
+    let rows: Vec<Row> = query(path, "SELECT * FROM samples ORDER BY rowid")?;
+    let records = rows
+        .into_iter()
+        .map(|row| {
+            let keys_pressed = row
+                .keys_pressed
+                .split('+')
+                .filter(|k| !k.is_empty())
+                .map(|k| k.parse().map_err(|_| format!("unknown key {}", k)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mouse_buttons = row
+                .mouse_buttons
+                .split('+')
+                .filter(|b| !b.is_empty())
+                .map(|b| b.parse().map_err(|_| "invalid mouse_buttons".to_string()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let gap = match (row.gap_reason, row.gap_start) {
+                (Some(reason), Some(start)) => Some(Gap { reason: reason.parse()?, start }),
+                _ => None,
+            };
+            let devices = match row.devices {
+                Some(devices) => serde_json::from_str(&devices).map_err(|e| format!("invalid devices: {}", e))?,
+                None => DeviceInput::default(),
+            };
+            let context = match row.context {
+                Some(context) => serde_json::from_str(&context).map_err(|e| format!("invalid context: {}", e))?,
+                None => SystemContext::default(),
+            };
+            let path = match row.path {
+                Some(path) => serde_json::from_str(&path).map_err(|e| format!("invalid path: {}", e))?,
+                None => Vec::new(),
+            };
+            Ok(ActivityRecord {
+                timestamp: row.timestamp,
+                mouse_x: row.mouse_x,
+                mouse_y: row.mouse_y,
+                keys_pressed,
+                mouse_buttons,
+                app: row.app,
+                marker: row.marker,
+                gap,
+                devices,
+                context,
+                path,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((meta, records))
+}
+
+fn query<T: for<'de> Deserialize<'de>>(path: &Path, sql: &str) -> Result<Vec<T>, String> {
+    let output = sqlite3(path, &["-readonly", "-json"], sql)?;
+    // No rows means no output at all rather than an empty array
+    if output.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(&output).map_err(|e| format!("Unexpected output from sqlite3: {}", e))
+}
+
+/// Runs `sql` against the database, passing it on stdin.
+fn sqlite3(path: &Path, args: &[&str], sql: &str) -> Result<String, String> {
+    let mut child = Command::new("sqlite3")
+        .args(["-bail", "-batch"])
+        .args(args)
+        .arg(path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run sqlite3: {}", e))?;
+    // Written alongside reading the output, so a statement that fails and
+    // makes sqlite3 quit is reported rather than the broken pipe it leaves
+    let stdin = child.stdin.take();
+    let (written, output) = thread::scope(|scope| {
+        let writer = scope.spawn(move || stdin.map_or(Ok(()), |mut stdin| stdin.write_all(sql.as_bytes())));
+        let output = child.wait_with_output();
+        (writer.join().unwrap_or(Ok(())), output)
+    });
+    let output = output.map_err(|e| format!("Failed to run sqlite3: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "sqlite3 failed on {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    written.map_err(|e| format!("Failed to run sqlite3: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A quoted SQL string literal.
+fn text(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
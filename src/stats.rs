@@ -0,0 +1,252 @@
+//! Statistical comparison of two groups of sessions, e.g. all sessions of
+//! two tasks: per-session metrics summarized per group and compared with
+//! Welch's t-test, which does not assume equal variances or group sizes.
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::analysis::Summary;
+
+#[derive(Serialize)]
+pub struct GroupStats {
+    pub sessions: usize,
+    pub mean: f64,
+    pub sd: f64,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+}
+
+#[derive(Serialize)]
+pub struct MetricComparison {
+    pub metric: &'static str,
+    pub a: GroupStats,
+    pub b: GroupStats,
+    /// Mean of `b` minus mean of `a`.
+    pub difference: f64,
+    pub t: Option<f64>,
+    /// Two-sided p-value; `None` when either group has fewer than two
+    /// sessions or neither varies.
+    pub p: Option<f64>,
+}
+
+#[derive(Serialize)]
+pub struct Comparison {
+    pub a: String,
+    pub b: String,
+    pub metrics: Vec<MetricComparison>,
+}
+
+/// A per-session value to compare.
+type Metric = fn(&Summary) -> f64;
+
+/// Metrics compared, by name.
+const METRICS: [(&str, Metric); 5] = [
+    ("tracked_minutes", |s| s.tracked_secs() as f64 / 60.0),
+    ("keys_per_minute", |s| per_minute(s, s.key_presses as f64)),
+    ("clicks_per_minute", |s| per_minute(s, s.clicks as f64)),
+    ("mouse_px_per_minute", |s| per_minute(s, s.mouse_distance)),
+    ("idle_ratio", |s| s.idle_ratio()),
+];
+
+fn per_minute(summary: &Summary, count: f64) -> f64 {
+    match summary.tracked_secs() {
+        0 => 0.0,
+        secs => count * 60.0 / secs as f64,
+    }
+}
+
+impl Comparison {
+    pub fn new(a: String, a_sessions: &[Summary], b: String, b_sessions: &[Summary]) -> Self {
+        let metrics = METRICS
+            .iter()
+            .map(|(metric, value)| {
+                let xs: Vec<f64> = a_sessions.iter().map(value).collect();
+                let ys: Vec<f64> = b_sessions.iter().map(value).collect();
+                let (t, p) = match welch(&xs, &ys) {
+                    Some((t, p)) => (Some(t), Some(p)),
+                    None => (None, None),
+                };
+                let (a, b) = (GroupStats::of(xs), GroupStats::of(ys));
+                MetricComparison { metric, difference: b.mean - a.mean, a, b, t, p }
+            })
+            .collect();
+        Comparison { a, b, metrics }
+    }
+
+    pub fn print(&self) {
+        let sessions = |m: &MetricComparison| (m.a.sessions, m.b.sessions);
+        if let Some((a, b)) = self.metrics.first().map(sessions) {
+            println!("A: {} ({} sessions)", self.a, a);
+            println!("B: {} ({} sessions)", self.b, b);
+        }
+        println!(
+            "{:<20} {:>16} {:>16} {:>10} {:>8}",
+            "metric", "A mean ± sd", "B mean ± sd", "B − A", "p"
+        );
+        for m in &self.metrics {
+            let p = match m.p {
+                Some(p) if p < 0.001 => "<0.001".to_string(),
+                Some(p) => format!("{:.3}", p),
+                None => "-".to_string(),
+            };
+            println!(
+                "{:<20} {:>16} {:>16} {:>+10.2} {:>8}",
+                m.metric,
+                format!("{:.2} ± {:.2}", m.a.mean, m.a.sd),
+                format!("{:.2} ± {:.2}", m.b.mean, m.b.sd),
+                m.difference,
+                p
+            );
+        }
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "metric,a_sessions,a_mean,a_sd,a_min,a_median,a_max,b_sessions,b_mean,b_sd,b_min,b_median,b_max,difference,t,p"
+        )?;
+        let optional = |value: Option<f64>| value.map_or(String::new(), |v| format!("{:.6}", v));
+        for m in &self.metrics {
+            writeln!(
+                out,
+                "{},{},{:.6},{:.6},{:.6},{:.6},{:.6},{},{:.6},{:.6},{:.6},{:.6},{:.6},{:.6},{},{}",
+                m.metric,
+                m.a.sessions, m.a.mean, m.a.sd, m.a.min, m.a.median, m.a.max,
+                m.b.sessions, m.b.mean, m.b.sd, m.b.min, m.b.median, m.b.max,
+                m.difference,
+                optional(m.t),
+                optional(m.p)
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+}
+
+impl GroupStats {
+    fn of(mut values: Vec<f64>) -> Self {
+        values.sort_by(f64::total_cmp);
+        let n = values.len();
+        let (mean, variance) = mean_variance(&values);
+        let median = match n {
+            0 => 0.0,
+            n if n % 2 == 1 => values[n / 2],
+            n => (values[n / 2 - 1] + values[n / 2]) / 2.0,
+        };
+        GroupStats {
+            sessions: n,
+            mean,
+            sd: variance.sqrt(),
+            min: values.first().copied().unwrap_or(0.0),
+            median,
+            max: values.last().copied().unwrap_or(0.0),
+        }
+    }
+}
+
+/// Mean and sample variance.
+fn mean_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    if values.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() < 2 {
+        return (mean, 0.0);
+    }
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    (mean, variance)
+}
+
+/// Welch's t statistic and its two-sided p-value.
+fn welch(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    if xs.len() < 2 || ys.len() < 2 {
+        return None;
+    }
+    let (mx, vx) = mean_variance(xs);
+    let (my, vy) = mean_variance(ys);
+    let (sx, sy) = (vx / xs.len() as f64, vy / ys.len() as f64);
+    if sx + sy == 0.0 {
+        return None;
+    }
+    let t = (my - mx) / (sx + sy).sqrt();
+    let df = (sx + sy).powi(2) / (sx.powi(2) / (xs.len() - 1) as f64 + sy.powi(2) / (ys.len() - 1) as f64);
+    // P(|T| > |t|) for Student's t with df degrees of freedom
+    let p = incomplete_beta(df / 2.0, 0.5, df / (df + t * t));
+    Some((t, p.clamp(0.0, 1.0)))
+}
+
+/// Regularized incomplete beta function I_x(a, b), by continued fraction
+/// (Numerical Recipes' `betai`).
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..200 {
+        let m = m as f64;
+        for aa in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-12 {
+            break;
+        }
+    }
+    h
+}
+
+/// Lanczos approximation of ln Γ(x).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
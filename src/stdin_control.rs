@@ -0,0 +1,55 @@
+//! Headless mode for supervisors: reads the same newline-delimited commands
+//! as the control endpoint from stdin and answers each with one JSON line on
+//! stdout, e.g. `{"ok":true,"message":"started \"review\""}`.
+
+use std::io::{self, BufRead, Write};
+
+use serde::Serialize;
+
+use crate::control::Command;
+use crate::recorder::Recorder;
+use crate::shutdown;
+
+#[derive(Serialize)]
+struct Response {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<String, String>> for Response {
+    fn from(result: Result<String, String>) -> Self {
+        match result {
+            Ok(message) => Response { ok: true, message: Some(message), error: None },
+            Err(error) => Response { ok: false, message: None, error: Some(error) },
+        }
+    }
+}
+
+/// Serves commands from stdin until it is closed, then saves whatever
+/// session is still in progress.
+pub fn run() -> Result<(), String> {
+    let recorder = Recorder::default();
+    shutdown::install_signal_handler(recorder.clone()).map_err(|e| e.to_string())?;
+
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("Failed to read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = Response::from(Command::parse(&line).and_then(|cmd| cmd.execute(&recorder)));
+        let json = serde_json::to_string(&response).map_err(|e| e.to_string())?;
+        // The supervisor is gone once stdout is closed; stop quietly
+        if writeln!(stdout, "{}", json).and_then(|_| stdout.flush()).is_err() {
+            break;
+        }
+    }
+
+    shutdown::finalize(&recorder);
+    Ok(())
+}
@@ -0,0 +1,601 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Local};
+use device_query::Keycode;
+use dirs::download_dir;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::DeviceInput;
+use crate::config::Config;
+use crate::context::SystemContext;
+use crate::recorder::{ActivityRecord, Gap, SAMPLE_INTERVAL};
+
+const CSV_HEADER_PREFIX: &str = "timestamp,mouse_x,mouse_y,keys_pressed";
+/// Header of files in the current schema.
+const CSV_HEADER: &str = "timestamp,mouse_x,mouse_y,keys_pressed,marker,gap_reason,gap_start,mouse_buttons,app,devices,context,path";
+
+/// Why a session or export could not be written.
+#[derive(Debug)]
+pub enum StorageError {
+    NoData,
+    NoOutputDir,
+    Create(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+}
+
+impl StorageError {
+    /// Whether trying again, possibly in another folder, could succeed.
+    pub fn is_retryable(&self) -> bool {
+        !matches!(self, StorageError::NoData)
+    }
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NoData => write!(f, "No activity data recorded."),
+            StorageError::NoOutputDir => write!(f, "No folder to save sessions in; choose one."),
+            StorageError::Create(path, e) => write!(f, "Failed to create {}: {}", path.display(), e),
+            StorageError::Write(path, e) => write!(f, "Failed to write {}: {}", path.display(), e),
+        }
+    }
+}
+
+impl From<StorageError> for String {
+    fn from(e: StorageError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Directory sessions are saved to and listed from: `output_dir` from the
+/// settings, else the first of Downloads, the app's data directory and the
+/// home directory that is usable.
+pub fn output_dir() -> Result<PathBuf, StorageError> {
+    if let Some(dir) = Config::load().ok().and_then(|config| config.output_dir) {
+        return Ok(dir);
+    }
+    if let Some(dir) = download_dir().filter(|dir| dir.is_dir()) {
+        return Ok(dir);
+    }
+    let data_dir = dirs::data_local_dir()
+        .map(|dir| dir.join("activity-tracker").join("sessions"))
+        .filter(|dir| fs::create_dir_all(dir).is_ok());
+    if let Some(dir) = data_dir.or_else(|| dirs::home_dir().filter(|dir| dir.is_dir())) {
+        tracing::info!("no Downloads directory; using {}", dir.display());
+        return Ok(dir);
+    }
+    Err(StorageError::NoOutputDir)
+}
+
+/// What a session was for, kept next to its CSV as `<name>.meta.json`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct SessionMeta {
+    pub task: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// The preset the session was started from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Milliseconds between two stored samples, which `sample_every`
+    /// stretches; missing from sessions saved before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_interval_ms: Option<u64>,
+}
+
+impl SessionMeta {
+    pub fn new(task: impl Into<String>) -> Self {
+        SessionMeta {
+            task: task.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Time between two stored samples.
+    pub fn sample_interval(&self) -> Duration {
+        self.sample_interval_ms.map_or(SAMPLE_INTERVAL, Duration::from_millis)
+    }
+}
+
+fn meta_path(csv_path: &Path) -> PathBuf {
+    csv_path.with_extension("meta.json")
+}
+
+pub fn save_meta(csv_path: &Path, meta: &SessionMeta) -> Result<(), StorageError> {
+    let path = meta_path(csv_path);
+    let json = serde_json::to_string_pretty(meta).map_err(|e| StorageError::Write(path.clone(), e.into()))?;
+    fs::write(&path, json).map_err(|e| StorageError::Write(path, e))
+}
+
+/// Reads the session's metadata. Sessions saved before metadata existed get
+/// their task name back from the file name.
+pub fn load_meta(csv_path: &Path) -> Result<SessionMeta, String> {
+    let path = meta_path(csv_path);
+    match fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).map_err(|e| format!("Invalid {}: {}", path.display(), e)),
+        Err(_) => {
+            let stem = csv_path.file_stem().unwrap_or_default().to_string_lossy();
+            let task = stem.rsplit_once('_').map_or(&*stem, |(task, _timestamp)| task);
+            Ok(SessionMeta::new(task.replace('_', " ")))
+        }
+    }
+}
+
+/// Session file names as written in the settings, e.g.
+/// `"{date}_{project}_{task}_{seq}"`. Placeholders are `{task}`,
+/// `{project}`, `{date}` (YYYY-MM-DD), `{time}` (HHMMSS), `{timestamp}`
+/// (Unix seconds) and `{seq}`, the lowest number not yet taken; `.csv` is
+/// appended.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "String")]
+pub struct FilenameTemplate(String);
+
+const PLACEHOLDERS: [&str; 6] = ["task", "project", "date", "time", "timestamp", "seq"];
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        FilenameTemplate("{task}_{timestamp}".to_string())
+    }
+}
+
+impl TryFrom<String> for FilenameTemplate {
+    type Error = String;
+
+    fn try_from(template: String) -> Result<Self, Self::Error> {
+        if template.contains(['/', '\\']) {
+            return Err(format!("filename template \"{}\" must not contain path separators", template));
+        }
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed placeholder in filename template \"{}\"", template))?;
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!(
+                    "unknown placeholder {{{}}} in filename template; use {}",
+                    name,
+                    PLACEHOLDERS.map(|p| format!("{{{}}}", p)).join(", ")
+                ));
+            }
+            rest = &rest[start + end + 1..];
+        }
+        Ok(FilenameTemplate(template))
+    }
+}
+
+impl FilenameTemplate {
+    fn has_seq(&self) -> bool {
+        self.0.contains("{seq}")
+    }
+
+    fn render(&self, meta: &SessionMeta, time: DateTime<Local>, seq: u32) -> String {
+        self.0
+            .replace("{task}", &sanitize(&meta.task))
+            .replace("{project}", &meta.project.as_deref().map(sanitize).unwrap_or_default())
+            .replace("{date}", &time.format("%Y-%m-%d").to_string())
+            .replace("{time}", &time.format("%H%M%S").to_string())
+            .replace("{timestamp}", &time.timestamp().to_string())
+            .replace("{seq}", &seq.to_string())
+    }
+}
+
+/// Longest task name accepted.
+pub const MAX_TASK_CHARS: usize = 100;
+/// Longest a task or project name gets in a file name.
+const MAX_NAME_PART_CHARS: usize = 64;
+
+/// Device names Windows reserves regardless of extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9", "LPT1",
+    "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Checks a task name before recording starts.
+pub fn validate_task_name(task: &str) -> Result<(), String> {
+    if task.trim().is_empty() {
+        return Err("Task name must not be empty.".to_string());
+    }
+    if task.chars().count() > MAX_TASK_CHARS {
+        return Err(format!("Task name must be at most {} characters.", MAX_TASK_CHARS));
+    }
+    if task.chars().any(char::is_control) {
+        return Err("Task name must not contain control characters.".to_string());
+    }
+    Ok(())
+}
+
+/// Turns a task or project name into something every platform accepts in a
+/// file name: letters, digits and `-_.+()` are kept, anything else
+/// (separators, `:`, emoji, ...) becomes `_`, names Windows reserves get a
+/// leading `_`, and the result is capped at `MAX_NAME_PART_CHARS`.
+pub fn sanitize(name: &str) -> String {
+    let mut sanitized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        let c = if c.is_alphanumeric() || "-_.+()".contains(c) { c } else { '_' };
+        // Collapse runs of replaced characters
+        if !(c == '_' && sanitized.ends_with('_')) {
+            sanitized.push(c);
+        }
+    }
+    let mut sanitized: String = sanitized
+        .trim_matches(|c| c == '.' || c == '_')
+        .chars()
+        .take(MAX_NAME_PART_CHARS)
+        .collect();
+    // Windows drops trailing dots, which could make two names collide
+    while sanitized.ends_with('.') {
+        sanitized.pop();
+    }
+
+    if sanitized.is_empty() {
+        return "task".to_string();
+    }
+    let stem = sanitized.split('.').next().unwrap_or_default();
+    if RESERVED_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        sanitized.insert(0, '_');
+    }
+    sanitized
+}
+
+/// Writes the records as CSV into `dir`, or the output directory when none
+/// is given, named after `template`, and returns the path.
+pub fn save_csv(
+    dir: Option<&Path>,
+    meta: &SessionMeta,
+    template: &FilenameTemplate,
+    data: &[ActivityRecord],
+) -> Result<PathBuf, StorageError> {
+    if data.is_empty() {
+        return Err(StorageError::NoData);
+    }
+
+    let output_dir = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => output_dir()?,
+    };
+    let now = Local::now();
+
+    // A name that is taken, e.g. by a session of the same task ending within
+    // the same second, moves on to the next {seq}, or gets a counter when
+    // the template has none
+    let mut seq = 1;
+    let (file_path, file) = loop {
+        let name = template.render(meta, now, seq);
+        let filename = match seq {
+            n if n > 1 && !template.has_seq() => format!("{}-{}.csv", name, n),
+            _ => format!("{}.csv", name),
+        };
+        let file_path = output_dir.join(filename);
+        match OpenOptions::new().write(true).create_new(true).open(&file_path) {
+            Ok(file) => break (file_path, file),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => seq += 1,
+            Err(e) => return Err(StorageError::Create(file_path, e)),
+        }
+    };
+    write_csv(BufWriter::new(file), data).map_err(|e| StorageError::Write(file_path.clone(), e))?;
+
+    Ok(file_path)
+}
+
+fn write_csv(mut file: impl Write, data: &[ActivityRecord]) -> io::Result<()> {
+    // Write CSV header
+    writeln!(file, "{}", CSV_HEADER)?;
+
+    // Write each record
+    for record in data.iter() {
+        let keys_str = record.keys_pressed
+            .iter()
+            .map(|k| format!("{:?}", k))
+            .collect::<Vec<String>>()
+            .join("+");
+
+        let buttons_str = record.mouse_buttons
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<String>>()
+            .join("+");
+
+        let (gap_reason, gap_start) = match record.gap {
+            Some(gap) => (gap.reason.as_str(), gap.start.to_string()),
+            None => ("", String::new()),
+        };
+
+        let devices_str = if record.devices.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&record.devices).map_err(io::Error::from)?
+        };
+        let context_str = if record.context.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&record.context).map_err(io::Error::from)?
+        };
+        // Holds no quotes, only numbers
+        let path_str = if record.path.is_empty() {
+            String::new()
+        } else {
+            serde_json::to_string(&record.path).map_err(io::Error::from)?
+        };
+
+        writeln!(
+            file,
+            "{},{},{},\"{}\",\"{}\",{},{},{},\"{}\",\"{}\",\"{}\",\"{}\"",
+            record.timestamp,
+            record.mouse_x,
+            record.mouse_y,
+            keys_str,
+            record.marker.as_deref().unwrap_or("").replace('"', "\"\""),
+            gap_reason,
+            gap_start,
+            buttons_str,
+            record.app.as_deref().unwrap_or("").replace('"', "\"\""),
+            devices_str.replace('"', "\"\""),
+            context_str.replace('"', "\"\""),
+            path_str
+        )?;
+    }
+
+    file.flush()
+}
+
+/// Writes the records as CSV to `path`, replacing any file there.
+pub fn write_csv_file(path: &Path, data: &[ActivityRecord]) -> Result<(), StorageError> {
+    let file = File::create(path).map_err(|e| StorageError::Create(path.to_path_buf(), e))?;
+    write_csv(BufWriter::new(file), data).map_err(|e| StorageError::Write(path.to_path_buf(), e))
+}
+
+/// Approximate size of the CSV [`save_csv`] would write for `data`, from the
+/// average row length of the most recent records.
+pub fn estimate_csv_size(data: &[ActivityRecord]) -> u64 {
+    const SAMPLE_ROWS: usize = 500;
+    let sample = &data[data.len().saturating_sub(SAMPLE_ROWS)..];
+    let mut header = Vec::new();
+    let mut buf = Vec::new();
+    if write_csv(&mut header, &[]).is_err() || write_csv(&mut buf, sample).is_err() || sample.is_empty() {
+        return header.len() as u64;
+    }
+    let row_bytes = (buf.len() - header.len()) as f64 / sample.len() as f64;
+    header.len() as u64 + (row_bytes * data.len() as f64).round() as u64
+}
+
+/// Reads a session previously written by [`save_csv`]. Files from before the
+/// `marker`, `gap_*`, `mouse_buttons`, `app`, `devices`, `context` and
+/// `path` columns existed are accepted too.
+pub fn load_csv(path: &Path) -> Result<Vec<ActivityRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    match lines.next() {
+        Some(Ok(header)) if header.starts_with(CSV_HEADER_PREFIX) => {}
+        _ => return Err(format!("{} is not an activity session file.", path.display())),
+    }
+
+    let mut records = Vec::new();
+    for (index, line) in lines.enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if line.is_empty() {
+            continue;
+        }
+        // Line 1 is the header
+        let record = parse_record(&line).map_err(|e| format!("{}:{}: {}", path.display(), index + 2, e))?;
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+fn parse_record(line: &str) -> Result<ActivityRecord, String> {
+    let fields = split_csv_line(line);
+    if fields.len() < 4 {
+        return Err(format!("expected at least 4 fields, found {}", fields.len()));
+    }
+
+    let number_error = |name: &str| format!("invalid {}", name);
+    let keys_pressed = fields[3]
+        .split('+')
+        .filter(|k| !k.is_empty())
+        .map(|k| k.parse::<Keycode>().map_err(|_| format!("unknown key {}", k)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mouse_buttons = fields
+        .get(7)
+        .map_or("", String::as_str)
+        .split('+')
+        .filter(|b| !b.is_empty())
+        .map(|b| b.parse::<u8>().map_err(|_| number_error("mouse_buttons")))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(ActivityRecord {
+        timestamp: fields[0].parse().map_err(|_| number_error("timestamp"))?,
+        mouse_x: fields[1].parse().map_err(|_| number_error("mouse_x"))?,
+        mouse_y: fields[2].parse().map_err(|_| number_error("mouse_y"))?,
+        keys_pressed,
+        mouse_buttons,
+        app: fields.get(8).filter(|a| !a.is_empty()).cloned(),
+        marker: fields.get(4).filter(|m| !m.is_empty()).cloned(),
+        gap: parse_gap(fields.get(5), fields.get(6))?,
+        devices: match fields.get(9).filter(|d| !d.is_empty()) {
+            Some(devices) => serde_json::from_str(devices).map_err(|e| format!("invalid devices: {}", e))?,
+            None => DeviceInput::default(),
+        },
+        context: match fields.get(10).filter(|c| !c.is_empty()) {
+            Some(context) => serde_json::from_str(context).map_err(|e| format!("invalid context: {}", e))?,
+            None => SystemContext::default(),
+        },
+        path: match fields.get(11).filter(|p| !p.is_empty()) {
+            Some(path) => serde_json::from_str(path).map_err(|e| format!("invalid path: {}", e))?,
+            None => Vec::new(),
+        },
+    })
+}
+
+fn parse_gap(reason: Option<&String>, start: Option<&String>) -> Result<Option<Gap>, String> {
+    let Some(reason) = reason.filter(|r| !r.is_empty()) else {
+        return Ok(None);
+    };
+    let start = start
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| "invalid gap_start".to_string())?;
+    Ok(Some(Gap { reason: reason.parse()?, start }))
+}
+
+/// Splits one CSV line, honouring double-quoted fields with `""` escapes.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+
+    fields
+}
+
+/// All session files in the output directory, oldest first.
+pub fn list_sessions() -> Result<Vec<PathBuf>, String> {
+    let dir = output_dir()?;
+    let entries = fs::read_dir(&dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    let mut sessions: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "csv") && is_session_file(path))
+        .map(|path| {
+            let modified = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .unwrap_or(UNIX_EPOCH);
+            (modified, path)
+        })
+        .collect();
+    sessions.sort();
+
+    Ok(sessions.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Whether the file starts with a session CSV header.
+pub fn is_session_file(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut header = String::new();
+    BufReader::new(file).read_line(&mut header).is_ok() && header.starts_with(CSV_HEADER_PREFIX)
+}
+
+/// Whether the session file already has every column of the current schema.
+pub fn is_current_schema(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut header = String::new();
+    BufReader::new(file).read_line(&mut header).is_ok() && header.trim_end() == CSV_HEADER
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::context::BrowserTab;
+    use crate::recorder::GapReason;
+
+    #[test]
+    fn split_csv_line_honours_quotes() {
+        assert_eq!(split_csv_line(r#"1,"a,b","say ""hi""",,"""#), ["1", "a,b", r#"say "hi""#, "", ""]);
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let context = SystemContext {
+            tab: Some(BrowserTab {
+                url: "https://example.com/?a=1,b=2".to_string(),
+                title: r#"The "quoted", title"#.to_string(),
+            }),
+            ..SystemContext::default()
+        };
+        let mut devices = DeviceInput::default();
+        devices.keyboards.push("USB Keyboard".to_string());
+        let record = ActivityRecord {
+            timestamp: 1_700_000_000,
+            mouse_x: -20,
+            mouse_y: 1080,
+            keys_pressed: vec![Keycode::LControl, Keycode::C],
+            mouse_buttons: vec![1, 4],
+            app: Some("Editor, \"Pro\"".to_string()),
+            marker: Some("step \"2\"".to_string()),
+            gap: Some(Gap { reason: GapReason::Lock, start: 1_699_999_000 }),
+            devices,
+            context,
+            path: vec![(1_699_999_999_300, -10, 1000), (1_699_999_999_600, -15, 1050)],
+        };
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, std::slice::from_ref(&record)).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        let parsed = parse_record(lines.next().unwrap()).unwrap();
+        assert_eq!(lines.next(), None);
+
+        assert_eq!(parsed.timestamp, record.timestamp);
+        assert_eq!((parsed.mouse_x, parsed.mouse_y), (record.mouse_x, record.mouse_y));
+        assert_eq!(parsed.keys_pressed, record.keys_pressed);
+        assert_eq!(parsed.mouse_buttons, record.mouse_buttons);
+        assert_eq!(parsed.app, record.app);
+        assert_eq!(parsed.marker, record.marker);
+        let gap = parsed.gap.unwrap();
+        assert!(gap.reason == GapReason::Lock && gap.start == 1_699_999_000);
+        assert_eq!(parsed.devices.keyboards, ["USB Keyboard"]);
+        let tab = parsed.context.tab.unwrap();
+        assert_eq!(tab.url, "https://example.com/?a=1,b=2");
+        assert_eq!(tab.title, r#"The "quoted", title"#);
+        assert_eq!(parsed.path, record.path);
+    }
+
+    #[test]
+    fn parse_record_accepts_old_rows() {
+        let parsed = parse_record(r#"5,10,20,"A+B""#).unwrap();
+        assert_eq!(parsed.keys_pressed, [Keycode::A, Keycode::B]);
+        assert!(parsed.marker.is_none() && parsed.gap.is_none() && parsed.app.is_none());
+        assert!(parsed.context.is_empty() && parsed.devices.is_empty() && parsed.path.is_empty());
+    }
+
+    #[test]
+    fn parse_record_rejects_bad_fields() {
+        assert!(parse_record("5,10").is_err());
+        assert!(parse_record(r#"5,x,20,"""#).is_err());
+        assert!(parse_record(r#"5,10,20,"NoSuchKey""#).is_err());
+    }
+
+    #[test]
+    fn validate_task_name_limits() {
+        assert!(validate_task_name("Write report").is_ok());
+        assert!(validate_task_name("  ").is_err());
+        assert!(validate_task_name("a\tb").is_err());
+        assert!(validate_task_name(&"x".repeat(MAX_TASK_CHARS + 1)).is_err());
+    }
+
+    #[test]
+    fn sanitize_makes_portable_names() {
+        assert_eq!(sanitize("Fix: parser / lexer"), "Fix_parser_lexer");
+        assert_eq!(sanitize("..."), "task");
+        assert_eq!(sanitize("con.txt"), "_con.txt");
+    }
+}
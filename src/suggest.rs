@@ -0,0 +1,54 @@
+//! Task names suggested from the windows in focus, for sessions started
+//! without one.
+
+use std::collections::HashMap;
+
+use crate::storage::MAX_TASK_CHARS;
+
+/// Separators editors and browsers put between the parts of a window title,
+/// e.g. "main.rs — activity-tracker — Visual Studio Code".
+const TITLE_SEPARATORS: &[&str] = &[" — ", " – ", " - ", " | ", " · "];
+
+/// Suggests a name such as "Working in Code — activity-tracker" from how
+/// many samples each `(app, title)` pair had focus.
+pub fn task_name(focus: &HashMap<(String, String), u32>) -> Option<String> {
+    let mut per_app: HashMap<&str, u32> = HashMap::new();
+    for ((app, _), count) in focus {
+        *per_app.entry(app).or_default() += count;
+    }
+    let app = per_app
+        .into_iter()
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+        .map(|(app, _)| app)?;
+    let title = focus
+        .iter()
+        .filter(|((a, _), _)| a == app)
+        .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0 .1.cmp(&a.0 .1)))
+        .map(|((_, title), _)| title.as_str())
+        .unwrap_or_default();
+
+    let name = match topic(app, title) {
+        Some(topic) => format!("Working in {} — {}", app, topic),
+        None => format!("Working in {}", app),
+    };
+    Some(name.chars().take(MAX_TASK_CHARS).collect())
+}
+
+/// The part of a window title naming what is being worked on: the last
+/// part that is not the app's own name, which editors put after the file
+/// and browsers after the page.
+fn topic<'a>(app: &str, title: &'a str) -> Option<&'a str> {
+    let mut parts = vec![title];
+    for separator in TITLE_SEPARATORS {
+        parts = parts.iter().flat_map(|part| part.split(separator)).collect();
+    }
+    let app = app.to_lowercase();
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|part| !part.is_empty() && part.chars().all(|c| !c.is_control()))
+        .rfind(|part| {
+            let part = part.to_lowercase();
+            !part.contains(&app) && !app.contains(&part)
+        })
+}
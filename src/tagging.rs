@@ -0,0 +1,73 @@
+//! Tags attached automatically when a session ends, from rules on where its
+//! time went. A rule such as
+//!
+//! ```json
+//! { "tag": "coding", "categories": ["Development", "Terminal"], "min_share": 0.5 }
+//! ```
+//!
+//! tags every session that spent at least half of its time in editors and
+//! terminals. Apps are looked up in the `categories` rules, then the
+//! built-in ones, as in the `categories` report.
+
+use serde::Deserialize;
+
+use crate::analysis;
+use crate::categories::{self, CategoryRule};
+use crate::config::Pattern;
+use crate::recorder::ActivityRecord;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TagRule {
+    pub tag: String,
+    /// Categories whose time counts towards the rule.
+    #[serde(default)]
+    pub categories: Vec<String>,
+    /// Apps whose time counts towards the rule, whatever their category.
+    #[serde(default)]
+    pub apps: Vec<Pattern>,
+    /// Least share of the session's app time, from 0 to 1, that has to be
+    /// spent in the apps and categories above.
+    #[serde(default = "default_min_share")]
+    pub min_share: f64,
+}
+
+fn default_min_share() -> f64 {
+    0.5
+}
+
+/// Tags from every rule the session satisfies, in rule order. Sessions
+/// recorded without `record_app` get none.
+pub fn tags(rules: &[TagRule], categories: &[CategoryRule], records: &[ActivityRecord]) -> Vec<String> {
+    if rules.is_empty() {
+        return Vec::new();
+    }
+    let defaults = categories::default_rules();
+    let app_time: Vec<(String, Option<&str>, u64)> = analysis::app_time(records)
+        .into_iter()
+        .map(|(app, secs)| {
+            let category = categories::categorize(categories.iter().chain(&defaults), &app).map(|rule| rule.category.as_str());
+            (app, category, secs)
+        })
+        .collect();
+    let total: u64 = app_time.iter().map(|(_, _, secs)| secs).sum();
+    if total == 0 {
+        return Vec::new();
+    }
+
+    let mut tags: Vec<String> = Vec::new();
+    for rule in rules {
+        let matched: u64 = app_time
+            .iter()
+            .filter(|(app, category, _)| {
+                rule.apps.iter().any(|pattern| pattern.is_match(app))
+                    || category.is_some_and(|c| rule.categories.iter().any(|wanted| wanted.eq_ignore_ascii_case(c)))
+            })
+            .map(|(_, _, secs)| secs)
+            .sum();
+        if matched as f64 / total as f64 >= rule.min_share && !tags.contains(&rule.tag) {
+            tags.push(rule.tag.clone());
+        }
+    }
+    tags
+}
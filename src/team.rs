@@ -0,0 +1,129 @@
+//! Combines sessions exported by several people, e.g. from a shared folder
+//! with one subfolder per team member:
+//!
+//! ```text
+//! shared/alice/review_1700000000.csv
+//! shared/bob/review_1700000450.csv
+//! ```
+//!
+//! Machines' clocks disagree, so totals are built from each session's own
+//! duration rather than by comparing timestamps across machines, and
+//! duplicates (the same file synced twice, or re-exported) are matched with
+//! a tolerance instead of exact start times.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::analysis::{self, Summary};
+use crate::storage::{self, SessionMeta};
+
+pub struct TeamSession {
+    pub member: String,
+    pub path: PathBuf,
+    pub meta: SessionMeta,
+    pub summary: Summary,
+}
+
+/// Loads every session file under the roots. The first folder below a root
+/// names the member; files directly in a root belong to the root itself.
+pub fn collect(roots: &[PathBuf]) -> (Vec<TeamSession>, Vec<String>) {
+    let mut sessions = Vec::new();
+    let mut errors = Vec::new();
+
+    for root in roots {
+        let mut files = Vec::new();
+        if let Err(e) = walk(root, &mut files) {
+            errors.push(format!("Failed to read {}: {}", root.display(), e));
+        }
+        // Directory order is arbitrary; keep which copy wins stable
+        files.sort();
+
+        for path in files {
+            let member = member_name(root, &path);
+            let loaded = storage::load_csv(&path)
+                .and_then(|records| Ok((storage::load_meta(&path)?, analysis::summarize(&records))));
+            match loaded {
+                Ok((meta, summary)) => sessions.push(TeamSession { member, path, meta, summary }),
+                Err(e) => errors.push(e),
+            }
+        }
+    }
+
+    (sessions, errors)
+}
+
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            walk(&path, files)?;
+        } else if path.extension().is_some_and(|ext| ext == "csv") && storage::is_session_file(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn member_name(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(member), Some(_)) => member.as_os_str().to_string_lossy().into_owned(),
+        _ => root
+            .file_name()
+            .map_or_else(|| root.display().to_string(), |name| name.to_string_lossy().into_owned()),
+    }
+}
+
+/// Drops sessions that are copies of an earlier one: same task, same sample
+/// and key press counts, and starts no more than `skew_secs` apart. Returns
+/// the kept sessions and `(duplicate, original)` path pairs.
+pub fn dedupe(sessions: Vec<TeamSession>, skew_secs: u64) -> (Vec<TeamSession>, Vec<(PathBuf, PathBuf)>) {
+    let mut kept: Vec<TeamSession> = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for session in sessions {
+        let original = kept.iter().find(|k| {
+            k.meta.task == session.meta.task
+                && k.summary.samples == session.summary.samples
+                && k.summary.key_presses == session.summary.key_presses
+                && k.summary.start.abs_diff(session.summary.start) <= skew_secs
+        });
+        match original {
+            Some(original) => duplicates.push((session.path, original.path.clone())),
+            None => kept.push(session),
+        }
+    }
+
+    (kept, duplicates)
+}
+
+pub struct ProjectReport {
+    pub project: String,
+    pub sessions: usize,
+    pub tracked_secs: u64,
+    /// Tracked time per member.
+    pub members: BTreeMap<String, u64>,
+}
+
+pub fn report(sessions: &[TeamSession]) -> Vec<ProjectReport> {
+    let mut projects: BTreeMap<String, ProjectReport> = BTreeMap::new();
+
+    for session in sessions {
+        let project = session.meta.project.clone().unwrap_or_else(|| "(none)".to_string());
+        let report = projects.entry(project.clone()).or_insert_with(|| ProjectReport {
+            project,
+            sessions: 0,
+            tracked_secs: 0,
+            members: BTreeMap::new(),
+        });
+
+        let tracked = session.summary.tracked_secs();
+        report.sessions += 1;
+        report.tracked_secs += tracked;
+        *report.members.entry(session.member.clone()).or_default() += tracked;
+    }
+
+    projects.into_values().collect()
+}
@@ -0,0 +1,155 @@
+//! Session timelines as SVG: activity per bucket, idle stretches, gaps and
+//! markers along a time axis.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use crate::analysis::{self, Summary};
+use crate::cli::format_duration;
+use crate::recorder::{ActivityRecord, SAMPLE_INTERVAL};
+
+const WIDTH: f64 = 1000.0;
+const MARGIN: f64 = 40.0;
+const TOP: f64 = 30.0;
+const PLOT_HEIGHT: f64 = 120.0;
+/// Aim for about this many labelled ticks on the time axis.
+const TICKS: u64 = 10;
+
+pub fn write_svg(records: &[ActivityRecord], bucket_secs: u64, path: &Path) -> Result<(), String> {
+    if records.is_empty() {
+        return Err("Session has no samples".to_string());
+    }
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    svg(records, bucket_secs.max(1), &mut BufWriter::new(file))
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+fn svg(records: &[ActivityRecord], bucket_secs: u64, out: &mut impl Write) -> std::io::Result<()> {
+    let summary = analysis::summarize(records);
+    let series = analysis::activity_series(records, bucket_secs);
+    let duration = summary.duration_secs().max(1) as f64;
+    let x = |timestamp: u64| MARGIN + timestamp.saturating_sub(summary.start) as f64 / duration * (WIDTH - 2.0 * MARGIN);
+    let bottom = TOP + PLOT_HEIGHT;
+    let height = bottom + 30.0;
+
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="11">"#,
+        WIDTH, height
+    )?;
+    writeln!(out, r##"<rect width="100%" height="100%" fill="#ffffff"/>"##)?;
+
+    // Idle stretches first, so activity and gaps are drawn over them
+    for (start, end) in idle_spans(&series, &summary, bucket_secs) {
+        writeln!(
+            out,
+            r##"<rect x="{:.1}" y="{}" width="{:.1}" height="{}" fill="#e6e6e6"><title>idle {}</title></rect>"##,
+            x(start),
+            TOP,
+            x(end) - x(start),
+            PLOT_HEIGHT,
+            format_duration(end - start)
+        )?;
+    }
+
+    let full = (bucket_secs as f64 / SAMPLE_INTERVAL.as_secs_f64()).max(1.0);
+    for (i, &count) in series.iter().enumerate() {
+        let start = summary.start + i as u64 * bucket_secs;
+        let end = (start + bucket_secs).min(summary.end.max(start + 1));
+        let bar = (count as f64 / full).min(1.0) * PLOT_HEIGHT;
+        if bar > 0.0 {
+            writeln!(
+                out,
+                r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#3b7dd8"/>"##,
+                x(start),
+                bottom - bar,
+                x(end) - x(start),
+                bar
+            )?;
+        }
+    }
+
+    for (start, end, reason) in &summary.gaps {
+        writeln!(
+            out,
+            r##"<rect x="{:.1}" y="{}" width="{:.1}" height="{}" fill="#f0a040" fill-opacity="0.5"><title>{} {}</title></rect>"##,
+            x(*start),
+            TOP,
+            x(*end) - x(*start),
+            PLOT_HEIGHT,
+            reason.as_str(),
+            format_duration(end - start)
+        )?;
+    }
+
+    for (timestamp, label) in &summary.markers {
+        let mx = x(*timestamp);
+        writeln!(
+            out,
+            r##"<line x1="{0:.1}" y1="{1}" x2="{0:.1}" y2="{2}" stroke="#c03030"/><text x="{0:.1}" y="{3}" fill="#c03030">{4}</text>"##,
+            mx,
+            TOP - 12.0,
+            bottom,
+            TOP - 16.0,
+            escape(label)
+        )?;
+    }
+
+    writeln!(
+        out,
+        r##"<line x1="{}" y1="{2}" x2="{}" y2="{2}" stroke="#333333"/>"##,
+        MARGIN,
+        WIDTH - MARGIN,
+        bottom
+    )?;
+    let step = tick_step(summary.duration_secs());
+    for offset in (0..=summary.duration_secs()).step_by(step as usize) {
+        let tx = x(summary.start + offset);
+        writeln!(
+            out,
+            r##"<line x1="{0:.1}" y1="{1}" x2="{0:.1}" y2="{2}" stroke="#333333"/><text x="{0:.1}" y="{3}" text-anchor="middle">{4}</text>"##,
+            tx,
+            bottom,
+            bottom + 4.0,
+            bottom + 16.0,
+            format_duration(offset)
+        )?;
+    }
+
+    writeln!(out, "</svg>")
+}
+
+/// Runs of buckets without input, outside of gaps, as `(start, end)`.
+fn idle_spans(series: &[usize], summary: &Summary, bucket_secs: u64) -> Vec<(u64, u64)> {
+    let in_gap = |t: u64| summary.gaps.iter().any(|(start, end, _)| t >= *start && t < *end);
+    let mut spans: Vec<(u64, u64)> = Vec::new();
+    for (i, &count) in series.iter().enumerate() {
+        let start = summary.start + i as u64 * bucket_secs;
+        if count > 0 || in_gap(start) {
+            continue;
+        }
+        let end = (start + bucket_secs).min(summary.end);
+        match spans.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => spans.push((start, end)),
+        }
+    }
+    spans
+}
+
+/// A round tick interval giving roughly `TICKS` ticks.
+fn tick_step(duration: u64) -> u64 {
+    const STEPS: [u64; 10] = [60, 300, 600, 900, 1800, 3600, 7200, 10800, 21600, 43200];
+    STEPS
+        .into_iter()
+        .find(|step| duration / step <= TICKS)
+        .unwrap_or(86400)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,127 @@
+//! Activity as time series for dashboards. Rows are in long format, one per
+//! interval and project, with the interval's start both as RFC 3339 and as
+//! epoch milliseconds, which Grafana's CSV and JSON datasources read as time
+//! fields without any transformation.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use chrono::{DateTime, Local, TimeZone, Timelike};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::analysis;
+use crate::config::Pattern;
+use crate::recorder::{ActivityRecord, SAMPLE_INTERVAL};
+use crate::storage::SessionMeta;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Interval {
+    Hour,
+    Day,
+}
+
+#[derive(Default, Serialize)]
+pub struct Point {
+    pub time: String,
+    pub time_ms: i64,
+    pub project: String,
+    pub tracked_secs: f64,
+    pub active_secs: f64,
+    pub key_presses: usize,
+    pub clicks: usize,
+    pub mouse_distance: f64,
+    /// Time in apps listed under `distraction_apps`.
+    pub distraction_secs: f64,
+}
+
+/// Accumulates sessions into per-interval points, splitting sessions that
+/// cross an interval boundary.
+pub struct TimeSeries {
+    interval: Interval,
+    distraction_apps: Vec<Pattern>,
+    points: BTreeMap<(i64, String), Point>,
+}
+
+impl TimeSeries {
+    pub fn new(interval: Interval, distraction_apps: Vec<Pattern>) -> Self {
+        TimeSeries { interval, distraction_apps, points: BTreeMap::new() }
+    }
+
+    pub fn add_session(&mut self, meta: &SessionMeta, records: &[ActivityRecord]) {
+        let project = meta.project.clone().unwrap_or_default();
+        let sample_secs = SAMPLE_INTERVAL.as_secs_f64();
+
+        let mut previous: Option<&ActivityRecord> = None;
+        for record in records {
+            let start = self.interval_start(record.timestamp);
+            let point = self.points.entry((start.timestamp(), project.clone())).or_insert_with(|| Point {
+                time: start.to_rfc3339(),
+                time_ms: start.timestamp_millis(),
+                project: project.clone(),
+                ..Point::default()
+            });
+
+            point.tracked_secs += sample_secs;
+            if record.app.as_deref().is_some_and(|app| self.distraction_apps.iter().any(|p| p.is_match(app))) {
+                point.distraction_secs += sample_secs;
+            }
+            point.key_presses += analysis::new_key_presses(previous, record);
+            point.clicks += analysis::new_clicks(previous, record);
+            let moved = previous.is_some_and(|p| {
+                let dx = (record.mouse_x - p.mouse_x) as f64;
+                let dy = (record.mouse_y - p.mouse_y) as f64;
+                point.mouse_distance += dx.hypot(dy);
+                dx != 0.0 || dy != 0.0
+            });
+            if moved || !record.keys_pressed.is_empty() || !record.mouse_buttons.is_empty() {
+                point.active_secs += sample_secs;
+            }
+            previous = Some(record);
+        }
+    }
+
+    fn interval_start(&self, timestamp: u64) -> DateTime<Local> {
+        let time = DateTime::from_timestamp(timestamp as i64, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local);
+        let start = match self.interval {
+            Interval::Hour => time.date_naive().and_hms_opt(time.hour(), 0, 0),
+            Interval::Day => time.date_naive().and_hms_opt(0, 0, 0),
+        };
+        start
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .unwrap_or(time)
+    }
+
+    /// The points in time order.
+    pub fn points(&self) -> impl Iterator<Item = &Point> {
+        self.points.values()
+    }
+
+    pub fn write_csv(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "time,time_ms,project,tracked_secs,active_secs,key_presses,clicks,mouse_distance,distraction_secs")?;
+        for p in self.points() {
+            writeln!(
+                out,
+                "{},{},\"{}\",{:.1},{:.1},{},{},{:.0},{:.1}",
+                p.time,
+                p.time_ms,
+                p.project.replace('"', "\"\""),
+                p.tracked_secs,
+                p.active_secs,
+                p.key_presses,
+                p.clicks,
+                p.mouse_distance,
+                p.distraction_secs
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let points: Vec<&Point> = self.points().collect();
+        serde_json::to_writer_pretty(&mut *out, &points)?;
+        writeln!(out)
+    }
+}
@@ -0,0 +1,18 @@
+use active_win_pos_rs::ActiveWindow;
+
+use crate::config::Pattern;
+
+/// The window that currently has focus, if the platform will tell us.
+pub fn foreground() -> Option<ActiveWindow> {
+    active_win_pos_rs::get_active_window().ok()
+}
+
+/// Matches against the application name and the executable's file name, so
+/// both `KeePassXC` and `keepassxc` patterns work.
+pub fn app_matches(pattern: &Pattern, window: &ActiveWindow) -> bool {
+    pattern.is_match(&window.app_name)
+        || window
+            .process_path
+            .file_stem()
+            .is_some_and(|name| pattern.is_match(&name.to_string_lossy()))
+}